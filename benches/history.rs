@@ -0,0 +1,79 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use rstype::history::{
+    get_history_records_from_file, history_totals_from_file, select_history_records_from_file, NumberOfRecords, TextFilter,
+    HISTORY_HEADER,
+};
+
+/// Rows of the generated history
+const ROWS: usize = 100_000;
+
+/// Allocator keeping the most memory held at once, to compare the peaks of both ways of reading
+struct PeakAllocator;
+
+static HELD: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let held = HELD.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK.fetch_max(held, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        HELD.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Time and peak memory of reading the history, the peak above what was held before
+/// # Arguments:
+/// * `read` - Reads the history and returns the number of records it got
+fn measure(label: &str, read: impl Fn() -> usize) {
+    let held_before = HELD.load(Ordering::Relaxed);
+    PEAK.store(held_before, Ordering::Relaxed);
+    let start = Instant::now();
+    let records = read();
+    let elapsed = start.elapsed();
+    let peak = PEAK.load(Ordering::Relaxed) - held_before;
+    println!("{:<32} {:>8.1} ms {:>10.1} KiB  {} records", label, elapsed.as_secs_f64() * 1000.0, peak as f64 / 1024.0, records);
+}
+
+fn main() {
+    let history_path = std::env::temp_dir().join(format!("rstype-bench-{}.csv", std::process::id()));
+    let mut content = HISTORY_HEADER.join(",") + "\n";
+    for row in 0..ROWS {
+        content.push_str(&format!(
+            "{},{:.2},2024-01-01,10:00:00,97.50,300.00,90.00,,0,no,no,60.00,300,2,3,db,1,3f2a9c1e\n",
+            row % 500 + 1, 40.0 + (row % 60) as f64,
+        ));
+    }
+    std::fs::write(&history_path, content).unwrap();
+
+    println!("History of {} records", ROWS);
+    // How the last records were read before, every record kept and the tail taken
+    measure("every record, then the last 10", || {
+        let records = get_history_records_from_file(&history_path, NumberOfRecords::All, &TextFilter::All).unwrap();
+        records[records.len() - 10..].len()
+    });
+    // What --history 10 reads, with and without a filter
+    measure("--history 10", || {
+        select_history_records_from_file(&history_path, NumberOfRecords::Last(10), &TextFilter::All, false).unwrap().len()
+    });
+    measure("--history 10 --text-id 42", || {
+        let filter = TextFilter::Exact("42".to_string());
+        select_history_records_from_file(&history_path, NumberOfRecords::Last(10), &filter, false).unwrap().len()
+    });
+    // What --stats adds, the totals of every record
+    measure("--stats totals", || {
+        let (summary, _, _) = history_totals_from_file(&history_path, &TextFilter::All, true).unwrap();
+        summary.tests
+    });
+
+    std::fs::remove_file(&history_path).unwrap();
+}
@@ -1,22 +1,42 @@
 use crate::calculations::{
-    accuracy, first_index_at_which_strings_differ,
-    get_space_count_after_ith_word, number_of_lines_to_fit_text_in_window,
-    speed_in_wpm, word_wrap
+    char_positions, is_pasted_input, line_ranges, looks_like_caps_lock, number_of_lines_to_fit_text_in_window,
+    pace_position, problem_bigrams, progress_bar, replay_delays, rolling_wpm, slice_by_char_indices, slowest_words,
+    sparkline, viewport_start, word_wpm, word_wrap, words_per_minute, wrap_lines, wrapped_char_index, WpmFormula,
+    PASTE_MAX_INTERVAL, PASTE_MIN_BURST, REPLAY_MAX_DELAY,
 };
-use crate::database::load_text_from_database;
+use crate::card::result_card;
+use crate::challenge::{comparison_lines, save_challenge, Challenge, ChallengeResult};
+use crate::clipboard::{Clipboard, SystemClipboard};
+use crate::database::{difficulty_of_text_id, load_text_from_database, load_text_from_database_based_on_difficulty};
 use crate::keycheck::{
-    get_key_mapping, is_backspace, is_ctrl_backspace, is_ctrl_c, is_ctrl_t, is_enter, is_escape,
-    is_resize, is_tab, is_valid_initial_key,
+    get_key_name, is_c, is_ctrl_c, is_ctrl_t, is_ctrl_y, is_enter, is_escape, is_h, is_resize, is_s,
+    is_tab, is_valid_initial_key,
 };
-use crate::{exit, history, timer, AppError, AppResult, PreparedText};
-use pancurses::{ColorPair, Input};
+use crate::heatmap::{heatmap_rows, key_errors, HEATMAP_KEY_WIDTH, HEATMAP_WIDTH};
+use crate::layout::{remap, Layout};
+use crate::replay::SavedReplay;
+use crate::session::{SessionRules, SessionStats, TextSource, TypingSession};
+use crate::share::{
+    default_share_targets, fill_template, Browser, ShareTarget, SystemBrowser, DEFAULT_COPY_TEMPLATE,
+    DEFAULT_SHARE_TEMPLATE,
+};
+use crate::terminal::TerminalUi;
+use crate::text_transform::TextTransforms;
+use crate::theme::{color_attributes, ColorMode, Theme};
+use crate::webhook::{post_stats, HttpTransport, WebhookTransport};
+use crate::lessons::{is_lesson_passed, LessonGoal, LESSON_MIN_ACCURACY};
+use crate::{cast, history, output, replay, suspend, timer, AppError, AppResult, PreparedText};
+use pancurses::{chtype, Input};
+use rand::Rng;
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
-use std::ops::Add;
-use std::time;
-use std::time::{Duration, SystemTime};
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use unicode_width::UnicodeWidthStr;
 
 
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Color {
     Green,
     Red,
@@ -27,204 +47,676 @@ pub enum Color {
     Black,
 }
 
-impl Color {
-    fn not_found_err(self) -> AppError {
-        AppError::ColorNotFoundError(self)
+/// Why `App::run` returned
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExitReason {
+    // Left from the end screen of a test, with the results saved to history
+    Completed(Box<SessionStats>),
+    // Left with Esc before the test started or with Ctrl+C, with the stats of the text typed so far
+    Aborted(Box<SessionStats>),
+}
+
+impl ExitReason {
+    /// Stats of the session the app was left in
+    pub fn stats(&self) -> &SessionStats {
+        match self {
+            ExitReason::Completed(stats) | ExitReason::Aborted(stats) => stats,
+        }
     }
 }
 
+/// Where endless mode takes the words of the next page from
+#[derive(Clone, Debug, PartialEq)]
+pub enum EndlessSource {
+    // Random texts of this difficulty from the database
+    Database(u32),
+    // Words sampled again from the original text
+    Words(Vec<String>),
+}
+
+/// Settings chosen on the command line that change how a test behaves
+#[derive(Clone, Default)]
+pub struct AppOptions {
+    // Speed figure saved to history
+    pub wpm_formula: WpmFormula,
+    // Show speed over the last few seconds instead of the whole test while typing
+    pub rolling_wpm: bool,
+    // Target speed for the pace caret, no caret when unset
+    pub pace_wpm: Option<f64>,
+    // Race against the best saved run of the same text
+    pub ghost: bool,
+    // End the test after this long even if the text isn't finished
+    pub time_limit: Option<Duration>,
+    // Keep showing new text until Esc is pressed
+    pub endless: Option<EndlessSource>,
+    // Texts typed right after the first one, as a single test
+    pub marathon: Vec<PreparedText>,
+    // Changes made to the texts loaded during the test, like they were made to the first one
+    pub transforms: TextTransforms,
+    // Mistyped characters are counted but not typed
+    pub strict: bool,
+    // Space moves on from a wrong word instead of waiting for it to be fixed
+    pub skip_wrong_words: bool,
+    // Backspace at the start of a word goes back into the previous one
+    pub backtrack: bool,
+    // Typed text, mistakes and live speed are only shown once the test ends
+    pub blind: bool,
+    // Nothing but the text is shown until the test ends
+    pub zen: bool,
+    // Characters of words skipped with Ctrl+N count as mistakes instead of being left out
+    pub count_skipped_as_errors: bool,
+    // Difficulty picked for the text, shown in the header when set
+    pub difficulty: Option<u32>,
+    // Where the first text came from, texts loaded later come from the same place
+    pub text_source: TextSource,
+    // Layout emulated on a QWERTY keyboard, shown in the header when set
+    pub layout: Option<Layout>,
+    // Lesson being taken, completed when the test reaches its goal
+    pub lesson: Option<LessonGoal>,
+    // Don't flag bursts of keys too fast for a human, steno sends whole words at once
+    pub allow_fast_input: bool,
+    // Speed to beat, the end screen shows whether the test reached it
+    pub goal_wpm: Option<f64>,
+    // Colors of the interface
+    pub theme: Theme,
+    // Full colors, colors and attributes that don't rely on red and green, or attributes only
+    pub color_mode: ColorMode,
+    // File the results are appended to, besides the history
+    pub output: Option<PathBuf>,
+    // History file the results are saved to, the word stats, ghosts and lessons are kept next to it
+    pub history_file: PathBuf,
+    // Results aren't saved to the history files, lesson progress still is
+    pub no_history: bool,
+    // URL the results are posted to as JSON at the end of a test
+    pub webhook_url: Option<String>,
+    // CSV file every key of the last finished test is written to
+    pub dump_keystrokes: Option<PathBuf>,
+    // asciinema file the replay of the last finished test is written to
+    pub export_cast: Option<PathBuf>,
+    // Challenge file the text and result of the last finished test are written to
+    pub export_challenge: Option<PathBuf>,
+    // Seed the text was picked with, kept in challenge files
+    pub seed: Option<u64>,
+    // Challenge being taken and its file, the result is compared to the challenger's and added to the file
+    pub challenge: Option<(PathBuf, Challenge)>,
+    // Templates of the shared result and of the text copied to the clipboard, the defaults of `share` when unset
+    pub share_template: Option<String>,
+    pub copy_template: Option<String>,
+    // Where Ctrl+T shares the result, a picker is shown when there are several
+    pub share_targets: Vec<ShareTarget>,
+}
+
+impl AppOptions {
+    /// Options that change what the typed keys do
+    pub fn session_rules(&self) -> SessionRules {
+        SessionRules {
+            strict: self.strict,
+            skip_wrong_words: self.skip_wrong_words,
+            backtrack: self.backtrack,
+            count_skipped_as_errors: self.count_skipped_as_errors,
+            wpm_formula: self.wpm_formula,
+        }
+    }
+}
+
+/// Number of words on every page of endless mode sampled from a text
+const ENDLESS_PAGE_WORDS: usize = 30;
+
+/// Number of slowest words listed on the end screen
+const SLOWEST_WORDS_SHOWN: usize = 5;
+
+/// Number of most mistyped character pairs listed on the end screen
+const PROBLEM_BIGRAMS_SHOWN: usize = 5;
+
+/// How far back the rolling live speed looks
+const ROLLING_WPM_WINDOW: Duration = Duration::from_secs(10);
+
+/// Rows of a long text shown at the least, smaller windows can't be used
+const MIN_VISIBLE_TEXT_ROWS: i32 = 3;
+
+/// Rows of typed text kept in view above the row being typed when a long text scrolls
+const SCROLL_CONTEXT_ROWS: i32 = 1;
+
+/// How long getch waits for a key before the loop runs without one
+const INPUT_TIMEOUT: Duration = Duration::from_millis(100);
+
 pub struct App {
-    text: String,
     text_id: String,
 
-    tokens: Vec<String>,
-    text_backup: String,
-
-    // Current typed word and entire string
-    current_word: String,
-    current_string: String,
+    // Typed text and stats of the test, everything else here is about showing them
+    session: TypingSession,
+    // The session of the test while a replay types its keys again on a copy
+    recorded_session: Option<TypingSession>,
+    // Keys of the test of an app made for a replay, typed once the text is wrapped
+    replay_keys: Vec<(Duration, Input)>,
+    // Screen cell (row, column) of every character in the wrapped text
+    text_positions: Vec<(i32, i32)>,
+    // Character indices of every line of the wrapped text
+    text_lines: Vec<Range<usize>>,
 
     // First valid key press
     first_key_pressed: bool,
-    // Stores keypress, time tuple
-    key_strokes: Vec<(f64, Input)>,
-    mistyped_keys: Vec<usize>,
 
     // Time at which test started
-    start_time: SystemTime,
+    start_time: Instant,
     // Time at which test ended
-    end_time: SystemTime,
+    end_time: Instant,
 
-    // Keep track of the token index in text
-    token_index: usize,
     // mode = 0 when in test
     // mode = 1 when in replay
     mode: u8,
 
     window_height: i32,
     window_width: i32,
+    // Window size the last test was typed in, for the exported replay
+    test_window_size: (i32, i32),
 
     number_of_lines_to_print_text: i32,
-
-    // Restrict current word length to a limit
-    // Used to highlight one the limit is reached
-    // limit is set to the length of largest word in string + 5 for buffer
-    current_word_limit: usize,
+    // Rows of text shown at once and the first one shown, long texts scroll as they are typed
+    visible_text_rows: i32,
+    scroll_row: i32,
 
     test_complete: bool,
+    // Whether the test reached the goal of the lesson, None outside lessons
+    lesson_passed: Option<bool>,
+    // Best result in history the test beat, None when it beat none
+    personal_best: Option<history::PersonalBest>,
+    // Totals of the history once the results are saved to it
+    practice_total: Option<history::HistorySummary>,
+    // The results were left out of the history on purpose, replays leave it unset
+    not_recorded: bool,
+    // Tests finished on the text since it was loaded, the ones after the first are Tab retries
+    attempts: u32,
+    // Show the keyboard heatmap instead of the detailed stats on the end screen
+    show_heatmap: bool,
+    // The last letters were typed in the wrong case, Caps Lock is probably on
+    caps_lock_warning: bool,
+
+    // Every text of a marathon, starting with the first one, and the one on screen
+    marathon_texts: Vec<PreparedText>,
+    marathon_index: usize,
+
+    // Results of the finished test, all zero while typing
+    stats: SessionStats,
+
+    // A key was refused in strict mode since the screen was last drawn
+    key_rejected: bool,
+    // Sends the results to the webhook
+    webhook: Box<dyn WebhookTransport>,
+    // Takes the result copied with Ctrl+Y
+    clipboard: Box<dyn Clipboard>,
+    // Opens the URL sharing the result with Ctrl+T
+    browser: Box<dyn Browser>,
+    // URL the browser couldn't be opened with, shown on the end screen to copy by hand
+    share_url: Option<String>,
+    // Message shown in the status bar of the end screen instead of the stats, like a failed webhook
+    status_message: Option<(Color, String)>,
+    // The status bar asks where to share the result, the next key picks the target
+    picking_share_target: bool,
+    // Character index reached by the pace caret
+    pace_index: usize,
+    // Best saved run of this text, only loaded when racing it
+    ghost: Option<history::GhostRun>,
+    // Best speed in history of every text shown so far, the history is only read once per text
+    text_best_wpm: HashMap<String, Option<f64>>,
 
-    // Real-time speed, the value at the end of the test is the result
-    // And a few other stats
-    current_speed_wpm: f64,
-    accuracy: f64,
-    time_taken: f64,
+    // Color mapping
+    color: HashMap<Color, chtype>,
 
-    total_chars_typed: usize,
+    options: AppOptions,
 
-    // Color mapping
-    color: HashMap<Color, ColorPair>,
+    // Set when the app should stop, the main loop returns it, errors after showing them
+    exit: Option<AppResult<ExitReason>>,
 }
 
 impl App {
     pub fn from_prepared_text(prepared_text: PreparedText) -> Self {
-        let (text, text_id) = prepared_text;
-        let tokens: Vec<String> = text
-            .split_ascii_whitespace()
-            .map(|s| s.to_string())
-            .collect();
+        Self::with_options(prepared_text, AppOptions::default())
+    }
 
-        let text = tokens.join(" ");
-        let text_backup = text.clone();
-        let current_word_limit = tokens.iter()
-            .map(|s| s.len())
-            .max()
-            .unwrap_or(0) + 5;
+    pub fn with_options(prepared_text: PreparedText, mut options: AppOptions) -> Self {
+        let marathon_texts = if options.marathon.is_empty() {
+            vec![]
+        } else {
+            let mut texts = vec![prepared_text.clone()];
+            texts.append(&mut options.marathon);
+            texts
+        };
+        let (text, text_id) = prepared_text;
 
         Self {
-            text,
             text_id,
-            tokens,
-            text_backup,
-            current_word: "".to_string(),
-            current_string: "".to_string(),
+            session: TypingSession::with_rules(&text, options.session_rules()),
+            recorded_session: None,
+            replay_keys: vec![],
+            text_positions: vec![],
+            text_lines: vec![],
             first_key_pressed: false,
-            key_strokes: vec![],
-            mistyped_keys: vec![],
-            start_time: SystemTime::now(),
-            end_time: SystemTime::now(),
-            token_index: 0,
+            start_time: Instant::now(),
+            end_time: Instant::now(),
             mode: 0,
             window_height: 0,
             window_width: 0,
+            test_window_size: (0, 0),
             number_of_lines_to_print_text: 0,
-            current_word_limit,
+            visible_text_rows: 0,
+            scroll_row: 0,
             test_complete: false,
-            current_speed_wpm: 0.0,
-            accuracy: 0.0,
-            time_taken: 0.0,
-            total_chars_typed: 0,
+            lesson_passed: None,
+            personal_best: None,
+            practice_total: None,
+            not_recorded: false,
+            attempts: 0,
+            show_heatmap: false,
+            caps_lock_warning: false,
+            stats: SessionStats::default(),
+            key_rejected: false,
+            webhook: Box::new(HttpTransport),
+            clipboard: Box::new(SystemClipboard),
+            browser: Box::new(SystemBrowser),
+            share_url: None,
+            status_message: None,
+            picking_share_target: false,
+            pace_index: 0,
+            ghost: None,
+            text_best_wpm: HashMap::new(),
+            marathon_texts,
+            marathon_index: 0,
             color: HashMap::new(),
+            options,
+            exit: None,
+        }
+    }
+
+    /// App on the end screen of a finished test, to draw its replay with `draw_replay`
+    ///
+    /// Nothing is saved again, and the ghost and the pace caret aren't raced in the replay.
+    /// The keys are typed on the text once `initialize_windows` has wrapped it.
+    /// # Arguments:
+    /// * `prepared_text` - Text of the page the keys were typed on, with its id
+    /// * `options` - Options the test was typed with
+    /// * `stats` - Results of the test, shown on the end screen
+    /// * `key_strokes` - Keys of the page with the time since the start of the test
+    pub fn for_replay(
+        prepared_text: PreparedText,
+        options: AppOptions,
+        stats: SessionStats,
+        key_strokes: &[(Duration, Input)],
+    ) -> Self {
+        let options = AppOptions {
+            ghost: false,
+            pace_wpm: None,
+            endless: None,
+            marathon: vec![],
+            // The header of a replay doesn't show the best result of the text
+            no_history: true,
+            ..options
+        };
+        let mut app = Self::with_options(prepared_text, options);
+        app.stats = stats;
+        app.mode = 1;
+        app.test_complete = true;
+        app.replay_keys = key_strokes.to_vec();
+        app
+    }
+
+    /// App playing a replay saved with the S key of the end screen
+    ///
+    /// The stats are worked out again from the keys, `run` starts by playing the replay.
+    pub fn from_saved_replay(replay: &SavedReplay, options: AppOptions) -> Self {
+        let key_strokes = replay.key_strokes();
+        let mut session = TypingSession::with_rules(&replay.text, options.session_rules());
+        for (time, key) in &key_strokes {
+            session.record_key(key, *time);
+            session.process_key(key);
         }
+        let duration = key_strokes.last().map_or(Duration::ZERO, |(time, _)| *time);
+        let stats = session.finish(&replay.text_id, duration);
+
+        Self::for_replay((replay.text.clone(), replay.text_id.clone()), options, stats, &key_strokes)
     }
 
-    pub fn run(&mut self, win: &pancurses::Window) -> AppResult<()> {
+    /// Send the results to the webhook with another transport than HTTP
+    pub fn set_webhook_transport(&mut self, transport: Box<dyn WebhookTransport>) {
+        self.webhook = transport;
+    }
+
+    /// Copy the result to another clipboard than the one of the system
+    pub fn set_clipboard(&mut self, clipboard: Box<dyn Clipboard>) {
+        self.clipboard = clipboard;
+    }
+
+    /// Open the share URL with another browser than the one of the system
+    pub fn set_browser(&mut self, browser: Box<dyn Browser>) {
+        self.browser = browser;
+    }
+
+    /// Run typing tests in the window until the user leaves
+    ///
+    /// The curses session is left running, ending it is up to the caller.
+    /// # Returns:
+    /// * `ExitReason` - Why the app stopped, so the caller can pick an exit code
+    pub fn run(&mut self, win: &mut impl TerminalUi) -> AppResult<ExitReason> {
+        // getch keeps the timeout set up in initialize_windows,
+        // so the loop also runs while no key is pressed
         self.initialize_windows(win)?;
-        win.nodelay(false);
+        if let Some(exit) = self.exit.take() {
+            return exit.map_err(|e| self.error_screen(win, e));
+        }
         win.keypad(true);
+        // Apps made for a saved replay start by playing it
+        if self.mode == 1 {
+            self.replay(win)?;
+            if let Some(exit) = self.exit.take() {
+                return exit.map_err(|e| self.error_screen(win, e));
+            }
+        }
 
+        let mut last_input = Instant::now();
         loop {
             let key = win.getch();
+            if suspend::take_resumed() {
+                // getch waits up to the timeout on its own, the rest of the gap was spent suspended
+                self.exclude_suspended_time(last_input.elapsed().saturating_sub(INPUT_TIMEOUT));
+                win.clearok(true);
+                win.refresh();
+            }
+            last_input = Instant::now();
+
+            if let Some(exit_reason) = self.handle_input(win, key)? {
+                return Ok(exit_reason);
+            }
+        }
+    }
 
-            if let Some(key) = key {
-                if !self.first_key_pressed {
-                    match key {
-                        Input::Character('\u{1b}') => {
-                            exit(0)
-                        }
-                        Input::KeyLeft => self.switch_text(win, -1)?,
-                        Input::KeyRight => self.switch_text(win, 1)?,
-                        _ => {}
+    /// Respond to one key, or to the timeout of getch when `key` is None
+    ///
+    /// `run` calls this for every getch, calling it directly drives the app
+    /// without waiting for keys, like in tests with a `FakeTerminal`.
+    /// # Returns:
+    /// * `Option<ExitReason>` - Why the app stopped, None while it keeps going
+    pub fn handle_input(&mut self, win: &mut impl TerminalUi, key: Option<Input>) -> AppResult<Option<ExitReason>> {
+        if let Some(key) = key {
+            // The key picks where to share the result, the window can still be resized meanwhile
+            if self.picking_share_target && !is_resize(&key) {
+                self.pick_share_target(&key)?;
+                self.print_stats(win)?;
+                win.refresh();
+                return Ok(None);
+            }
+
+            if !self.first_key_pressed {
+                match key {
+                    Input::Character('\u{1b}') => {
+                        return Ok(Some(self.leave()));
                     }
+                    Input::KeyLeft => self.switch_text(win, -1)?,
+                    Input::KeyRight => self.switch_text(win, 1)?,
+                    _ => {}
                 }
+            }
 
-                // Test mode
-                if self.mode == 0 {
-                    self.typing_mode(win, &key)?;
-                } else {
-                    // Again mode
-                    // Tab to retry last test
-                    if is_tab(&key) {
-                        win.clear();
-                        self.reset_test();
-                        self.setup_print(win)?;
-                        self.update_state(win)?;
+            // Test mode
+            if self.mode == 0 {
+                self.typing_mode(win, &key)?;
+            } else {
+                // Again mode
+                // Any other action puts the detailed stats back in place of the share URL
+                if self.share_url.is_some() && !is_resize(&key) {
+                    self.share_url = None;
+                    self.status_message = None;
+                    for line in self.number_of_lines_to_print_text + 5..self.window_height - 1 {
+                        self.clear_line(win, line);
                     }
+                    self.print_result_details(win)?;
+                    self.print_stats(win)?;
+                }
 
-                    // Replay
-                    if is_enter(&key) {
-                        self.replay(win)?;
-                    }
+                // Tab to retry last test
+                if is_tab(&key) {
+                    win.clear();
+                    self.reset_test(win);
+                    self.setup_print(win)?;
+                    self.update_state(win)?;
+                }
 
-                    // Tweet result
-                    if is_ctrl_t(&key) {
-                        self.share_result()?;
-                    }
+                // Replay
+                if is_enter(&key) {
+                    self.replay(win)?;
+                }
+
+                // Share result
+                if is_ctrl_t(&key) {
+                    self.share_result()?;
+                    self.print_result_details(win)?;
+                    self.print_stats(win)?;
+                }
+
+                // Copy result
+                if is_ctrl_y(&key) {
+                    self.copy_result();
+                    self.print_stats(win)?;
+                }
+
+                if is_resize(&key) {
+                    self.resize_end_screen(win)?;
+                }
+
+                // Save the replay to a file next to the working directory
+                if is_s(&key) {
+                    self.save_replay();
+                    self.print_stats(win)?;
+                }
+
+                // Save the result card next to the replays and copy it
+                if is_c(&key) {
+                    self.save_card();
+                    self.print_stats(win)?;
+                }
+
+                // Switch between the detailed stats and the keyboard heatmap
+                if is_h(&key) {
+                    self.show_heatmap = !self.show_heatmap;
+                    self.print_result_details(win)?;
                 }
             }
+        } else if self.first_key_pressed && self.mode == 0 {
+            // No key before the timeout, keep the live stats moving while idle
+            self.update_live_stats(win)?;
+        } else {
+            return Ok(None);
+        }
 
-            win.refresh();
+        // Timed test, score whatever was typed once the time is up
+        if self.mode == 0 && self.first_key_pressed && self.is_time_up(self.elapsed()) {
+            self.test_end(win)?;
+        }
+
+        if let Some(exit) = self.exit.take() {
+            return exit.map(Some).map_err(|e| self.error_screen(win, e));
+        }
+
+        win.refresh();
+        Ok(None)
+    }
+
+    /// Time since the first key of the test, without the time the process was suspended
+    pub fn elapsed(&self) -> Duration {
+        self.start_time.elapsed()
+    }
+
+    /// Leave time the process spent suspended out of the running test
+    ///
+    /// Before the first key the clock is started over anyway, on the end screen the test is already timed.
+    pub fn exclude_suspended_time(&mut self, suspended: Duration) {
+        if self.mode == 0 {
+            self.start_time += suspended;
+        }
+    }
+
+    /// Reason for leaving the app right now, completed when the end screen of a test is shown
+    fn leave(&mut self) -> ExitReason {
+        if self.mode == 1 {
+            ExitReason::Completed(Box::new(self.stats.clone()))
+        } else if self.first_key_pressed {
+            let stats = self.session.finish(&self.history_id(), self.test_duration(self.elapsed()));
+            ExitReason::Aborted(Box::new(stats))
+        } else {
+            ExitReason::Aborted(Box::new(SessionStats {
+                text_id: self.history_id(),
+                timestamp: chrono::Local::now(),
+                ..SessionStats::default()
+            }))
+        }
+    }
+
+    /// Redraw the live stats in the header without moving the cursor
+    fn update_live_stats(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let (cursor_y, cursor_x) = win.get_cur_yx();
+        self.print_text_markers(win)?;
+        self.print_header_stats(win)?;
+        win.mv(cursor_y, cursor_x);
+        Ok(())
+    }
+
+    /// Mark the positions of the ghost and the pace caret in the text
+    ///
+    /// The previous markers are cleared by redrawing the dimmed and untyped
+    /// parts of the text, the red region is left alone. The cursor is not moved.
+    fn print_text_markers(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if !self.first_key_pressed || self.mode != 0 {
+            return Ok(());
+        }
+        if self.options.pace_wpm.is_none() && self.ghost.is_none() {
+            return Ok(());
+        }
+        let (cursor_y, cursor_x) = win.get_cur_yx();
+        let typed_length = self.session.typed_text().chars().count();
+        let correct_length = self.session.correct_length();
+
+        if !self.is_blind() {
+            win.attrset(pancurses::A_DIM);
+            self.print_text_slice(win, 0, correct_length);
+            self.print_skipped_keys(win)?;
+        }
+        win.attrset(pancurses::A_BOLD);
+        self.print_text_slice(win, typed_length, self.session.text().chars().count());
+
+        self.print_ghost(win, correct_length, typed_length);
+        self.print_pace_caret(win, typed_length)?;
+        win.mv(cursor_y, cursor_x);
+        Ok(())
+    }
+
+    /// Underline the character the ghost of the best run is typing
+    fn print_ghost(&self, win: &mut impl TerminalUi, correct_length: usize, typed_length: usize) {
+        let Some(ghost) = &self.ghost else {
+            return;
+        };
+        let progress = ghost.progress_at(self.elapsed());
+        let index = wrapped_char_index(self.session.original_text(), self.session.text(), progress);
+        if index >= self.session.text().chars().count() {
+            return;
+        }
+        // Keep the dimming of typed text, never draw over the red region
+        let attributes = if self.is_blind() {
+            pancurses::A_BOLD
+        } else if index < correct_length {
+            pancurses::A_DIM
+        } else if index >= typed_length {
+            pancurses::A_BOLD
+        } else {
+            return;
+        };
+        win.attrset(attributes | pancurses::A_UNDERLINE);
+        self.print_text_slice(win, index, index + 1);
+    }
+
+    /// Mark the character a typist going at the target pace would be typing
+    fn print_pace_caret(&mut self, win: &mut impl TerminalUi, typed_length: usize) -> AppResult<()> {
+        let Some(target_wpm) = self.options.pace_wpm else {
+            return Ok(());
+        };
+        let text_length = self.session.text().chars().count();
+        let elapsed = timer::get_elapsed_minutes_since_first_keypress(self.start_time);
+        match pace_position(self.session.text(), self.window_width, target_wpm, elapsed) {
+            Some((index, _)) => {
+                self.pace_index = index;
+                if index >= typed_length {
+                    win.attrset(self.color_attribute(Color::Magenta));
+                    self.print_text_slice(win, index, index + 1);
+                }
+            }
+            None => self.pace_index = text_length,
         }
+        Ok(())
+    }
+
+    /// Attribute drawing the given color, plain text if there is none
+    fn color_attribute(&self, color: Color) -> chtype {
+        self.color.get(&color).copied().unwrap_or(pancurses::A_NORMAL)
     }
 
     /// Configure the initial state of the curses interface
     ///
     /// # Arguments
     /// * `win` - The curses window
-    pub fn initialize_windows(&mut self, win: &pancurses::Window) -> AppResult<()> {
+    pub fn initialize_windows(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
         {
             let (window_height, window_width) = get_dimensions(win);
             self.window_height = window_height;
             self.window_width = window_width;
         }
         // This works by adding extra spaces to the text where needed
-        self.text = word_wrap(&self.text, self.window_width)?;
-
-        // Check if we can fit text in the current window after adding word wrap
-        self.screen_size_check();
+        self.wrap_text();
+        self.load_ghost();
+        // The keys land on the same padding as in the test
+        for (time, key) in std::mem::take(&mut self.replay_keys) {
+            self.session.record_key(&key, time);
+            self.session.process_key(&key);
+        }
 
-        pancurses::init_pair(1, pancurses::COLOR_WHITE, pancurses::COLOR_GREEN);
-        pancurses::init_pair(2, pancurses::COLOR_WHITE, pancurses::COLOR_RED);
-        pancurses::init_pair(3, pancurses::COLOR_WHITE, pancurses::COLOR_BLUE);
-        pancurses::init_pair(4, pancurses::COLOR_WHITE, pancurses::COLOR_YELLOW);
-        pancurses::init_pair(5, pancurses::COLOR_WHITE, pancurses::COLOR_CYAN);
-        pancurses::init_pair(6, pancurses::COLOR_WHITE, pancurses::COLOR_MAGENTA);
-        pancurses::init_pair(7, pancurses::COLOR_BLACK, pancurses::COLOR_WHITE);
-
-        self.color = {
-            let mut color = HashMap::new();
-            color.insert(Color::Green, ColorPair(1));
-            color.insert(Color::Red, ColorPair(2));
-            color.insert(Color::Blue, ColorPair(3));
-            color.insert(Color::Yellow, ColorPair(4));
-            color.insert(Color::Cyan, ColorPair(5));
-            color.insert(Color::Magenta, ColorPair(6));
-            color.insert(Color::Black, ColorPair(7));
-            color
+        // Terminals without colors get the monochrome attributes whatever was asked for
+        let color_mode = if win.has_colors() {
+            self.options.color_mode
+        } else {
+            ColorMode::Monochrome
+        };
+        let theme = match color_mode {
+            ColorMode::Colorblind => self.options.theme.colorblind(),
+            _ => self.options.theme.clone(),
         };
+        if color_mode != ColorMode::Monochrome {
+            // Themes can keep the terminal background, and use the 256 colors where there are enough
+            let default_colors = win.use_default_colors();
+            let theme = theme.for_terminal(win.colors(), default_colors);
+            for (pair, _, foreground, background) in theme.pairs() {
+                win.init_pair(pair, foreground, background);
+            }
+        }
+        self.color = color_attributes(&theme, color_mode);
+
+        // Check if we can fit text in the current window after adding word wrap,
+        // the colors are set up first for the error screen
+        self.screen_size_check();
+        if self.exit.is_some() {
+            return Ok(());
+        }
 
         // This sets input to be a non-blocking call and will block for 100ms
         // Returns -1 if no input found at the end of time
         win.nodelay(true);
-        win.timeout(100);
+        win.timeout(INPUT_TIMEOUT.as_millis() as i32);
 
         self.setup_print(win)
     }
 
     /// Start recording typing session progress
-    fn typing_mode(&mut self, win: &pancurses::Window, key: &Input) -> AppResult<()> {
+    fn typing_mode(&mut self, win: &mut impl TerminalUi, key: &Input) -> AppResult<()> {
+        let key = &self.emulate_layout(key);
         // Note start time when the first valid key is pressed
         if !self.first_key_pressed && is_valid_initial_key(key) {
-            self.start_time = SystemTime::now();
+            self.start_time = Instant::now();
             self.first_key_pressed = true;
         }
 
@@ -236,257 +728,683 @@ impl App {
             return Ok(());
         }
 
-        self.key_strokes.push((
-            SystemTime::now()
-                .duration_since(time::UNIX_EPOCH)?
-                .as_secs_f64(),
-            *key,
-        ));
+        // Esc ends an endless test and isn't part of the replay
+        if self.options.endless.is_some() && is_escape(key) {
+            return self.test_end(win);
+        }
+
+        let elapsed = self.elapsed();
+        self.session.record_key(key, elapsed);
 
-        self.print_realtime_wpm(win)?;
+        self.print_header_stats(win)?;
 
         self.key_printer(win, key)
     }
 
+    /// Load the best saved run of the text when racing it
+    fn load_ghost(&mut self) {
+        // The ghost is optional, a ghosts file that can't be read means racing without one
+        self.ghost = if self.options.ghost {
+            history::load_ghost(&self.options.history_file, &self.text_id).ok().flatten()
+        } else {
+            None
+        };
+    }
+
+    /// Key the emulated layout would produce for a key pressed on a QWERTY keyboard
+    ///
+    /// Only characters are remapped, control keys and space are returned unchanged.
+    pub fn emulate_layout(&self, key: &Input) -> Input {
+        match (key, self.options.layout) {
+            (Input::Character(c), Some(layout)) => Input::Character(remap(layout, *c)),
+            _ => *key,
+        }
+    }
+
+    /// Replace the finished text with the next page of an endless test
+    fn start_next_page(&mut self, text: &str) {
+        self.session.start_next_page(text);
+        self.fit_text();
+    }
+
+    /// Start over on a new text, wrapped to the window if there is one
+    fn load_text(&mut self, text: &str) {
+        self.session = TypingSession::with_rules(text, self.options.session_rules());
+        self.fit_text();
+    }
+
+    /// Wrap the text to the window if there is one
+    fn fit_text(&mut self) {
+        if self.window_width > 0 {
+            self.wrap_text();
+            self.screen_size_check();
+        }
+    }
+
+    /// Whether finishing the text shows another one instead of ending the test
+    fn has_next_page(&self) -> bool {
+        self.options.endless.is_some() || self.marathon_index + 1 < self.marathon_texts.len()
+    }
+
+    /// Position in a marathon like "2/5", None outside of marathons
+    pub fn marathon_progress(&self) -> Option<String> {
+        if self.marathon_texts.is_empty() {
+            return None;
+        }
+        Some(format!("{}/{}", self.marathon_index + 1, self.marathon_texts.len()))
+    }
+
+    /// Move on to the next text of a marathon, returns false after the last one
+    pub fn start_next_marathon_text(&mut self) -> bool {
+        let Some((text, text_id)) = self.marathon_texts.get(self.marathon_index + 1).cloned() else {
+            return false;
+        };
+        self.marathon_index += 1;
+        self.text_id = text_id;
+        self.start_next_page(&text);
+        true
+    }
+
+    /// Id saved to history, a marathon is saved as one row with the ids of all its texts
+    pub fn history_id(&self) -> String {
+        if self.marathon_texts.is_empty() {
+            return self.text_id.clone();
+        }
+        self.marathon_texts.iter()
+            .map(|(_, text_id)| text_id.as_str())
+            .collect::<Vec<&str>>()
+            .join("+")
+    }
+
+    /// Load the text of the next page of an endless test
+    fn next_endless_text(&self) -> AppResult<String> {
+        match &self.options.endless {
+            Some(EndlessSource::Database(difficulty)) => {
+                let prepared_text = load_text_from_database_based_on_difficulty(*difficulty, "data.db")?;
+                Ok(self.options.transforms.apply(prepared_text)?.0)
+            }
+            Some(EndlessSource::Words(words)) if !words.is_empty() => {
+                let mut rng = rand::thread_rng();
+                Ok((0..ENDLESS_PAGE_WORDS)
+                    .map(|_| words[rng.gen_range(0..words.len())].as_str())
+                    .collect::<Vec<&str>>()
+                    .join(" "))
+            }
+            _ => Ok(self.session.original_text().to_string()),
+        }
+    }
+
+    /// Show the next page once the current one is typed out in endless mode
+    fn show_next_page(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if !self.start_next_marathon_text() {
+            let text = self.next_endless_text()?;
+            self.start_next_page(&text);
+        }
+
+        win.clear();
+        self.setup_print(win)?;
+        self.update_state(win)
+    }
+
     /// Print required key to terminal
-    fn key_printer(&mut self, win: &pancurses::Window, key: &Input) -> AppResult<()> {
+    fn key_printer(&mut self, win: &mut impl TerminalUi, key: &Input) -> AppResult<()> {
         // reset test
         if is_escape(key) {
-            self.reset_test()
+            self.reset_test(win)
         } else if is_ctrl_c(key) {
-            exit(0)
+            self.exit = Some(Ok(self.leave()));
+            return Ok(());
         } else if is_resize(key) {
             self.resize(win)?;
-        } else if is_backspace(key) {
-            self.erase_key();
-        } else if is_ctrl_backspace(key) {
-            self.erase_word()?;
-        }
-        // Ignore spaces at the start of the word (Plover support)
-        else if key == &Input::Character(' ') && self.current_word.len() < self.current_word_limit
-        {
-            self.total_chars_typed += 1;
-            if !self.current_word.is_empty() {
-                self.check_word()?;
-            }
-        } else if is_valid_initial_key(key) {
-            let key = get_key_mapping(key);
-            self.appendkey(&key);
-            self.total_chars_typed += 1;
+        } else {
+            self.process_key(key);
+            self.caps_lock_warning = looks_like_caps_lock(self.session.typed_text(), self.session.text());
         }
         self.update_state(win)
     }
 
-    fn appendkey(&mut self, key: &String) {
-        if self.current_word.len() < self.current_word_limit {
-            self.current_word += key;
-            self.current_string += key;
+    /// Apply an editing or character key to the typed text.
+    ///
+    /// Nothing is drawn here, a key refused in strict mode beeps at the next redraw.
+    pub fn process_key(&mut self, key: &Input) {
+        if !self.session.process_key(key) {
+            self.key_rejected = true;
+        }
+    }
+
+    /// Typing logic and stats of the test on screen
+    pub fn session(&self) -> &TypingSession {
+        &self.session
+    }
+
+    /// Whether the typing is hidden right now, the end screen and replay show everything
+    fn is_blind(&self) -> bool {
+        self.options.blind && self.mode == 0
+    }
+
+    /// Whether only the text is shown right now, the end screen and replay show everything
+    fn is_zen(&self) -> bool {
+        self.options.zen && self.mode == 0
+    }
+
+    /// Whether a timed test has run out of time after `elapsed`
+    pub fn is_time_up(&self, elapsed: Duration) -> bool {
+        self.options.time_limit.is_some_and(|limit| elapsed >= limit)
+    }
+
+    /// Time the test took, capped at the time limit of a timed test
+    pub fn test_duration(&self, elapsed: Duration) -> Duration {
+        match self.options.time_limit {
+            Some(limit) => elapsed.min(limit),
+            None => elapsed,
         }
     }
 
-    /// Accept finalized word
-    fn check_word(&mut self) -> AppResult<()> {
-        let spc = get_space_count_after_ith_word(self.current_string.len(), &self.text)?;
-        if self.current_word == self.tokens[self.token_index] {
-            self.token_index += 1;
-            self.current_word = "".to_string();
-            self.current_string += " ".repeat(spc).as_str();
-        } else {
-            self.current_word = format!("{} ", self.current_word);
-            self.current_string = format!("{} ", self.current_string);
+    /// Mark the characters left untyped by skipped words
+    fn print_skipped_keys(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        win.attrset(self.color_attribute(Color::Yellow));
+        for index in self.session.skipped_keys() {
+            self.print_text_slice(win, *index, *index + 1);
         }
         Ok(())
     }
 
-    /// Open twitter intent on a browser.
+    /// Share the result to the only share target, or ask which one with several
     fn share_result(&mut self) -> AppResult<()> {
-        let message =
-            format!("My typing speed is {:.2} WPM!\n\
-            Know yours on rstype.\n\
-            \"https://github.com/CyberDogFK/rstype\" by @CyberDogFK\n\
-            #TypingTest #Rust", self.current_speed_wpm);
-        let url = format!("https://twitter.com/intent/tweet?text={}", message);
-        open::that(&url).map_err(|e| {
-            AppError::TwitterError {
-                url,
-                error_description: e.to_string(),
-            }
-        })
-    }
-
-    /// Erase the last typed word
-    fn erase_word(&mut self) -> AppResult<()> {
-        if !self.current_word.is_empty() {
-            let index_word = self.current_word.rfind(" ")
-                .ok_or(AppError::NoCharFoundError(' '))?;
-            if index_word as i32 == -1 {
-                // Single word
-                let word_length = self.current_word.len();
-                self.current_string =
-                    self.current_string[0..self.current_string.len() - word_length].to_string();
-                self.current_word = "".to_string();
-            } else {
-                let diff = self.current_word.len() - index_word;
-                self.current_word =
-                    self.current_word[0..self.current_word.len() - diff].to_string();
-                self.current_string =
-                    self.current_string[0..self.current_string.len() - diff].to_string();
+        // Options built without targets share like rstype always did
+        if self.options.share_targets.is_empty() {
+            self.options.share_targets = default_share_targets();
+        }
+        match self.options.share_targets.as_slice() {
+            [] => Ok(()),
+            [target] => self.open_share_target(&target.clone()),
+            targets => {
+                let choices: Vec<String> = targets.iter()
+                    .take(9)
+                    .enumerate()
+                    .map(|(i, target)| format!("{} {}", i + 1, target.name))
+                    .collect();
+                let prompt = format!("Share to: {}, any other key to cancel", choices.join("  "));
+                self.status_message = Some((Color::Cyan, prompt));
+                self.picking_share_target = true;
+                Ok(())
             }
         }
-        Ok(())
     }
 
-    /// Erase the last typed character
-    fn erase_key(&mut self) {
-        if !self.current_word.is_empty() {
-            self.current_word.pop();
-            self.current_string.pop();
+    /// Share to the target picked with its number, any other key cancels
+    fn pick_share_target(&mut self, key: &Input) -> AppResult<()> {
+        self.picking_share_target = false;
+        self.status_message = None;
+        let picked = match key {
+            Input::Character(c) => c.to_digit(10)
+                .and_then(|number| (number as usize).checked_sub(1))
+                .and_then(|index| self.options.share_targets.get(index).cloned()),
+            _ => None,
+        };
+        match picked {
+            Some(target) => self.open_share_target(&target),
+            None => Ok(()),
+        }
+    }
+
+    /// Open the URL sharing the result to a target in the browser
+    ///
+    /// Without a browser, like over SSH, the URL is shown on the end screen instead.
+    fn open_share_target(&mut self, target: &ShareTarget) -> AppResult<()> {
+        let template = self.options.share_template.as_deref().unwrap_or(DEFAULT_SHARE_TEMPLATE);
+        let url = target.url(&fill_template(template, &self.stats)?)?;
+        if let Err(e) = self.browser.open(&url) {
+            self.status_message = Some((Color::Yellow, format!("Can't open the browser for {}: {}", target.name, e)));
+            self.share_url = Some(url);
         }
+        Ok(())
+    }
+
+    /// Copy the result to the clipboard, the status bar tells whether it worked
+    ///
+    /// Without a clipboard, like over SSH, there is only a warning.
+    fn copy_result(&mut self) {
+        let template = self.options.copy_template.as_deref().unwrap_or(DEFAULT_COPY_TEMPLATE);
+        let copied = fill_template(template, &self.stats)
+            .map_err(|e| e.to_string())
+            .and_then(|message| self.clipboard.set_text(&message).map_err(|e| e.to_string()));
+        self.status_message = Some(match copied {
+            Ok(()) => (Color::Green, "Copied!".to_string()),
+            Err(e) => (Color::Yellow, e),
+        });
     }
 
     /// Response to window resize events
-    fn resize(&mut self, win: &pancurses::Window) -> AppResult<()> {
+    fn resize(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if !self.fit_to_window(win) {
+            return Ok(());
+        }
+
+        self.print_header_stats(win)?;
+        self.setup_print(win)?;
+        self.update_state(win)?;
+        Ok(())
+    }
+
+    /// Response to window resize events on the end screen
+    ///
+    /// The summary is laid out again below the text, the stats of the test are kept.
+    fn resize_end_screen(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if !self.fit_to_window(win) {
+            return Ok(());
+        }
+
+        self.setup_print(win)?;
+        // The typed text was cleared at the end of the test
+        win.attrset(pancurses::A_DIM);
+        self.print_text_slice(win, 0, self.session.text().chars().count());
+        self.test_end(win)
+    }
+
+    /// Clear the window and wrap the text to its new size
+    ///
+    /// Returns false when the text doesn't fit anymore and the app is stopping.
+    fn fit_to_window(&mut self, win: &mut impl TerminalUi) -> bool {
         win.clear();
 
         let (window_height, window_width) = get_dimensions(win);
         self.window_height = window_height;
         self.window_width = window_width;
-        self.text = word_wrap(&self.text_backup, self.window_width)?;
+        self.wrap_text();
 
         self.screen_size_check();
-
-        self.print_realtime_wpm(win)?;
-        self.setup_print(win)?;
-        self.update_state(win)?;
-        Ok(())
+        self.exit.is_none()
     }
 
     /// Print setup text at beginning of each typing sessions.
-    fn setup_print(&mut self, win: &pancurses::Window) -> AppResult<()> {
-        win.attrset(*self.color.get(&Color::Cyan)
-            .ok_or(Color::Cyan.not_found_err())?);
-        win.mvaddstr(0, 0, format!(" ID:{} ", self.text_id));
-        win.attrset(*self.color.get(&Color::Blue).
-            ok_or(Color::Blue.not_found_err())?);
-        win.mvaddstr(0, self.window_width / 2 - 4, " RSTYPE ");
+    fn setup_print(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        self.load_text_best_wpm();
+        if !self.is_zen() {
+            self.print_header(win)?;
+        }
 
         // Text is printed BOLD initially
         // It is dimmed as user types on top of it
-        win.attrset(pancurses::A_BOLD);
-        win.mvaddstr(2, 0, &self.text);
+        self.scroll_row = 0;
+        self.print_text(win);
 
-        self.print_realtime_wpm(win)?;
+        self.print_header_stats(win)?;
 
         win.mv(2, 0);
         win.refresh();
         Ok(())
     }
 
-    fn print_realtime_wpm(&mut self, win: &pancurses::Window) -> AppResult<()> {
-        let mut current_wpm = 0.0;
-        let total_time = timer::get_elapsed_minutes_since_first_keypress(self.start_time)?;
-        if total_time != 0.0 {
-            let words = self.current_string.split_ascii_whitespace();
-            let word_count = words.count() as f64;
-            current_wpm = word_count / total_time;
+    /// Print the text ID and the title in the header
+    fn print_header(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let layout = self.header_layout();
+        // A long ID is cut short before the live WPM
+        let header_id: String = self.header_id().chars().take(layout.wpm.max(0) as usize).collect();
+        win.attrset(self.color_attribute(Color::Cyan));
+        win.mvaddstr(0, 0, header_id);
+        if let Some(title_x) = layout.title {
+            win.attrset(self.color_attribute(Color::Blue));
+            win.mvaddstr(0, title_x, HEADER_TITLE);
         }
-        win.attrset(*self.color.get(&Color::Cyan).
-            ok_or(Color::Cyan.not_found_err())?);
-        win.mvaddstr(0, self.window_width - 14, format!("{:.2}", current_wpm));
-        win.addstr(" WPM ");
         Ok(())
     }
 
-    /// Check if screen size is enough to print text.
+    /// Print the live stats shown in the header
+    fn print_header_stats(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if self.is_zen() {
+            return Ok(());
+        }
+        self.print_elapsed_time(win)?;
+        self.print_realtime_wpm(win)
+    }
+
+    fn print_elapsed_time(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let Some(timer_x) = self.header_layout().timer else {
+            return Ok(());
+        };
+        let elapsed = if self.mode == 1 {
+            Duration::from_secs_f64(self.stats.duration)
+        } else if self.first_key_pressed {
+            self.elapsed()
+        } else {
+            Duration::ZERO
+        };
+        win.attrset(self.color_attribute(Color::Green));
+        win.mvaddstr(0, timer_x, format!(" {} ", timer::format_elapsed(elapsed)));
+        Ok(())
+    }
+
+    /// Place the header items, leaving out the ones that don't fit the window
+    fn header_layout(&self) -> HeaderLayout {
+        header_layout(self.window_width, self.header_id().chars().count() as i32)
+    }
+
+    /// Id of the text shown at the start of the header, with the difficulty, the layout and the position in a marathon
+    fn header_id(&self) -> String {
+        let mut header_id = format!(" ID:{} ", self.text_id);
+        if let Some(difficulty) = self.options.difficulty {
+            header_id.push_str(&format!("D:{} ", difficulty));
+        }
+        if let Some(layout) = self.options.layout {
+            header_id.push_str(&format!("[{}] ", layout.name()));
+        }
+        if let Some(progress) = self.marathon_progress() {
+            header_id.push_str(&format!("{} ", progress));
+        }
+        if let Some(Some(best_wpm)) = self.text_best_wpm.get(&self.history_id()) {
+            header_id.push_str(&format!("PB: {:.1} WPM ", best_wpm));
+        }
+        header_id
+    }
+
+    /// Look up the best speed of the text in history, unless it was looked up already
+    ///
+    /// Nothing is shown when the text was never typed or the history can't be read.
+    fn load_text_best_wpm(&mut self) {
+        if self.options.no_history {
+            return;
+        }
+        let text_id = self.history_id();
+        if let Entry::Vacant(entry) = self.text_best_wpm.entry(text_id) {
+            let best_wpm = history::best_for_text(&self.options.history_file, entry.key()).ok().flatten();
+            entry.insert(best_wpm);
+        }
+    }
+
+    fn print_realtime_wpm(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        if self.is_blind() {
+            return Ok(());
+        }
+        // Replays and the end screen show the result instead of a speed still running
+        let current_wpm = if self.mode == 1 {
+            self.stats.wpm
+        } else if self.options.rolling_wpm {
+            rolling_wpm(&self.session.keystroke_times(), self.elapsed(), ROLLING_WPM_WINDOW)
+        } else {
+            let total_time = timer::get_elapsed_minutes_since_first_keypress(self.start_time);
+            words_per_minute(self.session.completed_words(), total_time)
+        };
+        // Behind the pace caret tints red, ahead of it green
+        let color = if self.options.pace_wpm.is_some() && self.first_key_pressed && self.mode == 0 {
+            if self.session.correct_length() >= self.pace_index {
+                Color::Green
+            } else {
+                Color::Red
+            }
+        } else {
+            Color::Cyan
+        };
+        win.attrset(self.color_attribute(color));
+        let layout = self.header_layout();
+        if layout.short_wpm {
+            win.mvaddstr(0, layout.wpm, format!("{:>4.0}wpm", current_wpm));
+        } else {
+            win.mvaddstr(0, layout.wpm, format!("{:.2}", current_wpm));
+            win.addstr(" WPM ");
+        }
+        Ok(())
+    }
+
+    /// Word wrap the original text to the current window width.
+    fn wrap_text(&mut self) {
+        self.session.wrap_text(self.window_width);
+        self.text_positions = char_positions(self.session.text(), self.window_width);
+        self.text_lines = line_ranges(self.session.text(), self.window_width);
+    }
+
+    /// Cell of the window the character at `index` of the text is drawn in
+    fn screen_position(&self, index: usize) -> (i32, i32) {
+        let (row, column) = self.text_position(index);
+        (2 + row - self.scroll_row, column)
+    }
+
+    /// Characters of the text in the rows shown right now
+    fn visible_chars(&self) -> Range<usize> {
+        let first_row = self.scroll_row as usize;
+        let last_row = (self.scroll_row + self.visible_text_rows) as usize;
+        match (self.text_lines.get(first_row), self.text_lines.get(last_row.saturating_sub(1))) {
+            (Some(first), Some(last)) => first.start..last.end,
+            _ => 0..self.session.text().chars().count(),
+        }
+    }
+
+    /// Draw the characters `start..end` of the text that are in the rows shown right now
+    fn print_text_slice(&self, win: &mut impl TerminalUi, start: usize, end: usize) {
+        let visible = self.visible_chars();
+        let (start, end) = (start.max(visible.start), end.min(visible.end));
+        if start >= end {
+            return;
+        }
+        let (row, column) = self.screen_position(start);
+        win.mvaddstr(row, column, slice_by_char_indices(self.session.text(), start, end));
+    }
+
+    /// Draw the rows of the text shown right now in bold, clearing what was there
+    fn print_text(&self, win: &mut impl TerminalUi) {
+        for row in 2..2 + self.visible_text_rows {
+            self.clear_line(win, row);
+        }
+        win.attrset(pancurses::A_BOLD);
+        self.print_text_slice(win, 0, self.session.text().chars().count());
+    }
+
+    /// Screen cell of the character at `index`, relative to the start of the text.
+    ///
+    /// The position just past the end of the text is placed after the last character.
+    fn text_position(&self, index: usize) -> (i32, i32) {
+        match self.text_positions.get(index) {
+            Some(position) => *position,
+            None => self.text_positions.last()
+                .map(|(row, column)| (*row, column + 1))
+                .unwrap_or((0, 0)),
+        }
+    }
+
+    /// Fit the text in the screen, stopping the app when not even a few rows of it fit.
+    ///
+    /// Texts taller than the screen only show the rows around the one being typed.
     fn screen_size_check(&mut self) {
-        self.number_of_lines_to_print_text =
-            number_of_lines_to_fit_text_in_window(&self.text, self.window_width) + 3;
-        if self.number_of_lines_to_print_text + 7 >= self.window_height {
-            eprintln!("Window too small to print given text");
-            exit(0)
+        if self.window_width < MIN_WINDOW_WIDTH {
+            self.exit = Some(Err(AppError::WindowTooNarrowError(MIN_WINDOW_WIDTH)));
+            return;
+        }
+        let text_rows = number_of_lines_to_fit_text_in_window(self.session.text(), self.window_width);
+        // Rows above the text, the prompt and the end screen below it take the rest of the screen
+        self.visible_text_rows = text_rows.min(self.window_height - 11);
+        self.number_of_lines_to_print_text = self.visible_text_rows + 3;
+        if self.visible_text_rows < text_rows.min(MIN_VISIBLE_TEXT_ROWS) {
+            self.exit = Some(Err(AppError::WindowTooSmallError));
         }
     }
 
-    /// Play out a recordning of the user's last session
-    fn replay(&mut self, win: &pancurses::Window) -> AppResult<()> {
+    /// Draw the screen a replay starts from, the keys are typed again on a copy of the session
+    fn start_replay(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
         win.clear();
         self.print_stats(win)?;
         win.mvaddstr(self.number_of_lines_to_print_text + 2, 0, " ".repeat(self.window_width as usize));
-        pancurses::curs_set(1);
+        win.curs_set(1);
 
-        win.attrset(*self.color.get(&Color::Cyan)
-            .ok_or(Color::Cyan.not_found_err())?);
+        win.attrset(self.color_attribute(Color::Cyan));
         win.mvaddstr(
             0,
             self.window_width,
-            format!(" {} ", self.current_speed_wpm),
+            format!(" {} ", self.stats.wpm),
         );
         win.attrset(pancurses::A_NORMAL);
 
-        self.setup_print(win)?;
+        let copy = self.session.replay_copy();
+        self.recorded_session = Some(std::mem::replace(&mut self.session, copy));
+        self.setup_print(win)
+    }
+
+    /// Put back the session of the test once its keys are replayed
+    fn end_replay(&mut self) {
+        if let Some(session) = self.recorded_session.take() {
+            self.session = session;
+        }
+    }
 
+    /// Play out a recordning of the user's last session
+    fn replay(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        self.start_replay(win)?;
         win.timeout(10);
 
-        let mut next_tick = SystemTime::now();
-        for key in &self.key_strokes.clone() {
-            next_tick = next_tick.add(Duration::from_secs_f64(key.0));
-            let wait_duration = 0.0_f64.max(next_tick.duration_since(time::UNIX_EPOCH)?
-                .as_secs_f64() - SystemTime::now()
-                .duration_since(time::UNIX_EPOCH)?
-                .as_secs_f64()
-            );
-            std::thread::sleep(Duration::from_secs_f64(wait_duration));
+        let mut next_tick = Instant::now();
+        // Endless and marathon tests only replay the page on screen
+        let keys = self.session.page_key_strokes().to_vec();
+        // Keys are recorded with the time since the start of the test, replayed with the time between them
+        let delays = self.replay_delays(&keys);
+        for (key, delay) in keys.iter().zip(delays) {
+            next_tick += delay;
+            std::thread::sleep(next_tick.saturating_duration_since(Instant::now()));
 
             if let Some(_key) = win.getch() {
                 if is_escape(&_key) || is_ctrl_c(&_key) {
-                    exit(0)
+                    self.end_replay();
+                    self.exit = Some(Ok(self.leave()));
+                    return Ok(());
+                }
+                // The replay goes on in the resized window
+                if is_resize(&_key) {
+                    self.resize(win)?;
+                    if self.exit.is_some() {
+                        self.end_replay();
+                        return Ok(());
+                    }
+                    self.print_stats(win)?;
                 }
             }
             self.key_printer(win, &key.1)?;
+            if self.exit.is_some() {
+                self.end_replay();
+                return Ok(());
+            }
+        }
+        // A timed test can end before the text is finished, the end screen puts the test back
+        if self.recorded_session.is_some() {
+            self.test_end(win)?;
+        }
+        win.timeout(INPUT_TIMEOUT.as_millis() as i32);
+        Ok(())
+    }
+
+    /// Draw the replay of a test without waiting between the keys, like for a recording
+    ///
+    /// The app has to come from `for_replay` and its windows have to be set up with `initialize_windows`.
+    /// # Arguments:
+    /// * `win` - Terminal the replay is drawn on
+    /// * `on_frame` - Called with the terminal and the time into the replay, the first key
+    ///   being played at once and long pauses cut, once the replay is set up and after every key
+    pub fn draw_replay<W: TerminalUi>(
+        &mut self,
+        win: &mut W,
+        mut on_frame: impl FnMut(&W, Duration),
+    ) -> AppResult<()> {
+        // The window was too small for the text
+        if let Some(Err(e)) = self.exit.take() {
+            return Err(e);
+        }
+        let key_strokes = self.session.page_key_strokes().to_vec();
+        self.start_replay(win)?;
+        let mut replay_time = Duration::ZERO;
+        on_frame(win, replay_time);
+        for ((_, key), delay) in key_strokes.iter().zip(self.replay_delays(&key_strokes)) {
+            replay_time += delay;
+            self.key_printer(win, key)?;
+            on_frame(win, replay_time);
+        }
+        // A timed test can end before the text is finished, the end screen puts the test back
+        if self.recorded_session.is_some() {
+            self.test_end(win)?;
+            on_frame(win, replay_time);
         }
-        win.timeout(100);
         Ok(())
     }
 
+    /// Time to wait before each key of a replay, the same for every replay of a test
+    fn replay_delays(&self, key_strokes: &[(Duration, Input)]) -> Vec<Duration> {
+        let times: Vec<Duration> = key_strokes.iter().map(|(time, _)| *time).collect();
+        replay_delays(&times, REPLAY_MAX_DELAY)
+    }
+
     /// Report on typing session results
-    fn update_state(&mut self, win: &pancurses::Window) -> AppResult<()> {
+    fn update_state(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        // Strict mode refused the last key
+        if std::mem::take(&mut self.key_rejected) {
+            win.beep();
+        }
+
         self.clear_line(win, self.number_of_lines_to_print_text);
+        self.clear_line(win, self.number_of_lines_to_print_text + 1);
         self.clear_line(win, self.number_of_lines_to_print_text + 2);
         self.clear_line(win, self.number_of_lines_to_print_text + 4);
 
+        if self.caps_lock_warning && !self.is_blind() {
+            win.attrset(self.color_attribute(Color::Yellow));
+            win.mvaddstr(self.number_of_lines_to_print_text + 1, 0, " Caps Lock? ");
+            win.attrset(pancurses::A_NORMAL);
+        }
+
         // Highlight in RED if a word reaches the word limit length
-        if self.current_word.len() >= self.current_word_limit {
-            win.attrset(*self.color.get(&Color::Red)
-                .ok_or(Color::Red.not_found_err())?);
-            win.mvaddstr(self.number_of_lines_to_print_text, 0, &self.current_word);
+        if self.session.is_word_limit_reached() {
+            win.attrset(self.color_attribute(Color::Red));
+            win.mvaddstr(self.number_of_lines_to_print_text, 0, self.session.current_word());
         } else {
-            win.mvaddstr(self.number_of_lines_to_print_text, 0, &self.current_word);
+            win.mvaddstr(self.number_of_lines_to_print_text, 0, self.session.current_word());
         }
 
+        // Positions are counted in characters, so multibyte text is never split
+        let typed_length = self.session.typed_text().chars().count();
+        // Long texts scroll to keep the row being typed in view
+        let (cursor_row, _) = self.text_position(typed_length);
+        self.scroll_row = viewport_start(
+            cursor_row,
+            self.visible_text_rows,
+            self.text_lines.len() as i32,
+            SCROLL_CONTEXT_ROWS,
+        );
+
         // Text is printed BOLD initially
         // It is dimmed as user types on top of it
-        win.attrset(pancurses::A_BOLD);
-        win.mvaddstr(2, 0, &self.text);
-        win.attrset(pancurses::A_DIM);
-        win.mvaddstr(2, 0, &self.text[0..self.current_string.len()]);
+        self.print_text(win);
+        let text_length = self.session.text().chars().count();
+        let index = self.session.correct_length();
+        let progress_line = self.number_of_lines_to_print_text - 1;
+        self.clear_line(win, progress_line);
+
+        if self.is_blind() {
+            // The text stays untouched, only the cursor moves along
+            let (row, column) = self.screen_position(typed_length);
+            win.mv(row, column);
+        } else {
+            win.attrset(pancurses::A_DIM);
+            self.print_text_slice(win, 0, typed_length);
+            self.print_skipped_keys(win)?;
+
+            // Progress bar on the empty line below the text
+            if !self.is_zen() {
+                win.attrset(self.color_attribute(Color::Green));
+                win.mvaddstr(
+                    progress_line,
+                    0,
+                    progress_bar(index, text_length, self.window_width as usize),
+                );
+            }
 
-        let index = first_index_at_which_strings_differ(&self.current_string, &self.text);
-        // Check if difference was found
-        if index < self.current_string.len() && self.current_string.len() <= self.text.len() {
-            self.mistyped_keys.push(self.current_string.len() - 1)
+            win.attrset(self.color_attribute(Color::Red));
+            self.print_text_slice(win, index, typed_length);
+            let (row, column) = self.screen_position(typed_length);
+            win.mv(row, column);
         }
 
-        win.attrset(*self.color.get(&Color::Red)
-            .ok_or(Color::Red.not_found_err())?);
-        win.mvaddstr(
-            2 + index as i32 / self.window_width,
-            index as i32 % self.window_width,
-            &self.text[index..self.current_string.len()],
-        );
-
         // End of test, all characters are typed out
-        if index == self.text.len() {
+        if index == text_length && self.has_next_page() && self.mode == 0 {
+            return self.show_next_page(win);
+        } else if index == text_length {
             self.test_end(win)?;
+        } else {
+            self.print_text_markers(win)?;
         }
 
         win.refresh();
@@ -497,167 +1415,681 @@ impl App {
     ///
     /// Display options for the user to choose at the end of the test.
     /// Display stats.
-    fn test_end(&mut self, win: &pancurses::Window) -> AppResult<()> {
-        for i in self.mistyped_keys.iter() {
-            win.attrset(*self.color.get(&Color::Red)
-                .ok_or(Color::Red.not_found_err())?);
-            win.mvaddstr(
-                2 + *i as i32 / self.window_width,
-                *i as i32 % self.window_width,
-                &self.text[*i..=*i],
-            );
+    fn test_end(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        // The end screen shows the test, not the copy the replay typed on
+        self.end_replay();
+        for i in self.session.mistyped_keys().keys() {
+            win.attrset(self.color_attribute(Color::Red));
+            self.print_text_slice(win, *i, *i + 1);
         }
+        self.print_skipped_keys(win)?;
+        // The Caps Lock warning makes room for the lesson result
+        self.clear_line(win, self.number_of_lines_to_print_text + 1);
 
-        pancurses::curs_set(0);
+        win.curs_set(0);
 
         // Calculate stats at the end of the test
         if self.mode == 0 {
-            self.current_speed_wpm = speed_in_wpm(&self.tokens, self.start_time)?;
-            let total_chars_in_text = self.text_backup.len();
-            let wrongly_typed_chars = self.total_chars_typed - total_chars_in_text;
-            self.accuracy = accuracy(self.total_chars_typed, wrongly_typed_chars);
-            self.time_taken = timer::get_elapsed_minutes_since_first_keypress(self.start_time)?;
+            // All speeds are based on the same elapsed time
+            self.stats = self.session.finish(&self.history_id(), self.test_duration(self.elapsed()));
+            self.stats.time_limit = self.options.time_limit.map(|limit| limit.as_secs());
+            self.stats.source = self.options.text_source;
+            self.attempts += 1;
+            self.stats.attempt = Some(self.attempts);
+            // Endless and marathon tests type several texts, they can't be retaken
+            let single_text = self.marathon_texts.is_empty() && self.options.endless.is_none();
+            self.stats.text_hash = single_text.then(|| history::text_hash(self.session.original_text()));
+            // Only database texts have a difficulty, a file could be named like one of their ids
+            self.stats.difficulty = match self.stats.source {
+                TextSource::Database => self.options.difficulty
+                    .or_else(|| self.text_id.parse().ok().and_then(difficulty_of_text_id)),
+                _ => None,
+            };
+            self.stats.suspect = !self.options.allow_fast_input
+                && is_pasted_input(&self.session.keystroke_times(), PASTE_MAX_INTERVAL, PASTE_MIN_BURST);
+            self.lesson_passed = self.options.lesson.as_ref()
+                .map(|lesson| is_lesson_passed(self.stats.accuracy, self.stats.wpm, lesson.min_wpm));
+            // Compared before the result is saved, it would beat itself otherwise
+            self.personal_best = self.find_personal_best();
+            self.stats.personal_best = matches!(self.personal_best, Some(history::PersonalBest::Overall(_)));
+            self.test_window_size = (self.window_height, self.window_width);
 
             self.mode = 1;
-            // Find time difference between the keystrokes
-            // The key_strokes list is storing the time at which the key is pressed
-            for index in (1..=(self.key_strokes.len() - 1)).rev() {
-                self.key_strokes[index].0 -= self.key_strokes[index - 1].0;
-            }
-            self.key_strokes[0].0 = Duration::from_secs(0).as_secs_f64();
         }
 
+        // The header was hidden while typing
+        if self.options.zen {
+            self.print_header(win)?;
+        }
+        // The live speed gives way to the one saved to history
+        self.print_header_stats(win)?;
+
         win.attrset(pancurses::A_NORMAL);
         win.mvaddstr(
             self.number_of_lines_to_print_text,
             0,
             " Your typing speed is ",
         );
-        win.attrset(*self.color.get(&Color::Magenta)
-            .ok_or(Color::Magenta.not_found_err())?);
-        win.addstr(format!(" {:.2} ", self.current_speed_wpm));
-        win.attroff(*self.color.get(&Color::Magenta)
-            .ok_or(Color::Magenta.not_found_err())?);
+        // The goal is checked against the speed saved to history
+        let goal_gap = self.options.goal_wpm.map(|goal_wpm| self.stats.wpm - goal_wpm);
+        let speed_color = match goal_gap {
+            Some(gap) if gap >= 0.0 => Color::Green,
+            _ => Color::Magenta,
+        };
+        win.attrset(self.color_attribute(speed_color));
+        win.addstr(format!(" {:.2} ", self.stats.wpm));
+        win.attrset(pancurses::A_NORMAL);
         win.addstr(" WPM ");
+        match goal_gap {
+            Some(gap) if gap >= 0.0 => {
+                win.attrset(self.color_attribute(Color::Green));
+                win.addstr(" Goal reached! ");
+            }
+            Some(gap) => {
+                win.addstr(format!(" {:.1} WPM to goal ", gap));
+            }
+            None => {}
+        }
 
-        win.attrset(*self.color.get(&Color::Black)
-            .ok_or(Color::Black.not_found_err())?);
+        win.attrset(self.color_attribute(Color::Black));
         win.mvaddstr(self.number_of_lines_to_print_text + 2, 1, " Enter ");
         win.attrset(pancurses::A_NORMAL);
         win.addstr(" to see replay, ");
 
-        win.attrset(*self.color.get(&Color::Black)
-            .ok_or(Color::Black.not_found_err())?);
+        win.attrset(self.color_attribute(Color::Black));
         win.addstr(" Tab ");
         win.attrset(pancurses::A_NORMAL);
-        win.addstr(" to retry.");
+        win.addstr(" to retry, ");
+
+        win.attrset(self.color_attribute(Color::Black));
+        win.addstr(" C ");
+        win.attrset(pancurses::A_NORMAL);
+        win.addstr(" to save a card.");
 
-        win.attrset(*self.color.get(&Color::Black)
-            .ok_or(Color::Black.not_found_err())?);
+        win.attrset(self.color_attribute(Color::Black));
         win.mvaddstr(self.number_of_lines_to_print_text + 3, 1, " Arrow keys ");
         win.attrset(pancurses::A_NORMAL);
-        win.addstr(" to change text.");
+        win.addstr(" to change text, ");
+
+        win.attrset(self.color_attribute(Color::Black));
+        win.addstr(" H ");
+        win.attrset(pancurses::A_NORMAL);
+        win.addstr(" to toggle the heatmap.");
 
-        win.attrset(*self.color.get(&Color::Black)
-            .ok_or(Color::Black.not_found_err())?);
+        win.attrset(self.color_attribute(Color::Black));
         win.mvaddstr(self.number_of_lines_to_print_text + 4, 1, " CTRL+T ");
         win.attrset(pancurses::A_NORMAL);
-        win.addstr(" to tweet result.");
+        win.addstr(" to share, ");
+
+        win.attrset(self.color_attribute(Color::Black));
+        win.addstr(" CTRL+Y ");
+        win.attrset(pancurses::A_NORMAL);
+        win.addstr(" to copy, ");
+
+        win.attrset(self.color_attribute(Color::Black));
+        win.addstr(" S ");
+        win.attrset(pancurses::A_NORMAL);
+        win.addstr(" to save replay.");
+
+        self.print_result_banners(win)?;
+        self.print_result_details(win)?;
 
         self.print_stats(win)?;
 
         self.first_key_pressed = false;
-        self.end_time = SystemTime::now();
-        self.current_string = "".to_string();
-        self.current_word = "".to_string();
-        self.token_index = 0;
+        self.end_time = Instant::now();
+        self.session.rewind();
 
-        self.start_time = SystemTime::now();
+        self.start_time = Instant::now();
         if !self.test_complete {
             win.refresh();
-            history::save_history(
-                &self.text_id,
-                self.current_speed_wpm,
-                self.accuracy,
-            )?;
+            if let Err(e) = self.save_results() {
+                return Err(self.error_screen(win, e));
+            }
+            // The total includes the test only once it is saved
+            self.print_result_banners(win)?;
+            // The results are saved already, a failed request only gets a warning
+            if let Some(url) = &self.options.webhook_url {
+                if let Err(e) = post_stats(self.webhook.as_mut(), url, &self.stats) {
+                    self.status_message = Some((Color::Yellow, e.to_string()));
+                    self.print_stats(win)?;
+                }
+            }
             self.test_complete = true;
         }
         Ok(())
     }
 
+    /// Save the results of the finished test to history and to the files given on the command line
+    fn save_results(&mut self) -> AppResult<()> {
+        if let Some(path) = &self.options.output {
+            output::save_results_to_file(path, &self.stats)?;
+        }
+        // Endless and marathon tests only write the keys of the page on screen, like the replay
+        if let Some(path) = &self.options.dump_keystrokes {
+            output::save_keystrokes_to_file(path, &self.session.simulated_keys())?;
+        }
+        if let Some(path) = &self.options.export_cast {
+            let cast = cast::record_cast(
+                (self.session.original_text().to_string(), self.text_id.clone()),
+                &self.options,
+                &self.stats,
+                self.session.page_key_strokes(),
+                self.test_window_size,
+            )?;
+            output::save_to_file(path, &cast)?;
+        }
+        // A challenge is met once, the retries with Tab are practice
+        let first_attempt = self.attempts == 1;
+        if let (Some(path), true) = (&self.options.export_challenge, first_attempt) {
+            let challenge = Challenge::new(&self.text_id, self.session.original_text(), self.options.seed, &self.stats);
+            save_challenge(path, &challenge)?;
+        }
+        if let (Some((path, challenge)), true) = (&mut self.options.challenge, first_attempt) {
+            challenge.results.push(ChallengeResult::from_stats(&self.stats));
+            save_challenge(path, challenge)?;
+        }
+        if let (Some(lesson), Some(true)) = (&self.options.lesson, self.lesson_passed) {
+            history::save_completed_lesson(&self.options.history_file, &lesson.name)?;
+        }
+        if self.options.no_history {
+            self.not_recorded = true;
+            return Ok(());
+        }
+        history::save_history_to_file(&self.options.history_file, &self.stats)?;
+        self.practice_total = history::summarize_history(&self.options.history_file).ok();
+        // The header shows the new best when the text is typed again
+        if !self.stats.suspect {
+            let best_wpm = self.text_best_wpm.entry(self.history_id()).or_insert(None);
+            *best_wpm = Some(best_wpm.map_or(self.stats.wpm, |best_wpm| best_wpm.max(self.stats.wpm)));
+        }
+        let word_speeds: Vec<(&str, f64)> = self.session.word_timings().iter()
+            .map(|(word, seconds)| (word.as_str(), word_wpm(word, *seconds)))
+            .collect();
+        history::save_word_timings(&self.options.history_file, &word_speeds)?;
+        history::save_mistyped_words(&self.options.history_file, &self.session.mistyped_words())?;
+        let run = history::GhostRun {
+            wpm: self.stats.wpm,
+            timeline: std::mem::take(&mut self.stats.timeline),
+        };
+        // A new best run is raced on the next attempt,
+        // unfinished runs and marathons can't be raced
+        let single_text = self.stats.completed && self.marathon_texts.is_empty();
+        // Failing to save the ghost doesn't lose the result, it is already in the history
+        let saved = single_text && history::save_ghost(&self.options.history_file, &self.text_id, &run).unwrap_or(false);
+        if saved && self.options.ghost {
+            self.ghost = Some(run);
+        }
+        Ok(())
+    }
+
+    /// Save the replay of the page on screen to a new file in the working directory
+    ///
+    /// The status bar tells where it was saved, failing to save it doesn't stop the app.
+    fn save_replay(&mut self) {
+        let path = format!("rstype-replay-{}.json", self.stats.timestamp.format("%Y%m%d-%H%M%S"));
+        let saved_replay = SavedReplay::new(
+            &self.text_id,
+            self.session.original_text(),
+            self.session.page_key_strokes(),
+        );
+        self.status_message = Some(match replay::save_replay(&path, &saved_replay) {
+            Ok(()) => (Color::Green, format!("Replay saved to {}, play it with --replay", path)),
+            Err(e) => (Color::Yellow, e.to_string()),
+        });
+    }
+
+    /// Write the result card to a file in the working directory and copy it to the clipboard
+    ///
+    /// The status bar tells where the card went, without a clipboard it is only saved.
+    fn save_card(&mut self) {
+        let path = format!("rstype-card-{}.txt", self.stats.timestamp.format("%Y%m%d-%H%M%S"));
+        let card = result_card(&self.stats).join("\n");
+        let saved = std::fs::write(&path, format!("{}\n", card));
+        let copied = self.clipboard.set_text(&card);
+        self.status_message = Some(match (saved, copied) {
+            (Ok(()), Ok(())) => (Color::Green, format!("Card copied and saved to {}", path)),
+            (Ok(()), Err(_)) => (Color::Green, format!("Card saved to {}", path)),
+            (Err(e), Ok(())) => (Color::Yellow, format!("Card copied, can't save it to {}: {}", path, e)),
+            (Err(e), Err(_)) => (Color::Yellow, format!("Can't save the card to {}: {}", path, e)),
+        });
+    }
+
+    /// Show an error that stops the app in the middle of the window until a key is pressed
+    ///
+    /// Messages printed to stderr while curses is running would never be seen.
+    /// # Arguments
+    /// * `win` - The curses window
+    /// * `error` - The error to show
+    /// # Returns
+    /// * `AppError` - The same error, to return up the stack
+    fn error_screen(&self, win: &mut impl TerminalUi, error: AppError) -> AppError {
+        win.clear();
+        let lines = wrap_lines(&error.to_string(), self.window_width);
+        let top = (self.window_height - lines.len() as i32) / 2;
+        win.attrset(self.color_attribute(Color::Red));
+        for (row, line) in lines.iter().enumerate() {
+            let left = (self.window_width - line.width() as i32) / 2;
+            win.mvaddstr(top.max(0) + row as i32, left.max(0), line);
+        }
+        win.attrset(pancurses::A_NORMAL);
+        win.refresh();
+
+        // Wait for a key without the timeout of the main loop
+        win.timeout(-1);
+        win.getch();
+        error
+    }
+
+    /// Print the time between keystrokes on the end screen
+    fn print_latency_stats(&self, win: &mut impl TerminalUi) {
+        let Some(latency) = &self.stats.latency else {
+            return;
+        };
+        let paused_key = self.session.key_strokes().get(latency.longest_pause_index)
+            .map(|(_, key)| get_key_name(key))
+            .unwrap_or_default();
+        let line: String = format!(
+            " Keystrokes: avg {:.0}ms, median {:.0}ms, longest pause {:.0}ms before '{}'",
+            latency.mean_ms, latency.median_ms, latency.longest_pause_ms, paused_key,
+        )
+            .chars()
+            .take(self.window_width as usize)
+            .collect();
+        win.attrset(pancurses::A_NORMAL);
+        win.mvaddstr(self.number_of_lines_to_print_text + 5, 0, line);
+    }
+
+    /// Draw the speed over the course of the test below the end screen stats
+    ///
+    /// Nothing is drawn when the window has no room left above the stats bar.
+    fn print_wpm_graph(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let graph_line = self.number_of_lines_to_print_text + 7;
+        if self.stats.wpm_over_time.is_empty() || graph_line >= self.window_height - 1 {
+            return Ok(());
+        }
+        let label = " WPM ";
+        let graph_width = (self.window_width as usize).saturating_sub(label.len() + 1);
+        win.attrset(pancurses::A_NORMAL);
+        win.mvaddstr(graph_line, 0, label);
+        win.attrset(self.color_attribute(Color::Magenta));
+        win.addstr(sparkline(&self.stats.wpm_over_time, graph_width));
+        Ok(())
+    }
+
+    /// Print the lesson result, the personal best and the total practice in a row below the typing speed
+    fn print_result_banners(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        self.print_lesson_result(win)?;
+        self.print_personal_best(win);
+        self.print_practice_total(win);
+        self.print_not_recorded(win);
+        Ok(())
+    }
+
+    /// Print whether the lesson was passed under the typing speed on the end screen
+    fn print_lesson_result(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let (Some(lesson), Some(passed)) = (&self.options.lesson, self.lesson_passed) else {
+            return Ok(());
+        };
+        let (color, line) = if passed {
+            (Color::Green, format!(" Lesson {} passed! ", lesson.name))
+        } else {
+            (Color::Red, format!(
+                " Lesson {} not passed, it needs {:.0}% accuracy and {:.0} WPM ",
+                lesson.name, LESSON_MIN_ACCURACY, lesson.min_wpm,
+            ))
+        };
+        let line: String = line.chars().take(self.window_width as usize).collect();
+        win.attrset(self.color_attribute(color));
+        win.mvaddstr(self.number_of_lines_to_print_text + 1, 0, line);
+        win.attrset(pancurses::A_NORMAL);
+        Ok(())
+    }
+
+    /// Best result in history the finished test beat
+    ///
+    /// Nothing is beaten when the history isn't used, can't be read, or the keys came in too fast.
+    fn find_personal_best(&self) -> Option<history::PersonalBest> {
+        if self.options.no_history || self.stats.suspect {
+            return None;
+        }
+        let best_overall = history::best_wpm_in_file(&self.options.history_file, None).ok().flatten();
+        let best_of_text = history::best_wpm_in_file(&self.options.history_file, Some(&self.history_id())).ok().flatten();
+        history::personal_best(self.stats.wpm, best_overall, best_of_text)
+    }
+
+    /// Announce a new personal best below the typing speed, after the lesson result
+    fn print_personal_best(&self, win: &mut impl TerminalUi) {
+        let line = match self.personal_best {
+            Some(history::PersonalBest::Overall(gain)) => format!(" New personal best! (+{:.1} WPM) ", gain),
+            Some(history::PersonalBest::Text(gain)) => format!(" New best on this text! (+{:.1} WPM) ", gain),
+            None => return,
+        };
+        let column = if self.lesson_passed.is_some() { win.get_cur_yx().1 } else { 0 };
+        let line: String = line.chars().take((self.window_width - column).max(0) as usize).collect();
+        win.attrset(self.color_attribute(Color::Green));
+        win.mvaddstr(self.number_of_lines_to_print_text + 1, column, line);
+        win.attrset(pancurses::A_NORMAL);
+    }
+
+    /// Show the time practiced over the whole history after the other results of the line
+    fn print_practice_total(&self, win: &mut impl TerminalUi) {
+        let Some(summary) = &self.practice_total else {
+            return;
+        };
+        let line = format!(" Total practice: {} ", timer::format_total(summary.practice_seconds));
+        let column = if self.lesson_passed.is_some() || self.personal_best.is_some() { win.get_cur_yx().1 } else { 0 };
+        let line: String = line.chars().take((self.window_width - column).max(0) as usize).collect();
+        win.attrset(self.color_attribute(Color::Cyan));
+        win.mvaddstr(self.number_of_lines_to_print_text + 1, column, line);
+        win.attrset(pancurses::A_NORMAL);
+    }
+
+    /// Note that the test isn't in history, in place of the total practice
+    fn print_not_recorded(&self, win: &mut impl TerminalUi) {
+        if !self.not_recorded {
+            return;
+        }
+        let column = if self.lesson_passed.is_some() { win.get_cur_yx().1 } else { 0 };
+        let line: String = " Not recorded in history ".chars().take((self.window_width - column).max(0) as usize).collect();
+        win.attrset(self.color_attribute(Color::Yellow));
+        win.mvaddstr(self.number_of_lines_to_print_text + 1, column, line);
+        win.attrset(pancurses::A_NORMAL);
+    }
+
+    /// Print the detailed stats below the typing speed, or the keyboard heatmap in their place
+    fn print_result_details(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        // The stats bar on the last line stays
+        let last_line = (self.number_of_lines_to_print_text + 8).min(self.window_height - 2);
+        for line in self.number_of_lines_to_print_text + 5..=last_line {
+            self.clear_line(win, line);
+        }
+        if let Some(url) = &self.share_url {
+            self.print_share_url(win, url);
+            return Ok(());
+        }
+        if self.show_heatmap {
+            return self.print_heatmap(win);
+        }
+        if let Some((_, challenge)) = &self.options.challenge {
+            self.print_challenge(win, challenge);
+            return Ok(());
+        }
+        self.print_latency_stats(win);
+        self.print_slowest_words(win);
+        self.print_wpm_graph(win)?;
+        self.print_problem_bigrams(win);
+        Ok(())
+    }
+
+    /// Print the result of the challenger next to the result of the test in place of the detailed stats
+    fn print_challenge(&self, win: &mut impl TerminalUi, challenge: &Challenge) {
+        let first_line = self.number_of_lines_to_print_text + 5;
+        let player = ChallengeResult::from_stats(&self.stats);
+        let lines = comparison_lines(challenge.challenger(), &player);
+        win.attrset(pancurses::A_NORMAL);
+        // The stats bar on the last line stays
+        for (row, line) in (first_line..self.window_height - 1).zip(lines) {
+            let line: String = line.chars().take(self.window_width as usize).collect();
+            win.mvaddstr(row, 0, line);
+        }
+    }
+
+    /// Print the share URL wrapped to the window in place of the detailed stats, for copying it by hand
+    ///
+    /// The URL takes every line down to the stats bar, a window too small for it cuts it off.
+    fn print_share_url(&self, win: &mut impl TerminalUi, url: &str) {
+        let first_line = self.number_of_lines_to_print_text + 5;
+        let width = self.window_width.max(1) as usize;
+        let wrapped: Vec<char> = word_wrap(url, self.window_width).chars().collect();
+        let lines = std::iter::once(" Can't open the browser, copy the link manually:".to_string())
+            .chain(wrapped.chunks(width).map(|line| line.iter().collect()));
+
+        win.attrset(pancurses::A_NORMAL);
+        // The stats bar on the last line stays
+        for (row, line) in (first_line..self.window_height - 1).zip(lines) {
+            self.clear_line(win, row);
+            win.mvaddstr(row, 0, line);
+        }
+    }
+
+    /// Draw a keyboard with every key colored by the mistakes made on it
+    ///
+    /// Nothing is drawn when the window has no room for the whole keyboard.
+    fn print_heatmap(&self, win: &mut impl TerminalUi) -> AppResult<()> {
+        let first_line = self.number_of_lines_to_print_text + 5;
+        let rows = heatmap_rows(&key_errors(self.session.text(), self.session.mistyped_keys()));
+        if first_line + rows.len() as i32 > self.window_height - 1 || HEATMAP_WIDTH > self.window_width as usize {
+            return Ok(());
+        }
+        for (row_index, row) in rows.into_iter().enumerate() {
+            // Rows are staggered like on a keyboard
+            win.mv(first_line + row_index as i32, row_index as i32);
+            for (key, color) in row {
+                match color {
+                    Some(color) => win.attrset(self.color_attribute(color)),
+                    None => win.attrset(self.color_attribute(Color::Black)),
+                };
+                win.addstr(format!("{:^width$}", key, width = HEATMAP_KEY_WIDTH));
+            }
+        }
+        win.attrset(pancurses::A_NORMAL);
+        Ok(())
+    }
+
+    /// Print the character pairs mistyped the most below the speed graph
+    ///
+    /// Nothing is printed without mistakes or when the window has no room left above the stats bar.
+    fn print_problem_bigrams(&self, win: &mut impl TerminalUi) {
+        let bigrams_line = self.number_of_lines_to_print_text + 8;
+        if bigrams_line >= self.window_height - 1 {
+            return;
+        }
+        let mistake_positions: Vec<usize> = self.session.mistyped_keys().iter()
+            .flat_map(|(index, count)| std::iter::repeat_n(*index, *count as usize))
+            .collect();
+        let bigrams = problem_bigrams(self.session.text(), &mistake_positions, PROBLEM_BIGRAMS_SHOWN);
+        if bigrams.is_empty() {
+            return;
+        }
+        let pairs = bigrams.iter()
+            .map(|(bigram, count)| format!("{} ({})", bigram, count))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let line: String = format!(" Problem pairs: {}", pairs)
+            .chars()
+            .take(self.window_width as usize)
+            .collect();
+        win.attrset(pancurses::A_NORMAL);
+        win.mvaddstr(bigrams_line, 0, line);
+    }
+
+    /// Print the words that took the longest to type on the end screen
+    fn print_slowest_words(&self, win: &mut impl TerminalUi) {
+        let slowest = slowest_words(self.session.word_timings(), SLOWEST_WORDS_SHOWN);
+        if slowest.is_empty() {
+            return;
+        }
+        let words = slowest.iter()
+            .map(|(word, wpm)| format!("{} ({:.0})", word, wpm))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let line: String = format!(" Slowest words: {}", words)
+            .chars()
+            .take(self.window_width as usize)
+            .collect();
+        win.attrset(pancurses::A_NORMAL);
+        win.mvaddstr(self.number_of_lines_to_print_text + 6, 0, line);
+    }
+
     /// Print the bottom stats bar after each run.
-    fn print_stats(&mut self, win: &pancurses::Window) -> AppResult<()> {
-        win.attrset(*self.color.get(&Color::Magenta)
-            .ok_or(Color::Magenta.not_found_err())?);
+    fn print_stats(&mut self, win: &mut impl TerminalUi) -> AppResult<()> {
+        // The message takes the whole bar, the same figures are on the end screen
+        if let Some((color, message)) = &self.status_message {
+            let line: String = format!(" {} ", message).chars().take(self.window_width as usize).collect();
+            self.clear_line(win, self.window_height - 1);
+            win.attrset(self.color_attribute(*color));
+            win.mvaddstr(self.window_height - 1, 0, line);
+            win.attrset(pancurses::A_NORMAL);
+            return Ok(());
+        }
+        win.attrset(self.color_attribute(Color::Magenta));
         win.mvaddstr(
             self.window_height - 1,
             0,
-            format!(" WPM: {:.2} ", self.current_speed_wpm),
+            format!(" Words: {:.2} ", self.stats.words_wpm),
         );
+        win.addstr(format!(" Raw: {:.2} ", self.stats.raw_wpm));
+        win.addstr(format!(" Net: {:.2} ", self.stats.net_wpm));
+        win.addstr(format!(" CPM: {:.2} ", self.stats.cpm));
+
+        win.attrset(self.color_attribute(Color::Green));
+        win.addstr(format!(" Time: {:.2}s ", self.stats.duration));
 
-        win.attrset(*self.color.get(&Color::Green)
-            .ok_or(Color::Green.not_found_err())?);
-        win.addstr(format!(" Time: {:.2}s ", self.time_taken * 60.0));
+        win.attrset(self.color_attribute(Color::Cyan));
+        win.addstr(format!(" Accuracy: {:.2}% ", self.stats.accuracy));
 
-        win.attrset(*self.color.get(&Color::Cyan)
-            .ok_or(Color::Cyan.not_found_err())?);
-        win.addstr(format!(" Accuracy: {:.2}% ", self.accuracy));
+        win.attrset(self.color_attribute(Color::Yellow));
+        win.addstr(format!(" Consistency: {:.2}% ", self.stats.consistency));
+        if self.session.words_skipped_by_key() > 0 {
+            win.addstr(format!(" Skipped: {} ", self.session.words_skipped_by_key()));
+        }
+        if self.stats.suspect {
+            win.attrset(self.color_attribute(Color::Red));
+            win.addstr(" Too fast, saved as suspect ");
+        }
         Ok(())
     }
 
     /// Clear a line on the window
-    fn clear_line(&self, win: &pancurses::Window, line: i32) {
+    fn clear_line(&self, win: &mut impl TerminalUi, line: i32) {
         win.mv(line, 0);
         win.clrtoeol();
     }
 
     /// Reset the data for current typing session.
-    fn reset_test(&mut self) {
+    fn reset_test(&mut self, win: &mut impl TerminalUi) {
         self.mode = 0;
-        self.current_word = "".to_string();
-        self.current_string = "".to_string();
         self.first_key_pressed = false;
-        self.key_strokes = vec![];
-        self.mistyped_keys = vec![];
-        self.start_time = SystemTime::now();
-        self.token_index = 0;
-        self.current_speed_wpm = 0.0;
-        self.total_chars_typed = 0;
-        self.accuracy = 0.0;
-        self.time_taken = 0.0;
+        self.start_time = Instant::now();
+        self.stats = SessionStats::default();
+        self.key_rejected = false;
+        self.pace_index = 0;
+        // Retrying a marathon starts again from its first text
+        let text = if self.marathon_index > 0 {
+            self.marathon_index = 0;
+            let (text, text_id) = self.marathon_texts[0].clone();
+            self.text_id = text_id;
+            text
+        } else {
+            self.session.original_text().to_string()
+        };
+        self.load_text(&text);
         self.test_complete = false;
-        pancurses::curs_set(1);
+        self.lesson_passed = None;
+        self.personal_best = None;
+        self.practice_total = None;
+        self.not_recorded = false;
+        self.show_heatmap = false;
+        self.caps_lock_warning = false;
+        self.status_message = None;
+        self.picking_share_target = false;
+        self.share_url = None;
+        win.curs_set(1);
+    }
+
+    /// Id of the database text being typed, None for daily texts, drills, files and the like
+    fn database_text_id(&self) -> Option<u32> {
+        if self.options.text_source != TextSource::Database {
+            return None;
+        }
+        self.options.transforms.original_id(&self.text_id)?.parse().ok()
     }
 
     /// Load next of previous text snippet from database.
-    fn switch_text(&mut self, win: &pancurses::Window, direction: i32) -> AppResult<()> {
+    fn switch_text(&mut self, win: &mut impl TerminalUi, direction: i32) -> AppResult<()> {
+        // Only database texts have neighbours, the arrows do nothing on other texts,
+        // and a challenge is taken on its own text
+        let Some(text_id) = self.database_text_id().filter(|_| self.options.challenge.is_none()) else {
+            return Ok(());
+        };
         win.clear();
 
-        let text_id = self.text_id.parse::<i32>()? + direction;
-        self.text_id = text_id.to_string();
-        self.text = load_text_from_database(text_id as u32, "data.db")?.0;
-        self.tokens = self.text
-            .split_ascii_whitespace()
-            .map(|s| s.to_string())
-            .collect();
-        self.text = self.tokens.join(" ");
-        self.text_backup = self.text.clone();
-
-        self.text = word_wrap(&self.text, self.window_width)?;
+        let prepared_text = load_text_from_database(text_id.saturating_add_signed(direction), "data.db")
+            .map_err(AppError::from)
+            .and_then(|prepared_text| self.options.transforms.apply(prepared_text));
+        let text = match prepared_text {
+            Ok((text, text_id)) => {
+                self.text_id = text_id;
+                text
+            }
+            Err(e) => return Err(self.error_screen(win, e)),
+        };
+        self.load_text(&text);
+        self.attempts = 0;
+        if let Some(first_text) = self.marathon_texts.first_mut() {
+            *first_text = (text, self.text_id.clone());
+        }
+        self.load_ghost();
 
-        self.reset_test();
+        self.reset_test(win);
         self.setup_print(win)?;
         self.update_state(win)?;
         Ok(())
     }
 }
 
+const HEADER_TITLE: &str = " RSTYPE ";
+// Space reserved at the end of the header for the live WPM
+const HEADER_WPM_WIDTH: i32 = 14;
+// Space reserved for the shortened live WPM, like " 123wpm"
+const HEADER_SHORT_WPM_WIDTH: i32 = 7;
+// Width of the " MM:SS " timer
+const HEADER_TIMER_WIDTH: i32 = 7;
+/// Narrowest window the text and the stats can be laid out in
+pub const MIN_WINDOW_WIDTH: i32 = 20;
+
+/// Columns of the header items, `None` when an optional item doesn't fit
+#[derive(Debug, PartialEq)]
+pub struct HeaderLayout {
+    pub title: Option<i32>,
+    pub timer: Option<i32>,
+    pub wpm: i32,
+    // The live WPM is shown without decimals and a shorter label
+    pub short_wpm: bool,
+}
+
+/// Lay out the header row.
+///
+/// The text ID and live WPM are always shown. When the window is too narrow
+/// the title is dropped first, then the timer, then the WPM is shortened.
+/// # Arguments
+/// * `window_width` - Width of the terminal
+/// * `id_width` - Width of the text ID label at the start of the row
+pub fn header_layout(window_width: i32, id_width: i32) -> HeaderLayout {
+    let short_wpm = window_width - HEADER_WPM_WIDTH < id_width;
+    let wpm_x = if short_wpm {
+        window_width - HEADER_SHORT_WPM_WIDTH
+    } else {
+        window_width - HEADER_WPM_WIDTH
+    };
+    let timer_x = wpm_x - HEADER_TIMER_WIDTH;
+    let timer = (!short_wpm && timer_x >= id_width).then_some(timer_x);
+
+    let title_width = HEADER_TITLE.len() as i32;
+    let title_x = window_width / 2 - title_width / 2;
+    let title_end = timer.unwrap_or(wpm_x);
+    let title = (title_x >= id_width && title_x + title_width <= title_end).then_some(title_x);
+
+    HeaderLayout { title, timer, wpm: wpm_x, short_wpm }
+}
+
 /// Get the height and width of terminal
 ///
 /// # Arguments
 /// * `win` - The curses window
 /// # Returns
 /// * `(i32, i32)` containing the height and width of the terminal
-fn get_dimensions(win: &pancurses::Window) -> (i32, i32) {
+fn get_dimensions(win: &impl TerminalUi) -> (i32, i32) {
     win.get_max_yx()
 }
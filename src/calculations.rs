@@ -1,29 +1,28 @@
 use crate::timer;
-use std::cmp::min;
-use std::time::{SystemTime, SystemTimeError};
-use crate::AppError;
+use std::collections::HashMap;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// Return index at which there is a change in strings.
 ///
 /// This is used to determine the index up to which text must be dimmed and
 /// after which must be colored red (indicating mismatch).
+///
+/// The index is counted in characters and both strings are walked only once.
 pub fn first_index_at_which_strings_differ(string1: &str, string2: &str) -> usize {
-    let length = min(string1.len(), string2.len());
-    let string1_chars: Vec<char> = string1.chars().collect();
-    let string2_chars: Vec<char> = string2.chars().collect();
-
-    for index in 0..length {
-        if string1_chars[index] != string2_chars[index] {
-            return index;
-        }
-    }
-    length
+    string1.chars()
+        .zip(string2.chars())
+        .take_while(|(a, b)| a == b)
+        .count()
 }
 
 /// Count the number of lines required for displaying text.
 pub fn number_of_lines_to_fit_text_in_window(string: &str, window_width: i32) -> i32 {
-    let n = string.len() as f64 / window_width as f64;
-    f64::ceil(n) as i32
+    char_positions(string, window_width)
+        .last()
+        .map(|(row, _)| row + 1)
+        .unwrap_or(0)
 }
 
 /// Calculate speed in words per minute.
@@ -32,55 +31,664 @@ pub fn number_of_lines_to_fit_text_in_window(string: &str, window_width: i32) ->
 /// * `start_time` - Time at which typing started the sample text.
 /// # Returns:
 /// * `f64` Speed in words per minute
-pub fn speed_in_wpm(text: &[String], start_time: SystemTime) -> Result<f64, SystemTimeError> {
-    let time_taken = timer::get_elapsed_minutes_since_first_keypress(start_time)?;
-    Ok(text.len() as f64 / time_taken)
+pub fn speed_in_wpm(text: &[String], start_time: Instant) -> f64 {
+    let time_taken = timer::get_elapsed_minutes_since_first_keypress(start_time);
+    speed_in_wpm_with_minutes(text, time_taken)
+}
+
+/// Calculate speed in words per minute for a known duration.
+/// # Arguments:
+/// * `text` - Text to calculate speed for
+/// * `minutes` - Time taken to type the text
+/// # Returns:
+/// * `f64` Speed in words per minute, 0 if no time has passed
+pub fn speed_in_wpm_with_minutes(text: &[String], minutes: f64) -> f64 {
+    words_per_minute(text.len(), minutes)
+}
+
+/// Calculate speed in words per minute from the number of completed words.
+///
+/// Used for the live speed shown while typing, so it matches `speed_in_wpm`
+/// once every word of the text is completed.
+/// # Returns:
+/// * `f64` Speed in words per minute, 0 if no time has passed
+pub fn words_per_minute(completed_words: usize, minutes: f64) -> f64 {
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+    completed_words as f64 / minutes
+}
+
+/// Number of characters that make up one standard word.
+pub const CHARS_PER_WORD: f64 = 5.0;
+
+/// Calculate gross speed in words per minute, counting every 5 typed characters as a word.
+/// # Arguments:
+/// * `chars_typed` - Number of characters typed, including mistakes
+/// * `minutes` - Time taken to type them
+/// # Returns:
+/// * `f64` Gross speed in words per minute, 0 if no time has passed
+pub fn gross_wpm(chars_typed: usize, minutes: f64) -> f64 {
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+    chars_typed as f64 / CHARS_PER_WORD / minutes
+}
+
+/// Calculate net speed in words per minute, the gross speed minus errors per minute.
+/// # Arguments:
+/// * `chars_typed` - Number of characters typed, including mistakes
+/// * `errors` - Number of mistyped characters
+/// * `minutes` - Time taken to type them
+/// # Returns:
+/// * `f64` Net speed in words per minute, never below 0
+pub fn net_wpm(chars_typed: usize, errors: usize, minutes: f64) -> f64 {
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+    (gross_wpm(chars_typed, minutes) - errors as f64 / minutes).max(0.0)
+}
+
+/// Calculate speed in characters per minute.
+/// # Arguments:
+/// * `chars_typed` - Number of correctly typed characters
+/// * `minutes` - Time taken to type them
+/// # Returns:
+/// * `f64` Characters per minute, 0 if no time has passed
+pub fn chars_per_minute(chars_typed: usize, minutes: f64) -> f64 {
+    if minutes <= 0.0 {
+        return 0.0;
+    }
+    chars_typed as f64 / minutes
+}
+
+/// Calculate speed in words per minute over only the most recent keystrokes.
+///
+/// Every keystroke counts as a character and every 5 characters as a word.
+/// At the start of a test the window is shortened to the time elapsed so far.
+/// # Arguments:
+/// * `keystroke_times` - Time since the start of the test of every keystroke
+/// * `elapsed` - Time since the start of the test
+/// * `window` - How far back to look for keystrokes
+/// # Returns:
+/// * `f64` Speed in words per minute, 0 if no time has passed
+pub fn rolling_wpm(keystroke_times: &[Duration], elapsed: Duration, window: Duration) -> f64 {
+    let window = window.min(elapsed);
+    if window.is_zero() {
+        return 0.0;
+    }
+    let window_start = elapsed - window;
+    let keystrokes = keystroke_times.iter()
+        .filter(|time| **time >= window_start && **time <= elapsed)
+        .count();
+    gross_wpm(keystrokes, window.as_secs_f64() / 60.0)
+}
+
+/// Character index a typist going steadily at the target speed would have reached.
+/// # Arguments:
+/// * `target_wpm` - Target speed, counting every 5 characters as a word
+/// * `elapsed_minutes` - Time since the test started
+/// * `text_length` - Number of characters in the text
+/// # Returns:
+/// * `usize` Index of the next character to type at that speed, at most `text_length`
+pub fn pace_char_index(target_wpm: f64, elapsed_minutes: f64, text_length: usize) -> usize {
+    if target_wpm <= 0.0 || elapsed_minutes <= 0.0 {
+        return 0;
+    }
+    let chars_typed = (target_wpm * CHARS_PER_WORD * elapsed_minutes).floor() as usize;
+    chars_typed.min(text_length)
+}
+
+/// Screen cell of the pace caret, relative to the start of the text.
+/// # Arguments:
+/// * `text` - Word wrapped text
+/// * `window_width` - Width of the window the text is wrapped to
+/// * `target_wpm` - Target speed, counting every 5 characters as a word
+/// * `elapsed_minutes` - Time since the test started
+/// # Returns:
+/// * `Option<(usize, (i32, i32))>` Character index and its (row, column),
+///   None once the pace has reached the end of the text
+pub fn pace_position(
+    text: &str,
+    window_width: i32,
+    target_wpm: f64,
+    elapsed_minutes: f64,
+) -> Option<(usize, (i32, i32))> {
+    let positions = char_positions(text, window_width);
+    let index = pace_char_index(target_wpm, elapsed_minutes, positions.len());
+    positions.get(index).map(|position| (index, *position))
+}
+
+/// Percentage of the text typed correctly, rounded down.
+///
+/// Only reaches 100 once the whole text is typed.
+pub fn progress_percent(typed_length: usize, text_length: usize) -> usize {
+    if text_length == 0 {
+        return 0;
+    }
+    typed_length.min(text_length) * 100 / text_length
+}
+
+/// Render a progress bar like `[=====     ]  50%` that is exactly `width` characters wide.
+///
+/// Returns only the percentage if the bar itself doesn't fit.
+pub fn progress_bar(typed_length: usize, text_length: usize, width: usize) -> String {
+    let percent = progress_percent(typed_length, text_length);
+    let label = format!(" {:>3}%", percent);
+    // Brackets and the percentage take up part of the width
+    let Some(bar_width) = width.checked_sub(label.len() + 2).filter(|w| *w > 0) else {
+        return label.trim_start().chars().take(width).collect();
+    };
+    let filled = bar_width * percent / 100;
+    format!("[{}{}]{}", "=".repeat(filled), " ".repeat(bar_width - filled), label)
+}
+
+/// Speed at which a single word was typed, counting 5 characters as a word.
+/// # Arguments:
+/// * `word` - The typed word
+/// * `seconds` - Time taken to type the word
+pub fn word_wpm(word: &str, seconds: f64) -> f64 {
+    gross_wpm(word.chars().count(), seconds / 60.0)
+}
+
+/// Find the words that were typed the slowest.
+/// # Arguments:
+/// * `word_timings` - Every typed word with the seconds it took
+/// * `count` - Maximum number of words to return
+/// # Returns:
+/// * `Vec<(&str, f64)>` Words with their speed in words per minute, slowest first
+pub fn slowest_words(word_timings: &[(String, f64)], count: usize) -> Vec<(&str, f64)> {
+    let mut speeds: Vec<(&str, f64)> = word_timings.iter()
+        .map(|(word, seconds)| (word.as_str(), word_wpm(word, *seconds)))
+        .collect();
+    speeds.sort_by(|a, b| a.1.total_cmp(&b.1));
+    speeds.truncate(count);
+    speeds
+}
+
+/// Number of letters in a row typed in the wrong case that suggests Caps Lock is on
+pub const CAPS_LOCK_MIN_LETTERS: usize = 3;
+
+/// Whether the last typed letters look like they were typed with Caps Lock on.
+///
+/// That is the case when the last `CAPS_LOCK_MIN_LETTERS` letters are the expected
+/// lowercase letters typed in uppercase. Characters that aren't letters are skipped,
+/// so one correctly cased letter is enough to clear the warning.
+pub fn looks_like_caps_lock(typed: &str, expected: &str) -> bool {
+    let letters: Vec<(char, char)> = typed.chars()
+        .zip(expected.chars())
+        .filter(|(typed, _)| typed.is_alphabetic())
+        .collect();
+    letters.len() >= CAPS_LOCK_MIN_LETTERS
+        && letters.iter().rev().take(CAPS_LOCK_MIN_LETTERS).all(|(typed, expected)| {
+            typed.is_uppercase() && expected.is_lowercase() && typed.to_lowercase().eq(expected.to_lowercase())
+        })
+}
+
+/// Keys closer together than this can't be typed by hand
+pub const PASTE_MAX_INTERVAL: Duration = Duration::from_millis(15);
+
+/// Number of intervals in a row below `PASTE_MAX_INTERVAL` that marks the input as pasted
+pub const PASTE_MIN_BURST: usize = 10;
+
+/// Whether keystrokes came in a burst too fast to be typed by hand.
+///
+/// Pasting a text into the terminal sends every character at almost the same time.
+/// # Arguments:
+/// * `keystroke_times` - Time of every keystroke since the start of the test
+/// * `max_interval` - Intervals shorter than this are too fast
+/// * `min_burst` - Number of too fast intervals in a row needed to flag the input
+pub fn is_pasted_input(keystroke_times: &[Duration], max_interval: Duration, min_burst: usize) -> bool {
+    let mut burst = 0;
+    for pair in keystroke_times.windows(2) {
+        if pair[1].saturating_sub(pair[0]) < max_interval {
+            burst += 1;
+            if burst >= min_burst {
+                return true;
+            }
+        } else {
+            burst = 0;
+        }
+    }
+    false
+}
+
+/// Longest wait between two keys of a replay, longer pauses are cut to it
+pub const REPLAY_MAX_DELAY: Duration = Duration::from_secs(3);
+
+/// Time to wait before playing each key of a replay.
+///
+/// The first key is played at once, the others after the time since the previous key,
+/// cut to `max_delay`. A key recorded before the previous one doesn't wait.
+/// # Arguments:
+/// * `keystroke_times` - Time of every keystroke since the start of the test
+/// * `max_delay` - Longest wait before a key
+pub fn replay_delays(keystroke_times: &[Duration], max_delay: Duration) -> Vec<Duration> {
+    let mut previous_time = keystroke_times.first().copied().unwrap_or_default();
+    keystroke_times.iter()
+        .map(|time| {
+            let delay = time.saturating_sub(previous_time).min(max_delay);
+            previous_time = *time;
+            delay
+        })
+        .collect()
+}
+
+/// Find the character pairs that were mistyped the most.
+///
+/// A mistake at position `i` counts toward the pair of characters `i - 1` and `i`.
+/// Pairs with a space in them are left out, as are mistakes on the first character.
+/// # Arguments:
+/// * `text` - Text the positions point into
+/// * `mistake_positions` - Character index of every mistake, repeated for repeated mistakes
+/// * `count` - Maximum number of pairs to return
+/// # Returns:
+/// * `Vec<(String, usize)>` Pairs with their number of mistakes, most mistyped first
+pub fn problem_bigrams(text: &str, mistake_positions: &[usize], count: usize) -> Vec<(String, usize)> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut totals: HashMap<String, usize> = HashMap::new();
+    for &position in mistake_positions {
+        let previous = position.checked_sub(1).and_then(|index| chars.get(index));
+        let (Some(&first), Some(&second)) = (previous, chars.get(position)) else {
+            continue;
+        };
+        if !first.is_whitespace() && !second.is_whitespace() {
+            *totals.entry(format!("{}{}", first, second)).or_insert(0) += 1;
+        }
+    }
+    let mut bigrams: Vec<(String, usize)> = totals.into_iter().collect();
+    bigrams.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    bigrams.truncate(count);
+    bigrams
+}
+
+/// Summary of the time between consecutive keystrokes
+#[derive(Clone, Debug, PartialEq)]
+pub struct LatencyStats {
+    pub mean_ms: f64,
+    pub median_ms: f64,
+    pub longest_pause_ms: f64,
+    // Index of the keystroke that followed the longest pause
+    pub longest_pause_index: usize,
+}
+
+/// Calculate rhythm stats from the times at which keys were pressed.
+/// # Arguments:
+/// * `keystroke_times` - Time since the start of the test of every keystroke, in order
+/// # Returns:
+/// * `Option<LatencyStats>` - `None` when there are fewer than two keystrokes
+pub fn latency_stats(keystroke_times: &[Duration]) -> Option<LatencyStats> {
+    let intervals: Vec<f64> = keystroke_times
+        .windows(2)
+        .map(|pair| pair[1].saturating_sub(pair[0]).as_secs_f64() * 1000.0)
+        .collect();
+    if intervals.is_empty() {
+        return None;
+    }
+
+    let mean_ms = intervals.iter().sum::<f64>() / intervals.len() as f64;
+
+    let mut sorted = intervals.clone();
+    sorted.sort_by(f64::total_cmp);
+    let middle = sorted.len() / 2;
+    let median_ms = if sorted.len().is_multiple_of(2) {
+        (sorted[middle - 1] + sorted[middle]) / 2.0
+    } else {
+        sorted[middle]
+    };
+
+    // The first of several equally long pauses wins
+    let (pause_index, longest_pause_ms) = intervals.iter()
+        .enumerate()
+        .fold((0, f64::MIN), |longest, (index, interval)| {
+            if *interval > longest.1 { (index, *interval) } else { longest }
+        });
+
+    Some(LatencyStats {
+        mean_ms,
+        median_ms,
+        longest_pause_ms,
+        longest_pause_index: pause_index + 1,
+    })
+}
+
+/// Calculate the speed within consecutive time buckets of a test.
+///
+/// Every keystroke counts as a character and every 5 characters as a word.
+/// The last bucket ends at the last keystroke, so it may be shorter.
+/// # Arguments:
+/// * `keystroke_times` - Time since the start of the test of every keystroke, in order
+/// * `bucket` - Length of each bucket
+/// # Returns:
+/// * `Vec<f64>` Speed in words per minute for every bucket
+pub fn wpm_buckets(keystroke_times: &[Duration], bucket: Duration) -> Vec<f64> {
+    let Some(last) = keystroke_times.last() else {
+        return vec![];
+    };
+    if bucket.is_zero() {
+        return vec![];
+    }
+    let bucket_count = (last.as_nanos() / bucket.as_nanos()) as usize + 1;
+    let mut keystrokes = vec![0; bucket_count];
+    for time in keystroke_times {
+        keystrokes[(time.as_nanos() / bucket.as_nanos()) as usize] += 1;
+    }
+
+    keystrokes.iter()
+        .enumerate()
+        .map(|(index, count)| {
+            let bucket_start = bucket * index as u32;
+            let bucket_length = bucket.min(last.saturating_sub(bucket_start));
+            // A final bucket holding only the last keystroke lasts no time at all
+            gross_wpm(*count, bucket_length.max(Duration::from_secs(1)).as_secs_f64() / 60.0)
+        })
+        .collect()
 }
 
+/// Block characters of a sparkline, from the lowest to the highest value
+const SPARKLINE_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Render values as a line of block characters, the tallest for the highest value.
+///
+/// The line is resized to exactly `width` characters, averaging neighbouring
+/// values when there are more values than characters.
+pub fn sparkline(values: &[f64], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let resized = resize_values(values, width);
+    let max = resized.iter().cloned().fold(0.0, f64::max);
+    resized.iter()
+        .map(|value| {
+            if max <= 0.0 {
+                return SPARKLINE_BLOCKS[0];
+            }
+            let level = (value / max * (SPARKLINE_BLOCKS.len() - 1) as f64).round() as usize;
+            SPARKLINE_BLOCKS[level.min(SPARKLINE_BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Render values as a line of block characters scaled between their lowest and highest value.
+///
+/// Small changes stand out better than with `sparkline`, which starts at zero.
+/// There is one character per value, neighbouring values are averaged when there are
+/// more values than `width`. Equal values are drawn as a flat line in the middle.
+pub fn trend_sparkline(values: &[f64], width: usize) -> String {
+    if values.is_empty() || width == 0 {
+        return String::new();
+    }
+
+    let resized = resize_values(values, width.min(values.len()));
+    let min = resized.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = resized.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    resized.iter()
+        .map(|value| {
+            if max - min <= f64::EPSILON {
+                return SPARKLINE_BLOCKS[SPARKLINE_BLOCKS.len() / 2];
+            }
+            let level = ((value - min) / (max - min) * (SPARKLINE_BLOCKS.len() - 1) as f64).round() as usize;
+            SPARKLINE_BLOCKS[level.min(SPARKLINE_BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Stretch or shrink values to `width`, averaging the values that fall on the same column
+fn resize_values(values: &[f64], width: usize) -> Vec<f64> {
+    (0..width)
+        .map(|column| {
+            let start = column * values.len() / width;
+            let end = ((column + 1) * values.len() / width).max(start + 1);
+            values[start..end].iter().sum::<f64>() / (end - start) as f64
+        })
+        .collect()
+}
+
+/// Pauses between keystrokes longer than this many seconds are not counted for consistency.
+pub const CONSISTENCY_MAX_PAUSE_SECONDS: f64 = 5.0;
+
+/// Calculate how steady the typing rhythm was.
+///
+/// Computed as `100 * (1 - coefficient of variation)` of the intervals between
+/// keystrokes, clamped to the range 0-100.
+/// # Arguments:
+/// * `intervals` - Seconds between consecutive keystrokes
+/// # Returns:
+/// * `f64` Consistency in percent, 100 when there are fewer than 2 intervals
+pub fn consistency(intervals: &[f64]) -> f64 {
+    if intervals.len() < 2 {
+        return 100.0;
+    }
+    let count = intervals.len() as f64;
+    let mean = intervals.iter().sum::<f64>() / count;
+    if mean <= 0.0 {
+        return 100.0;
+    }
+    let variance = intervals.iter()
+        .map(|interval| (interval - mean).powi(2))
+        .sum::<f64>() / count;
+    let coefficient_of_variation = variance.sqrt() / mean;
+    (100.0 * (1.0 - coefficient_of_variation)).clamp(0.0, 100.0)
+}
+
+/// Which speed figure is reported as the result of a test.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum WpmFormula {
+    /// Number of words in the text per minute, the figure history was always saved with
+    #[default]
+    Words,
+    /// Every typed character divided by 5 per minute, including erased ones, the Raw figure of the stats bar
+    #[value(alias = "raw")]
+    Gross,
+    /// Gross speed minus the mistakes per minute
+    Net,
+}
+
+/// Percentage of typed characters that were correct.
+///
+/// The result is clamped to the range 0-100 and is 0 when nothing was typed.
 pub fn accuracy(total_chars_typed: usize, wrongly_typed: usize) -> f64 {
-    ((total_chars_typed - wrongly_typed) as f64 / total_chars_typed as f64) * 100.0
+    if total_chars_typed == 0 {
+        return 0.0;
+    }
+    let correctly_typed = total_chars_typed.saturating_sub(wrongly_typed);
+    (correctly_typed as f64 / total_chars_typed as f64) * 100.0
 }
 
-// Since index is copy value, we can modify it without affecting the original value
-pub fn get_space_count_after_ith_word(mut index: usize, text: &str) -> Result<usize, AppError> {
-    let mut count = 0;
-    while index < text.len() && text.chars().nth(index)
-        .ok_or(AppError::NoIndexFoundError(index))? == ' ' {
-        index += 1;
-        count += 1;
+/// Index of the word that the character at `char_index` of `text` belongs to.
+///
+/// Spaces, including the padding added by `word_wrap`, belong to the word before them.
+pub fn word_index_at(text: &str, char_index: usize) -> usize {
+    let mut previous = ' ';
+    let mut word_starts: usize = 0;
+    for c in text.chars().take(char_index + 1) {
+        if c != ' ' && previous == ' ' {
+            word_starts += 1;
+        }
+        previous = c;
+    }
+    word_starts.saturating_sub(1)
+}
+
+/// Pick the text difficulty that suits a typist's recent results.
+///
+/// Faster typists get harder texts, and a low accuracy takes the difficulty
+/// one step down. The result is always within the range 1-5.
+pub fn difficulty_for_stats(average_wpm: f64, average_accuracy: f64) -> u32 {
+    let difficulty = match average_wpm {
+        wpm if wpm < 30.0 => 1,
+        wpm if wpm < 45.0 => 2,
+        wpm if wpm < 60.0 => 3,
+        wpm if wpm < 80.0 => 4,
+        _ => 5,
+    };
+    if average_accuracy < 90.0 {
+        (difficulty - 1).max(1)
+    } else {
+        difficulty
     }
-    Ok(count)
+}
+
+/// Count the spaces that follow the given character index in `text`.
+///
+/// These are the padding spaces inserted by `word_wrap` after a word.
+/// An index past the end of the text has no spaces after it.
+pub fn get_space_count_after_ith_word(index: usize, text: &str) -> usize {
+    text.chars()
+        .skip(index)
+        .take_while(|c| *c == ' ')
+        .count()
 }
 
 /// Wrap text on the screen according to the window width.
 ///
 /// Returns text with extra spaces which makes the string word wrap.
-pub fn word_wrap(text: &str, width: i32) -> Result<String, AppError> {
-    // For the end of each line, move backwards until you find a space.
-    // When you do, append those many spaces after the single space.
-    let mut text = text.to_string();
-    for line in 1..=number_of_lines_to_fit_text_in_window(&text, width) + 1 {
-        // Current line fits in the window
-        if line * width >= text.len() as i32 {
-            continue;
+/// Widths are measured in terminal columns, so full-width characters take two cells.
+pub fn word_wrap(text: &str, width: i32) -> String {
+    // Whenever the next word doesn't fit on the current line, the single space
+    // before it is replaced with enough spaces to fill the rest of the line.
+    let width = width.max(1) as usize;
+    let mut wrapped = String::with_capacity(text.len());
+    let mut column = 0;
+    let word_count = text.split(' ').count();
+    for (i, word) in text.split(' ').enumerate() {
+        if i > 0 {
+            // Only the last word may end in the last cell of a line, otherwise
+            // the space after it would start the next line
+            let end = column + 1 + word.width();
+            if end < width || (end == width && i == word_count - 1) {
+                wrapped.push(' ');
+                column += 1;
+            } else {
+                let space_count = (width - column).max(1);
+                wrapped.push_str(&" ".repeat(space_count));
+                column = (column + space_count) % width;
+            }
+        }
+        for c in word.chars() {
+            column = advance_column(column, char_width(c), width);
         }
+        wrapped.push_str(word);
+    }
+    wrapped
+}
 
-        // Last cell of that line
-        let mut index: usize = (line * width - 1) as usize;
+/// Split text into the lines it takes on the screen, without the padding added by `word_wrap`
+pub fn wrap_lines(text: &str, width: i32) -> Vec<String> {
+    let wrapped = word_wrap(text, width);
+    let mut lines: Vec<String> = vec![];
+    for (c, (row, _)) in wrapped.chars().zip(char_positions(&wrapped, width)) {
+        if lines.len() <= row as usize {
+            lines.push(String::new());
+        }
+        lines[row as usize].push(c);
+    }
+    lines.iter().map(|line| line.trim().to_string()).collect()
+}
 
-        // Continue if already a space
-        if text.chars().nth(index).ok_or(AppError::NoIndexFoundError(index))? == ' ' {
-            continue;
+/// Character indices of every line `text` takes on the screen
+/// # Arguments:
+/// * `text` - Text already wrapped with `word_wrap`
+/// * `window_width` - Width the text was wrapped to
+pub fn line_ranges(text: &str, window_width: i32) -> Vec<Range<usize>> {
+    let mut lines: Vec<Range<usize>> = vec![];
+    for (index, (row, _)) in char_positions(text, window_width).into_iter().enumerate() {
+        match lines.get_mut(row as usize) {
+            Some(line) => line.end = index + 1,
+            None => lines.push(index..index + 1),
+        }
+    }
+    lines
+}
+
+/// First row of the text shown when only `visible_rows` of it fit on the screen.
+///
+/// The row being typed stays in view with `context_rows` of already typed text above it,
+/// and the text never scrolls past its last row.
+/// # Arguments:
+/// * `cursor_row` - Row of the text being typed
+/// * `visible_rows` - Number of rows shown
+/// * `total_rows` - Number of rows of the whole text
+/// * `context_rows` - Number of rows kept above the row being typed
+pub fn viewport_start(cursor_row: i32, visible_rows: i32, total_rows: i32, context_rows: i32) -> i32 {
+    let context_rows = context_rows.min(visible_rows - 1).max(0);
+    (cursor_row - context_rows).clamp(0, (total_rows - visible_rows).max(0))
+}
+
+/// Compute the `(row, column)` cell of every character of `text`.
+///
+/// This follows the way curses wraps a string written in one go, so wide
+/// characters that don't fit at the end of a line are moved to the next one.
+pub fn char_positions(text: &str, window_width: i32) -> Vec<(i32, i32)> {
+    let width = window_width.max(1) as usize;
+    let mut positions = Vec::with_capacity(text.len());
+    let (mut row, mut column) = (0, 0);
+    for c in text.chars() {
+        let char_width = char_width(c);
+        if column + char_width > width {
+            row += 1;
+            column = 0;
         }
+        positions.push((row, column as i32));
+        column += char_width;
+        if column >= width {
+            row += 1;
+            column = 0;
+        }
+    }
+    positions
+}
 
-        index = text[0..index].rfind(' ').ok_or(AppError::NoIndexFoundError(index))?;
+fn char_width(c: char) -> usize {
+    c.width().unwrap_or(0)
+}
 
-        let space_count = line * width - index as i32;
-        let space_string = " ".repeat(space_count as usize);
+fn advance_column(column: usize, char_width: usize, width: usize) -> usize {
+    let column = if column + char_width > width { 0 } else { column };
+    (column + char_width) % width
+}
 
-        let first = text[0..index].to_string();
-        let third = text[index + 1..text.len()].to_string();
-        text = format!("{}{}{}", first, space_string, third);
+/// Find where a character of the original text ended up after word wrapping.
+///
+/// Word wrapping only inserts spaces, so characters are matched in order and
+/// the inserted ones are skipped.
+/// # Arguments:
+/// * `original` - Text before word wrapping
+/// * `wrapped` - The same text after `word_wrap`
+/// * `original_index` - Character index in the original text
+/// # Returns:
+/// * `usize` Character index in the wrapped text, its length if past the end
+pub fn wrapped_char_index(original: &str, wrapped: &str, original_index: usize) -> usize {
+    let mut remaining = original.chars().enumerate().peekable();
+    for (index, c) in wrapped.chars().enumerate() {
+        if let Some(&(next_index, next_char)) = remaining.peek() {
+            if next_char == c {
+                if next_index == original_index {
+                    return index;
+                }
+                remaining.next();
+            }
+        }
     }
-    Ok(text)
+    wrapped.chars().count()
+}
+
+/// Convert an index counted in characters into a byte offset within `text`.
+///
+/// Indices past the end of the text are clamped to its byte length.
+pub fn char_index_to_byte_offset(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map(|(offset, _)| offset)
+        .unwrap_or(text.len())
+}
+
+/// Slice `text` between two character indices.
+///
+/// Both bounds are clamped to the text, so this never splits a UTF-8 sequence.
+pub fn slice_by_char_indices(text: &str, start: usize, end: usize) -> &str {
+    let start = char_index_to_byte_offset(text, start);
+    let end = char_index_to_byte_offset(text, end).max(start);
+    &text[start..end]
 }
@@ -0,0 +1,41 @@
+use crate::calculations::sparkline;
+use crate::session::SessionStats;
+use unicode_width::UnicodeWidthStr;
+
+/// Columns taken by the result card with its border, narrow enough to paste into chat apps
+pub const RESULT_CARD_WIDTH: usize = 40;
+
+/// Render the results of a test as a small text card with a border
+///
+/// Every line is exactly `RESULT_CARD_WIDTH` columns wide, a text id too long for
+/// the card is cut off. The speed graph is left out when there is nothing to draw.
+/// # Arguments:
+/// * `stats` - Results of the finished test
+/// # Returns:
+/// * `Vec<String>` - Lines of the card, without line breaks
+pub fn result_card(stats: &SessionStats) -> Vec<String> {
+    // Two columns of border and two of padding around the content
+    let inner_width = RESULT_CARD_WIDTH - 4;
+    let mut lines = vec![
+        format!("╭{}╮", "─".repeat(RESULT_CARD_WIDTH - 2)),
+        card_row("rstype", &stats.timestamp.format("%Y-%m-%d %H:%M").to_string(), inner_width),
+        card_row("", "", inner_width),
+        card_row("Speed", &format!("{:.1} WPM", stats.wpm), inner_width),
+        card_row("Accuracy", &format!("{:.1}%", stats.accuracy), inner_width),
+        card_row("Time", &format!("{:.0}s", stats.duration), inner_width),
+        card_row("Text", &stats.text_id, inner_width),
+    ];
+    if !stats.wpm_over_time.is_empty() {
+        lines.push(card_row("", "", inner_width));
+        lines.push(card_row(&sparkline(&stats.wpm_over_time, inner_width), "", inner_width));
+    }
+    lines.push(format!("╰{}╯", "─".repeat(RESULT_CARD_WIDTH - 2)));
+    lines
+}
+
+/// Line of the card with a label on the left and a value on the right
+fn card_row(label: &str, value: &str, inner_width: usize) -> String {
+    let value: String = value.chars().take(inner_width.saturating_sub(label.width() + 1)).collect();
+    let gap = inner_width.saturating_sub(label.width() + value.width());
+    format!("│ {}{}{} │", label, " ".repeat(gap), value)
+}
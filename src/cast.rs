@@ -0,0 +1,128 @@
+use std::time::Duration;
+use pancurses::{chtype, Input};
+use serde::Serialize;
+use crate::app::{App, AppOptions, Color};
+use crate::session::SessionStats;
+use crate::terminal::{FakeTerminal, TerminalUi, WIDE_CHAR_CONTINUATION};
+use crate::theme::{ColorMode, Theme, DEFAULT_COLOR};
+use crate::{AppResult, PreparedText};
+
+/// First line of an asciinema v2 file
+#[derive(Serialize)]
+struct CastHeader<'a> {
+    version: u8,
+    width: i32,
+    height: i32,
+    // Unix time the test was finished
+    timestamp: i64,
+    title: &'a str,
+}
+
+/// Record the replay of a test as an asciinema v2 file
+///
+/// The replay is drawn like the one of the end screen, with one event per key
+/// redrawing the whole screen, timed like the in-app replay.
+/// # Arguments:
+/// * `prepared_text` - Text of the page the keys were typed on, with its id
+/// * `options` - Options the test was typed with
+/// * `stats` - Results of the test, shown on the screen of the replay
+/// * `key_strokes` - Keys of the page with the time since the start of the test
+/// * `window_size` - Height and width of the terminal the test was typed in
+/// # Returns:
+/// * `String` - Content of the .cast file, one JSON value per line
+pub fn record_cast(
+    prepared_text: PreparedText,
+    options: &AppOptions,
+    stats: &SessionStats,
+    key_strokes: &[(Duration, Input)],
+    window_size: (i32, i32),
+) -> AppResult<String> {
+    let (height, width) = window_size;
+    let title = format!("rstype - text {}", prepared_text.1);
+    // The fake terminal takes every color of the theme and the default colors
+    let theme = match options.color_mode {
+        ColorMode::Colorblind => options.theme.colorblind(),
+        _ => options.theme.clone(),
+    }.for_terminal(256, true);
+
+    let mut app = App::for_replay(prepared_text, options.clone(), stats.clone(), key_strokes);
+    let mut term = FakeTerminal::new(height, width);
+    app.initialize_windows(&mut term)?;
+    let mut events = vec![];
+    app.draw_replay(&mut term, |term, time| {
+        events.push((time.as_secs_f64(), "o", screen_to_ansi(term, &theme)));
+    })?;
+
+    let header = CastHeader { version: 2, width, height, timestamp: stats.timestamp.timestamp(), title: &title };
+    let mut cast = serde_json::to_string(&header)?;
+    for event in events {
+        cast.push('\n');
+        cast.push_str(&serde_json::to_string(&event)?);
+    }
+    cast.push('\n');
+    Ok(cast)
+}
+
+/// Escape sequences drawing the whole screen of the terminal, with the cursor where it was left
+/// # Arguments:
+/// * `term` - Terminal the screen was drawn on
+/// * `theme` - Colors of the color pairs the screen was drawn with
+pub fn screen_to_ansi(term: &FakeTerminal, theme: &Theme) -> String {
+    let (height, width) = term.get_max_yx();
+    let pairs = theme.pairs();
+    let mut screen = String::from("\x1b[H\x1b[2J");
+    for row in 0..height {
+        if row > 0 {
+            screen.push_str("\r\n");
+        }
+        // Blank cells at the end of a row are left out
+        let last_column = (0..width)
+            .rev()
+            .find(|column| term.char_at(row, *column) != ' ' || term.attributes_at(row, *column) != pancurses::A_NORMAL)
+            .map_or(0, |column| column + 1);
+        let mut attributes = pancurses::A_NORMAL;
+        for column in 0..last_column {
+            let c = term.char_at(row, column);
+            if c == WIDE_CHAR_CONTINUATION {
+                continue;
+            }
+            if term.attributes_at(row, column) != attributes {
+                attributes = term.attributes_at(row, column);
+                screen.push_str(&select_graphic_rendition(attributes, &pairs));
+            }
+            screen.push(c);
+        }
+        if attributes != pancurses::A_NORMAL {
+            screen.push_str("\x1b[0m");
+        }
+    }
+    let (row, column) = term.get_cur_yx();
+    screen.push_str(&format!("\x1b[{};{}H", row + 1, column + 1));
+    screen.push_str(if term.cursor_visibility == 0 { "\x1b[?25l" } else { "\x1b[?25h" });
+    screen
+}
+
+/// Escape sequence switching to the given curses attributes
+fn select_graphic_rendition(attributes: chtype, pairs: &[(i16, Color, i16, i16)]) -> String {
+    let mut codes = vec!["0".to_string()];
+    for (attribute, code) in [
+        (pancurses::A_BOLD, "1"),
+        (pancurses::A_DIM, "2"),
+        (pancurses::A_UNDERLINE, "4"),
+        (pancurses::A_REVERSE, "7"),
+    ] {
+        if attributes & attribute != 0 {
+            codes.push(code.to_string());
+        }
+    }
+    let pair = (attributes & pancurses::A_COLOR) >> pancurses::A_COLOR.trailing_zeros();
+    if let Some((_, _, foreground, background)) = pairs.iter().find(|(number, ..)| chtype::from(*number as u16) == pair) {
+        if *foreground != DEFAULT_COLOR {
+            codes.push(format!("38;5;{}", foreground));
+        }
+        if *background != DEFAULT_COLOR {
+            codes.push(format!("48;5;{}", background));
+        }
+    }
+    format!("\x1b[{}m", codes.join(";"))
+}
@@ -0,0 +1,162 @@
+use std::fmt::Formatter;
+use std::path::Path;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use crate::replay::checksum;
+use crate::session::SessionStats;
+
+/// Version of the challenge files written by this version, older and newer ones are refused
+pub const CHALLENGE_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum ChallengeError {
+    IoError(String, std::io::Error),
+    JsonError(String, serde_json::Error),
+    UnsupportedVersion(String, u32),
+    TextMismatch(String),
+    NoResults(String),
+}
+
+impl std::fmt::Display for ChallengeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChallengeError::IoError(path, e) => {
+                write!(f, "An IO error occurred for challenge file: {}, {}", path, e)
+            }
+            ChallengeError::JsonError(path, e) => {
+                write!(f, "Not a challenge file: {}, {}", path, e)
+            }
+            ChallengeError::UnsupportedVersion(path, version) => {
+                write!(f, "Challenge file {} has version {}, this version of rstype reads version {}", path, version, CHALLENGE_VERSION)
+            }
+            ChallengeError::TextMismatch(path) => {
+                write!(f, "The text in challenge file {} was changed, it isn't the text the challenge was typed on", path)
+            }
+            ChallengeError::NoResults(path) => {
+                write!(f, "Challenge file {} has no result to beat", path)
+            }
+        }
+    }
+}
+
+/// Result of one player of a challenge
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChallengeResult {
+    pub timestamp: DateTime<Local>,
+    pub wpm: f64,
+    pub accuracy: f64,
+    // Seconds the test took
+    pub duration: f64,
+    pub errors: usize,
+    pub completed: bool,
+}
+
+impl ChallengeResult {
+    pub fn from_stats(stats: &SessionStats) -> ChallengeResult {
+        ChallengeResult {
+            timestamp: stats.timestamp,
+            wpm: stats.wpm,
+            accuracy: stats.accuracy,
+            duration: stats.duration,
+            errors: stats.errors,
+            completed: stats.completed,
+        }
+    }
+}
+
+/// Text to race on with the results of everyone who typed it, kept in a JSON file
+///
+/// The text is part of the file, so it can be typed without the database it came from.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Challenge {
+    pub version: u32,
+    pub text_id: String,
+    pub text: String,
+    // FNV-1a hash of the text, results only compare on the text they were typed on
+    pub text_checksum: String,
+    // Seed the text was picked with, None when it isn't known
+    pub seed: Option<u64>,
+    // Result of whoever sent the challenge, then of everyone who took it
+    pub results: Vec<ChallengeResult>,
+}
+
+impl Challenge {
+    /// Challenge to beat the result of a test
+    /// # Arguments:
+    /// * `text_id` - Id of the text
+    /// * `text` - Text the test was typed on, without the padding of the wrapped lines
+    /// * `seed` - Seed the text was picked with
+    /// * `stats` - Results of the test
+    pub fn new(text_id: &str, text: &str, seed: Option<u64>, stats: &SessionStats) -> Challenge {
+        Challenge {
+            version: CHALLENGE_VERSION,
+            text_id: text_id.to_string(),
+            text: text.to_string(),
+            text_checksum: checksum(text),
+            seed,
+            results: vec![ChallengeResult::from_stats(stats)],
+        }
+    }
+
+    /// Result the challenge was sent with
+    pub fn challenger(&self) -> &ChallengeResult {
+        &self.results[0]
+    }
+}
+
+/// Write a challenge to a JSON file, replacing it
+pub fn save_challenge<P: AsRef<Path>>(path: P, challenge: &Challenge) -> Result<(), ChallengeError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+    let content = serde_json::to_string_pretty(challenge).map_err(|e| ChallengeError::JsonError(get_path(), e))?;
+    std::fs::write(path, content).map_err(|e| ChallengeError::IoError(get_path(), e))
+}
+
+/// Read a challenge file written by `save_challenge`
+///
+/// Files of another version, files whose text doesn't match its checksum and files without a result are refused.
+pub fn load_challenge<P: AsRef<Path>>(path: P) -> Result<Challenge, ChallengeError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+    let content = std::fs::read_to_string(path).map_err(|e| ChallengeError::IoError(get_path(), e))?;
+
+    // The version is checked before the rest, later versions may change the other fields
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    let Version { version } = serde_json::from_str(&content).map_err(|e| ChallengeError::JsonError(get_path(), e))?;
+    if version != CHALLENGE_VERSION {
+        return Err(ChallengeError::UnsupportedVersion(get_path(), version));
+    }
+
+    let challenge: Challenge = serde_json::from_str(&content).map_err(|e| ChallengeError::JsonError(get_path(), e))?;
+    if challenge.text_checksum != checksum(&challenge.text) {
+        return Err(ChallengeError::TextMismatch(get_path()));
+    }
+    if challenge.results.is_empty() {
+        return Err(ChallengeError::NoResults(get_path()));
+    }
+    Ok(challenge)
+}
+
+/// Lines comparing the result of the challenger with the result of the player, side by side
+///
+/// The speed gets the difference to the challenger, positive when the player was faster.
+pub fn comparison_lines(challenger: &ChallengeResult, player: &ChallengeResult) -> Vec<String> {
+    vec![
+        format!(" {:<10} {:>10} {:>10}", "Challenge", "Them", "You"),
+        format!(
+            " {:<10} {:>10.2} {:>10.2}  {:+.2}",
+            "WPM", challenger.wpm, player.wpm, player.wpm - challenger.wpm,
+        ),
+        format!(
+            " {:<10} {:>10} {:>10}",
+            "Accuracy", format!("{:.1}%", challenger.accuracy), format!("{:.1}%", player.accuracy),
+        ),
+        format!(
+            " {:<10} {:>10} {:>10}",
+            "Time", format!("{:.0}s", challenger.duration), format!("{:.0}s", player.duration),
+        ),
+    ]
+}
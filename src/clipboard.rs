@@ -0,0 +1,53 @@
+use std::fmt::Formatter;
+
+#[derive(Debug)]
+pub enum ClipboardError {
+    // No clipboard to reach, like over SSH
+    Unavailable(String),
+    // Built without the clipboard feature
+    Unsupported,
+}
+
+impl std::fmt::Display for ClipboardError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClipboardError::Unavailable(e) => {
+                write!(f, "Can't copy to the clipboard: {}", e)
+            }
+            ClipboardError::Unsupported => {
+                write!(f, "Not copied, rstype was built without the clipboard feature")
+            }
+        }
+    }
+}
+
+/// Holds text for pasting elsewhere
+///
+/// The app uses the system clipboard, tests can keep the text instead.
+pub trait Clipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError>;
+}
+
+/// Clipboard of the desktop session
+#[cfg(feature = "clipboard")]
+pub struct SystemClipboard;
+
+#[cfg(feature = "clipboard")]
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.set_text(text))
+            .map_err(|e| ClipboardError::Unavailable(e.to_string()))
+    }
+}
+
+/// Stands in for the system clipboard when the clipboard feature is off, copying always fails
+#[cfg(not(feature = "clipboard"))]
+pub struct SystemClipboard;
+
+#[cfg(not(feature = "clipboard"))]
+impl Clipboard for SystemClipboard {
+    fn set_text(&mut self, _text: &str) -> Result<(), ClipboardError> {
+        Err(ClipboardError::Unsupported)
+    }
+}
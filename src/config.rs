@@ -0,0 +1,207 @@
+use std::fmt::Formatter;
+use std::path::{Path, PathBuf};
+use crate::profile::{data_directory, profile_directory, user_directories, DEFAULT_PROFILE, PROFILE_CONFIG_FILE};
+use crate::share::ShareTarget;
+
+/// Settings kept between runs in the config file
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    // Speed to beat, the end screen shows how close the test came
+    pub goal_wpm: Option<f64>,
+    // Name of the built-in theme
+    pub theme: Option<String>,
+    // Colors replacing the ones of the theme, role with "foreground,background" from `theme.<role>` keys
+    pub theme_colors: Vec<(String, String)>,
+    // URL the results of every test are posted to
+    pub webhook_url: Option<String>,
+    // Text of the shared result and of the copied result, with placeholders, `\n` in the file for line breaks
+    pub share_template: Option<String>,
+    pub copy_template: Option<String>,
+    // Where Ctrl+T shares the result, from `share_target.<name>` keys in the order of the file
+    pub share_targets: Vec<ShareTarget>,
+    // Leave the results of every test out of the history, like --no-history
+    pub no_history: bool,
+    // History file, --history-file and RSTYPE_HISTORY replace it
+    pub history_file: Option<PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    IoError(std::io::Error),
+    HomeDirError(String),
+    InvalidValue { key: String, value: String },
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::IoError(e) => {
+                write!(f, "An IO error occurred: {}", e)
+            }
+            ConfigError::HomeDirError(s) => {
+                write!(f, "Unable to get home directory: {}", s)
+            }
+            ConfigError::InvalidValue { key, value } => {
+                write!(f, "Invalid value for {}: {}", key, value)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::IoError(e)
+    }
+}
+
+/// Load the config file from the home directory, with the config of the profile over it
+pub fn load_config(profile: &str) -> Result<Config, ConfigError> {
+    load_profile_config_from_file(config_file_absolute_path()?, profile_config_file_path(profile)?)
+}
+
+/// Load the config of a profile, its keys replace the ones of the main config file
+///
+/// Share targets and theme colors are added to the ones of the main file.
+/// The history file of the main file is the one of the default profile, other profiles keep theirs.
+/// # Arguments:
+/// * `config_file_path` - Path of the main config file
+/// * `profile_config_file_path` - Config file of the profile, None for the default profile
+pub fn load_profile_config_from_file<P: AsRef<Path>>(
+    config_file_path: P,
+    profile_config_file_path: Option<PathBuf>,
+) -> Result<Config, ConfigError> {
+    let mut config = load_config_from_file(config_file_path)?;
+    if let Some(profile_config_file_path) = profile_config_file_path {
+        config.history_file = None;
+        read_config_file(&mut config, profile_config_file_path)?;
+    }
+    Ok(config)
+}
+
+/// Load the config from the given file
+///
+/// Every line holds one `key = value` pair, empty lines, lines starting
+/// with `#` and unknown keys are skipped.
+/// # Arguments:
+/// * `config_file_path` - Path of the config file
+/// # Returns:
+/// * `Config` - The default config when the file doesn't exist
+pub fn load_config_from_file<P: AsRef<Path>>(config_file_path: P) -> Result<Config, ConfigError> {
+    let mut config = Config::default();
+    read_config_file(&mut config, config_file_path)?;
+    Ok(config)
+}
+
+/// Set the keys of a config file in the config, a missing file changes nothing
+fn read_config_file<P: AsRef<Path>>(config: &mut Config, config_file_path: P) -> Result<(), ConfigError> {
+    if !config_file_path.as_ref().exists() {
+        return Ok(());
+    }
+
+    for line in std::fs::read_to_string(config_file_path)?.lines() {
+        let Some((key, value)) = parse_line(line) else {
+            continue;
+        };
+        if key == "goal_wpm" {
+            let goal_wpm = value.parse::<f64>().map_err(|_| ConfigError::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            })?;
+            // A goal of 0 turns the goal off
+            config.goal_wpm = Some(goal_wpm).filter(|goal_wpm| *goal_wpm > 0.0);
+        } else if key == "theme" {
+            config.theme = Some(value.to_string());
+        } else if key == "webhook_url" {
+            config.webhook_url = Some(value.to_string()).filter(|url| !url.is_empty());
+        } else if key == "no_history" {
+            config.no_history = value.parse::<bool>().map_err(|_| ConfigError::InvalidValue {
+                key: key.to_string(),
+                value: value.to_string(),
+            })?;
+        } else if key == "history_file" {
+            config.history_file = Some(PathBuf::from(value)).filter(|path| !path.as_os_str().is_empty());
+        } else if key == "share_template" {
+            config.share_template = Some(value.replace("\\n", "\n"));
+        } else if key == "copy_template" {
+            config.copy_template = Some(value.replace("\\n", "\n"));
+        } else if let Some(role) = key.strip_prefix("theme.") {
+            config.theme_colors.push((role.to_string(), value.to_string()));
+        } else if let Some(name) = key.strip_prefix("share_target.") {
+            config.share_targets.push(ShareTarget::new(name, value));
+        }
+    }
+    Ok(())
+}
+
+/// Set one value in the config file of the profile, the one in the home directory for the default profile
+pub fn save_config_value(profile: &str, key: &str, value: &str) -> Result<(), ConfigError> {
+    let config_file_path = match profile_config_file_path(profile)? {
+        Some(path) => path,
+        None => config_file_absolute_path()?,
+    };
+    save_config_value_to_file(config_file_path, key, value)
+}
+
+/// Set one value in the given config file, keeping the other lines as they are
+pub fn save_config_value_to_file<P: AsRef<Path>>(
+    config_file_path: P,
+    key: &str,
+    value: &str,
+) -> Result<(), ConfigError> {
+    let config_file_path = config_file_path.as_ref();
+    let content = if config_file_path.exists() {
+        std::fs::read_to_string(config_file_path)?
+    } else {
+        String::new()
+    };
+
+    // The directory of a new profile is made with its first setting
+    if let Some(directory) = config_file_path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        std::fs::create_dir_all(directory)?;
+    }
+    let new_line = format!("{} = {}", key, value);
+    let mut replaced = false;
+    let mut lines: Vec<String> = content.lines()
+        .map(|line| match parse_line(line) {
+            Some((line_key, _)) if line_key == key => {
+                replaced = true;
+                new_line.clone()
+            }
+            _ => line.to_string(),
+        })
+        .collect();
+    if !replaced {
+        lines.push(new_line);
+    }
+    std::fs::write(config_file_path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Split a config line into its key and value, None for comments and empty lines
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    line.split_once('=').map(|(key, value)| (key.trim(), value.trim()))
+}
+
+/// Config file in the directory of a profile, None for the default profile
+fn profile_config_file_path(profile: &str) -> Result<Option<PathBuf>, ConfigError> {
+    if profile == DEFAULT_PROFILE {
+        return Ok(None);
+    }
+    let (home, data_home) = user_directories().ok_or(ConfigError::HomeDirError(PROFILE_CONFIG_FILE.to_string()))?;
+    Ok(profile_directory(&data_directory(&home, data_home.as_deref()), profile)
+        .map(|directory| directory.join(PROFILE_CONFIG_FILE)))
+}
+
+fn config_file_absolute_path() -> Result<PathBuf, ConfigError> {
+    let config_filename = ".rstype_config";
+    Ok(
+        home::home_dir()
+            .take_if(|p| !p.as_os_str().is_empty())
+            .ok_or(ConfigError::HomeDirError(config_filename.to_string()))?
+            .join(config_filename)
+    )
+}
@@ -49,8 +49,18 @@ pub fn load_text_from_database(text_id: u32, database_path: &str) -> Result<Prep
 pub fn load_text_from_database_with_random_difficulty(
     database_path: &str,
 ) -> Result<PreparedText, DatabaseError> {
-    let random = rand::thread_rng().gen_range(1..6);
-    load_text_from_database_based_on_difficulty(random, database_path)
+    load_text_from_database_with_random_difficulty_with_rng(database_path, &mut rand::thread_rng())
+}
+
+/// Load text of random difficulty, chosen with the given random number generator.
+///
+/// A seeded generator always selects the same text from the same database.
+pub fn load_text_from_database_with_random_difficulty_with_rng(
+    database_path: &str,
+    rng: &mut impl Rng,
+) -> Result<PreparedText, DatabaseError> {
+    let random = rng.gen_range(1..=5);
+    load_text_from_database_based_on_difficulty_with_rng(random, database_path, rng)
 }
 
 /// Load text of given difficulty from database if parameter is passed.
@@ -61,6 +71,17 @@ pub fn load_text_from_database_with_random_difficulty(
 pub fn load_text_from_database_based_on_difficulty(
     difficulty: u32,
     database_path: &str,
+) -> Result<PreparedText, DatabaseError> {
+    load_text_from_database_based_on_difficulty_with_rng(difficulty, database_path, &mut rand::thread_rng())
+}
+
+/// Load text of given difficulty, chosen with the given random number generator.
+///
+/// A seeded generator always selects the same text from the same database.
+pub fn load_text_from_database_based_on_difficulty_with_rng(
+    difficulty: u32,
+    database_path: &str,
+    rng: &mut impl Rng,
 ) -> Result<PreparedText, DatabaseError> {
     let max_level = 5;
 
@@ -69,7 +90,7 @@ pub fn load_text_from_database_based_on_difficulty(
         let upper_limit = difficulty * 1200;
         let lower_limit = upper_limit - 1200 + 1;
 
-        let text_id = rand::thread_rng().gen_range(lower_limit..upper_limit + 1);
+        let text_id = rng.gen_range(lower_limit..=upper_limit);
         let text = fetch_text_with_id(text_id, database_path)?;
         Ok((text, text_id.to_string()))
     } else {
@@ -77,6 +98,49 @@ pub fn load_text_from_database_based_on_difficulty(
     }
 }
 
+/// Difficulty section a text of the database belongs to, see `load_text_from_database_based_on_difficulty`.
+/// # Arguments
+/// * `text_id` - ID of the text in the database
+/// # Returns
+/// * `Option<u32>` - Difficulty from 1 to 5, None for an ID outside the database
+pub fn difficulty_of_text_id(text_id: u32) -> Option<u32> {
+    (1..=6000).contains(&text_id).then(|| (text_id - 1) / 1200 + 1)
+}
+
+/// Pick the text of the daily challenge for a date.
+///
+/// The date is hashed with FNV-1a, which unlike the standard hasher gives the
+/// same result on every machine and Rust version.
+/// # Arguments
+/// * `date` - Local date of the challenge
+/// # Returns
+/// * `u32` - ID of the text, in range [1,6000]
+pub fn daily_text_id(date: chrono::NaiveDate) -> u32 {
+    let row_count = 6000;
+    let hash = date.format("%Y-%m-%d")
+        .to_string()
+        .bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+    (hash % row_count) as u32 + 1
+}
+
+/// Load the text of the daily challenge for a date.
+/// # Arguments
+/// * `date` - Local date of the challenge
+/// # Returns
+/// * `Result<FileText>` - Text and an ID like "daily-2024-06-01"
+pub fn load_daily_text(date: chrono::NaiveDate, database_path: &str) -> Result<PreparedText, DatabaseError> {
+    let text = fetch_text_with_id(daily_text_id(date), database_path)?;
+    Ok((text, daily_text_key(date)))
+}
+
+/// ID under which the daily challenge of a date is saved to history
+pub fn daily_text_key(date: chrono::NaiveDate) -> String {
+    format!("daily-{}", date.format("%Y-%m-%d"))
+}
+
 /// Fetch row from data.db database.
 /// # Arguments
 /// * `serial_id` - The unique ID of database entry.
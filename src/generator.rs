@@ -0,0 +1,159 @@
+use std::fmt::Formatter;
+use crate::PreparedText;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Number of words in a drill when no word count is given
+pub const DEFAULT_DRILL_WORDS: usize = 40;
+/// Longest drill word, short enough to never be split by `word_wrap`
+pub const DEFAULT_MAX_WORD_LENGTH: usize = 7;
+
+const DIGITS: &str = "0123456789";
+const SYMBOLS: &str = "{}[]()<>;:,.=+-*/&|!?#$%^_~@'\"\\`";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum GeneratorError {
+    EmptyKeySet,
+    NoWords,
+}
+
+impl std::fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::EmptyKeySet => {
+                write!(f, "No keys given to practice")
+            }
+            GeneratorError::NoWords => {
+                write!(f, "No words given to practice")
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Drill {
+    /// Groups of random digits
+    Numbers,
+    /// Common programming symbols
+    Symbols,
+}
+
+impl Drill {
+    fn name(&self) -> &'static str {
+        match self {
+            Drill::Numbers => "numbers",
+            Drill::Symbols => "symbols",
+        }
+    }
+
+    fn characters(&self) -> &'static str {
+        match self {
+            Drill::Numbers => DIGITS,
+            Drill::Symbols => SYMBOLS,
+        }
+    }
+
+    fn longest_word(&self) -> usize {
+        match self {
+            Drill::Numbers => 4,
+            Drill::Symbols => 3,
+        }
+    }
+}
+
+/// Generate a drill text without touching the database.
+/// # Arguments
+/// * `drill` - Kind of characters to practice
+/// * `word_count` - Number of words in the text
+/// * `max_word_length` - No word is longer than this many characters
+/// * `seed` - The same seed always generates the same text
+/// # Returns
+/// * `PreparedText` with an id like "drill:numbers:42"
+pub fn generate_drill(drill: Drill, word_count: usize, max_word_length: usize, seed: u64) -> PreparedText {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let characters: Vec<char> = drill.characters().chars().collect();
+    let longest_word = drill.longest_word().min(max_word_length).max(1);
+    let text = (0..word_count)
+        .map(|_| random_word(&mut rng, &characters, 1, longest_word))
+        .collect::<Vec<String>>()
+        .join(" ");
+    (text, format!("drill:{}:{}", drill.name(), seed))
+}
+
+/// Generate pseudo-words of 3 to 7 characters using only the given keys.
+/// # Arguments
+/// * `keys` - Keys to practice, repeated keys and whitespace are ignored
+/// * `word_count` - Number of words in the text
+/// * `max_word_length` - No word is longer than this many characters
+/// * `seed` - The same seed always generates the same text
+/// # Returns
+/// * `PreparedText` with an id like "drill:keys:asdf:42", an error if no keys are left
+pub fn generate_key_drill(
+    keys: &str,
+    word_count: usize,
+    max_word_length: usize,
+    seed: u64,
+) -> Result<PreparedText, GeneratorError> {
+    let mut characters: Vec<char> = Vec::new();
+    for c in keys.chars().filter(|c| !c.is_whitespace()) {
+        if !characters.contains(&c) {
+            characters.push(c);
+        }
+    }
+    if characters.is_empty() {
+        return Err(GeneratorError::EmptyKeySet);
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let longest_word = max_word_length.clamp(1, 7);
+    let shortest_word = longest_word.min(3);
+    let text = (0..word_count)
+        .map(|_| random_word(&mut rng, &characters, shortest_word, longest_word))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let key_set: String = characters.iter().collect();
+    Ok((text, format!("drill:keys:{}:{}", key_set, seed)))
+}
+
+/// Build a text from the given words, picking the most mistyped ones most often.
+/// # Arguments
+/// * `word_errors` - Words with the number of times they were mistyped
+/// * `word_count` - Number of words in the text
+/// * `seed` - The same seed always generates the same text
+/// # Returns
+/// * `PreparedText` with an id like "drill:mistakes:42", an error if no word has a mistake
+pub fn generate_mistakes_drill(
+    word_errors: &[(String, u32)],
+    word_count: usize,
+    seed: u64,
+) -> Result<PreparedText, GeneratorError> {
+    let total_errors: u64 = word_errors.iter().map(|(_, errors)| u64::from(*errors)).sum();
+    if total_errors == 0 {
+        return Err(GeneratorError::NoWords);
+    }
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let text = (0..word_count)
+        .map(|_| {
+            // Every mistake is one ticket for its word
+            let mut ticket = rng.gen_range(0..total_errors);
+            word_errors.iter()
+                .find(|(_, errors)| {
+                    let found = ticket < u64::from(*errors);
+                    ticket = ticket.saturating_sub(u64::from(*errors));
+                    found
+                })
+                .map(|(word, _)| word.as_str())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<&str>>()
+        .join(" ");
+    Ok((text, format!("drill:mistakes:{}", seed)))
+}
+
+fn random_word(rng: &mut StdRng, characters: &[char], shortest: usize, longest: usize) -> String {
+    let length = rng.gen_range(shortest..=longest);
+    (0..length)
+        .map(|_| characters[rng.gen_range(0..characters.len())])
+        .collect()
+}
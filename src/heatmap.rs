@@ -0,0 +1,58 @@
+use crate::app::Color;
+use crate::layout::base_key;
+use std::collections::HashMap;
+
+/// Unshifted keys of a QWERTY keyboard, row by row
+pub const KEYBOARD_ROWS: [&str; 4] = ["`1234567890-=", "qwertyuiop[]\\", "asdfghjkl;'", "zxcvbnm,./"];
+
+/// Columns taken by one key of the heatmap
+pub const HEATMAP_KEY_WIDTH: usize = 3;
+
+/// Columns needed to draw the heatmap, every row is shifted one column right of the one above
+pub const HEATMAP_WIDTH: usize = 13 * HEATMAP_KEY_WIDTH + KEYBOARD_ROWS.len() - 1;
+
+/// Count the mistakes made on every key of the keyboard.
+///
+/// Every mistake counts toward the key of the character expected at its
+/// position, shifted characters count toward their base key and characters
+/// that aren't on the keyboard are left out.
+/// # Arguments
+/// * `text` - Text the positions point into
+/// * `mistyped_keys` - Number of mistakes at every character index of `text`
+pub fn key_errors(text: &str, mistyped_keys: &HashMap<usize, u32>) -> HashMap<char, u32> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut errors: HashMap<char, u32> = HashMap::new();
+    for (index, count) in mistyped_keys {
+        if let Some(key) = chars.get(*index).and_then(|c| base_key(*c)) {
+            *errors.entry(key).or_insert(0) += count;
+        }
+    }
+    errors
+}
+
+/// Color of a key with `errors` mistakes when the worst key has `max_errors`
+///
+/// Keys without mistakes have no color.
+pub fn heat_color(errors: u32, max_errors: u32) -> Option<Color> {
+    if errors == 0 || max_errors == 0 {
+        None
+    } else if errors * 3 <= max_errors {
+        Some(Color::Yellow)
+    } else if errors * 3 <= max_errors * 2 {
+        Some(Color::Magenta)
+    } else {
+        Some(Color::Red)
+    }
+}
+
+/// Rows of keys with the color showing how often they were mistyped
+pub fn heatmap_rows(key_errors: &HashMap<char, u32>) -> Vec<Vec<(char, Option<Color>)>> {
+    let max_errors = key_errors.values().copied().max().unwrap_or(0);
+    KEYBOARD_ROWS.iter()
+        .map(|row| {
+            row.chars()
+                .map(|key| (key, heat_color(key_errors.get(&key).copied().unwrap_or(0), max_errors)))
+                .collect()
+        })
+        .collect()
+}
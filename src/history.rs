@@ -1,15 +1,55 @@
+use std::collections::{HashMap, VecDeque};
 use std::fmt::Formatter;
 use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use chrono::{Datelike, NaiveDate};
 use csv::StringRecord;
+use serde::Serialize;
+use crate::calculations::trend_sparkline;
+use crate::database::daily_text_id;
+use crate::replay::checksum;
+use crate::profile::{data_directory, profile_directory, user_directories};
+use crate::session::{SessionStats, TextSource};
+use crate::timer::format_total;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Columns of the history file, in the order they are written
+pub const HISTORY_HEADER: [&str; 18] = [
+    "ID", "WPM", "DATE", "TIME", "ACCURACY", "CPM", "CONSISTENCY", "TIME_LIMIT", "SKIPPED", "SUSPECT", "BEST",
+    "DURATION_S", "CHARS", "ERRORS", "DIFFICULTY", "SOURCE", "RETRY", "HASH",
+];
+
+/// Columns of the companion file storing the speed of every typed word
+pub const WORD_TIMINGS_HEADER: [&str; 2] = ["WORD", "WPM"];
+
+/// Columns of the companion file storing the mistakes in every mistyped word
+pub const MISTYPED_WORDS_HEADER: [&str; 2] = ["WORD", "ERRORS"];
+
+/// Columns of the file storing the completed lessons
+pub const LESSONS_HEADER: [&str; 2] = ["LESSON", "DATE"];
+
+/// Columns of the file storing the best run of every text
+pub const GHOSTS_HEADER: [&str; 3] = ["ID", "WPM", "TIMELINE"];
+
+/// Names older versions wrote for columns of `HISTORY_HEADER` that were renamed since
+const RENAMED_HISTORY_COLUMNS: [(&str, &str); 2] = [("TIME_LIMIT", "DURATION"), ("DURATION_S", "ELAPSED")];
 
 #[derive(Debug)]
 pub enum HistoryError {
     CsvError(csv::Error),
     IoError(std::io::Error),
     HomeDirError(String),
+    JsonError(serde_json::Error),
     FileDoesNotExist,
     FileIsEmpty,
+    // Number of the record asked for and the number of records in the history
+    RecordOutOfRange(usize, usize),
+    UnknownExportFormat(String),
+    // Number of the record and the id of its text
+    NotRetakeable(usize, String),
+    TextChanged(usize, String),
 }
 
 impl std::fmt::Display for HistoryError {
@@ -24,12 +64,27 @@ impl std::fmt::Display for HistoryError {
             HistoryError::CsvError(e) => {
                 write!(f, "An error occurred while reading or writing CSV: {}", e)
             }
+            HistoryError::JsonError(e) => {
+                write!(f, "An error occurred while writing JSON: {}", e)
+            }
             HistoryError::FileDoesNotExist => {
                 write!(f, "The history file does not exist")
             }
             HistoryError::FileIsEmpty => {
                 write!(f, "The history file is empty")
             }
+            HistoryError::RecordOutOfRange(number, count) => {
+                write!(f, "No history record {}, the history has records 1 to {} as numbered by --history", number, count)
+            }
+            HistoryError::UnknownExportFormat(name) => {
+                write!(f, "Unknown history export format: {}, use json or markdown", name)
+            }
+            HistoryError::NotRetakeable(number, text_id) => {
+                write!(f, "Record {} can't be retaken, {} isn't a single text from the database or a file", number, text_id)
+            }
+            HistoryError::TextChanged(number, text_id) => {
+                write!(f, "The text {} changed since record {} was typed, retake it anyway with --force", text_id, number)
+            }
         }
     }
 }
@@ -40,33 +95,223 @@ impl From<csv::Error> for HistoryError {
     }
 }
 
+impl From<serde_json::Error> for HistoryError {
+    fn from(e: serde_json::Error) -> Self {
+        HistoryError::JsonError(e)
+    }
+}
+
 impl From<std::io::Error> for HistoryError {
     fn from(e: std::io::Error) -> Self {
         HistoryError::IoError(e)
     }
 }
 
+/// One row of the history file, with the columns of `HISTORY_HEADER`
+///
+/// Columns missing from records written by older versions are None.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct HistoryRecord {
+    pub id: String,
+    pub wpm: Option<f64>,
+    pub date: String,
+    pub time: String,
+    pub accuracy: Option<f64>,
+    pub cpm: Option<f64>,
+    pub consistency: Option<f64>,
+    // Time limit in seconds, the TIME_LIMIT column, None for tests without one
+    pub time_limit: Option<u64>,
+    pub skipped: Option<usize>,
+    pub suspect: bool,
+    // The result was a new personal best when it was saved
+    pub personal_best: bool,
+    // Seconds the test took, the DURATION_S column, and characters typed in it
+    pub elapsed: Option<f64>,
+    pub chars: Option<usize>,
+    // Mistyped positions of the text
+    pub errors: Option<usize>,
+    // Difficulty of a text from the database, None for other texts
+    pub difficulty: Option<u32>,
+    // Where the text came from, guessed from the id for records saved without it
+    pub source: TextSource,
+    // Attempt at the text in one run of rstype, the RETRY column, above 1 for Tab retries
+    pub attempt: Option<u32>,
+    // `text_hash` of the text, None for records saved before the hash and for endless and marathon tests
+    pub text_hash: Option<String>,
+}
+
+impl HistoryRecord {
+    /// Whether the test was typed again with Tab after finishing the text, None attempts are first ones
+    pub fn is_retry(&self) -> bool {
+        self.attempt.is_some_and(|attempt| attempt > 1)
+    }
+}
+
+impl From<&StringRecord> for HistoryRecord {
+    fn from(record: &StringRecord) -> Self {
+        let column = |index: usize| record.get(index).unwrap_or("");
+        HistoryRecord {
+            id: column(0).to_string(),
+            wpm: column(1).parse().ok(),
+            date: column(2).to_string(),
+            time: column(3).to_string(),
+            accuracy: column(4).parse().ok(),
+            cpm: column(5).parse().ok(),
+            consistency: column(6).parse().ok(),
+            time_limit: column(7).parse().ok(),
+            skipped: column(8).parse().ok(),
+            suspect: column(9) == "yes",
+            personal_best: column(10) == "yes",
+            elapsed: column(11).parse().ok(),
+            chars: column(12).parse().ok(),
+            errors: column(13).parse().ok(),
+            difficulty: column(14).parse().ok(),
+            source: text_source(column(0), column(15)),
+            attempt: column(16).parse().ok(),
+            text_hash: Some(column(17).to_string()).filter(|hash| !hash.is_empty()),
+        }
+    }
+}
+
+/// Where the text of a record came from, guessed from its id when the SOURCE column is empty
+fn text_source(text_id: &str, source_name: &str) -> TextSource {
+    TextSource::from_name(source_name).unwrap_or_else(|| TextSource::guess(text_id))
+}
+
 pub enum NumberOfRecords {
     All,
     Last(usize),
 }
 
+/// Texts whose records are read from history, by the ID column
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum TextFilter {
+    #[default]
+    All,
+    // The ID is exactly this, a database id or the path of a text file
+    Exact(String),
+    // The ID contains this
+    Contains(String),
+    // The text came from there
+    Source(TextSource),
+}
+
+impl TextFilter {
+    /// Whether a record of the history matches
+    pub fn matches(&self, record: &HistoryRecord) -> bool {
+        self.matches_text(&record.id, || record.source)
+    }
+
+    /// Whether the text of a record matches, its source is only found out when the filter needs it
+    fn matches_text(&self, text_id: &str, source: impl FnOnce() -> TextSource) -> bool {
+        match self {
+            TextFilter::All => true,
+            TextFilter::Exact(id) => text_id == id,
+            TextFilter::Contains(part) => text_id.contains(part.as_str()),
+            TextFilter::Source(expected) => source() == *expected,
+        }
+    }
+}
+
+impl std::fmt::Display for TextFilter {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextFilter::All => write!(f, "every text"),
+            TextFilter::Exact(id) => write!(f, "text {}", id),
+            TextFilter::Contains(part) => write!(f, "texts with \"{}\" in their id", part),
+            TextFilter::Source(source) => write!(f, "texts from source {}", source.name()),
+        }
+    }
+}
+
 impl From<usize> for NumberOfRecords {
     fn from(value: usize) -> Self {
         NumberOfRecords::Last(value)
     }
 }
 
-/// Get records from history
+/// Get records from the given history file
 ///
-/// Defaults to -1 if argumetns value not provided on command line
+/// Records are returned with the columns of `HISTORY_HEADER`, columns missing
+/// from files written by older versions are None. The last `number_of_records`
+/// records are counted among the records matching the filter.
+pub fn get_history_records_from_file<P: AsRef<Path>>(
+    history_file_path: P,
+    number_of_records: NumberOfRecords,
+    filter: &TextFilter,
+) -> Result<Vec<HistoryRecord>, HistoryError> {
+    let (rows, _) = read_history_rows(history_file_path.as_ref(), number_of_records, filter)?;
+    Ok(rows.iter().map(|(_, row)| HistoryRecord::from(row)).collect())
+}
+
+/// Get the records listed by `show_history`, with their number in the whole history
+///
+/// Records are numbered from 1 for the oldest one, for --history-compare. The last
+/// `number_of_records` matching records are picked before they are ordered, the
+/// others are read and dropped.
 /// # Arguments:
-/// * `number_of_records` - Number of last records to print
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `number_of_records` - Number of last matching records to pick
+/// * `filter` - Texts to pick the records of
+/// * `newest_first` - Return the records from the newest one instead of the oldest one
+pub fn select_history_records_from_file<P: AsRef<Path>>(
+    history_file_path: P,
+    number_of_records: NumberOfRecords,
+    filter: &TextFilter,
+    newest_first: bool,
+) -> Result<Vec<(usize, HistoryRecord)>, HistoryError> {
+    let (rows, _) = read_history_rows(history_file_path.as_ref(), number_of_records, filter)?;
+    let mut selected: Vec<(usize, HistoryRecord)> = rows.iter()
+        .map(|(number, row)| (*number, HistoryRecord::from(row)))
+        .collect();
+    if newest_first {
+        selected.reverse();
+    }
+    Ok(selected)
+}
+
+/// Read the rows of a history file one at a time, in the columns of `HISTORY_HEADER`
+///
+/// Only the last `number_of_records` matching rows are kept while reading, the row
+/// pushed out of them is reused for the next one, so a few last records of a long
+/// history don't take the memory of all of it.
 /// # Returns:
-/// * `Vec<String>` - The len of this list is `number_of_records` or all records
-pub fn get_history_records(number_of_records: NumberOfRecords) -> Result<Vec<StringRecord>, HistoryError> {
-    let history_file_path = history_file_absolute_path()?;
+/// * `(Vec<(usize, StringRecord)>, usize)` - Rows kept, oldest first, with their number in the
+///   whole file from 1, and the number of rows matching the filter
+fn read_history_rows(
+    history_file_path: &Path,
+    number_of_records: NumberOfRecords,
+    filter: &TextFilter,
+) -> Result<(Vec<(usize, StringRecord)>, usize), HistoryError> {
+    let limit = match number_of_records {
+        NumberOfRecords::All => usize::MAX,
+        NumberOfRecords::Last(n) => n,
+    };
 
+    let mut kept: VecDeque<(usize, StringRecord)> = VecDeque::new();
+    let mut matching = 0;
+    for_each_history_row(history_file_path, filter, |number, row| {
+        matching += 1;
+        if limit == 0 {
+            return;
+        }
+        let (_, mut kept_row) = if kept.len() == limit { kept.pop_front().unwrap_or_default() } else { Default::default() };
+        kept_row.clear();
+        kept_row.extend(row);
+        kept.push_back((number, kept_row));
+    })?;
+    Ok((kept.into(), matching))
+}
+
+/// Call `visit` with every row of a history file matching the filter, in the columns of `HISTORY_HEADER`
+///
+/// Rows are numbered from 1 for the oldest one, the rows left out by the filter count too.
+/// The row given to `visit` is reused for the next one.
+fn for_each_history_row(
+    history_file_path: &Path,
+    filter: &TextFilter,
+    mut visit: impl FnMut(usize, &StringRecord),
+) -> Result<(), HistoryError> {
     if !history_file_path.exists() {
         return Err(HistoryError::FileDoesNotExist);
     }
@@ -75,87 +320,1427 @@ pub fn get_history_records(number_of_records: NumberOfRecords) -> Result<Vec<Str
     if !reader.has_headers() {
         return Err(HistoryError::FileIsEmpty);
     }
+    let positions = column_positions(reader.headers()?);
+
+    let mut row = StringRecord::new();
+    let mut normalized = StringRecord::new();
+    let mut number = 0;
+    while reader.read_record(&mut row)? {
+        number += 1;
+        let column = |header_index: usize| positions[header_index].and_then(|index| row.get(index)).unwrap_or("");
+        if !filter.matches_text(column(0), || text_source(column(0), column(15))) {
+            continue;
+        }
+        normalize_record_into(&positions, &row, &mut normalized);
+        visit(number, &normalized);
+    }
+    Ok(())
+}
+
+/// Get the latest record of a text from the given history file
+/// # Arguments:
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `text_id` - Id saved with the record
+/// # Returns:
+/// * `Option<HistoryRecord>` - None if the text was never typed
+pub fn find_history_record_in_file<P: AsRef<Path>>(
+    history_file_path: P,
+    text_id: &str,
+) -> Result<Option<HistoryRecord>, HistoryError> {
+    let filter = TextFilter::Exact(text_id.to_string());
+    let records = match get_history_records_from_file(history_file_path, NumberOfRecords::Last(1), &filter) {
+        Err(HistoryError::FileDoesNotExist | HistoryError::FileIsEmpty) => vec![],
+        result => result?,
+    };
+    Ok(records.into_iter().next())
+}
+
+/// Average WPM and accuracy of the given history records
+///
+/// Records flagged as suspect are left out.
+/// # Arguments:
+/// * `records` - Records returned by `get_history_records_from_file`
+/// # Returns:
+/// * `Option<(f64, f64)>` - None if no record has both values
+pub fn average_wpm_and_accuracy(records: &[HistoryRecord]) -> Option<(f64, f64)> {
+    let results: Vec<(f64, f64)> = records.iter()
+        .filter(|record| !record.suspect)
+        .filter_map(|record| Some((record.wpm?, record.accuracy?)))
+        .collect();
+    if results.is_empty() {
+        return None;
+    }
+    let count = results.len() as f64;
+    let wpm = results.iter().map(|(wpm, _)| wpm).sum::<f64>() / count;
+    let accuracy = results.iter().map(|(_, accuracy)| accuracy).sum::<f64>() / count;
+    Some((wpm, accuracy))
+}
+
+/// Best result a test beat, with the WPM it was beaten by
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PersonalBest {
+    // Faster than every earlier test
+    Overall(f64),
+    // Faster than every earlier test of the same text
+    Text(f64),
+}
+
+/// Which best result a speed beats, the best of every text before the best of its text
+///
+/// Ties don't count, and there is nothing to beat before the first result.
+/// # Arguments:
+/// * `wpm` - Speed of the finished test
+/// * `best_overall` - Best speed in history, see `best_wpm_in_file`
+/// * `best_of_text` - Best speed in history on the same text
+pub fn personal_best(wpm: f64, best_overall: Option<f64>, best_of_text: Option<f64>) -> Option<PersonalBest> {
+    match (best_overall, best_of_text) {
+        (Some(best), _) if wpm > best => Some(PersonalBest::Overall(wpm - best)),
+        (_, Some(best)) if wpm > best => Some(PersonalBest::Text(wpm - best)),
+        _ => None,
+    }
+}
 
-    let mut records: Vec<StringRecord> = vec![];
+/// Best speed saved to the history file for a text, shown in the header before it is typed
+pub fn best_for_text(history_file_path: &Path, text_id: &str) -> Result<Option<f64>, HistoryError> {
+    best_wpm_in_file(history_file_path, Some(text_id))
+}
+
+/// Best speed saved to the given history file
+///
+/// Records flagged as suspect are left out. Rows are read one at a time, the file isn't kept in memory.
+/// # Arguments:
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `text_id` - Only look at the records of this text, None for every record
+/// # Returns:
+/// * `Option<f64>` - None when no record matches or there is no history yet
+pub fn best_wpm_in_file<P: AsRef<Path>>(history_file_path: P, text_id: Option<&str>) -> Result<Option<f64>, HistoryError> {
+    if !history_file_path.as_ref().exists() {
+        return Ok(None);
+    }
+    let mut reader = csv::Reader::from_path(history_file_path)?;
+    // Files written by older versions may have fewer columns
+    let headers = reader.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|header| header == name);
+    let (id_column, wpm_column, suspect_column) = (column("ID"), column("WPM"), column("SUSPECT"));
+
+    let mut best: Option<f64> = None;
     for record in reader.records() {
         let record = record?;
-        records.push(record);
+        let field = |index: Option<usize>| index.and_then(|index| record.get(index));
+        if field(suspect_column) == Some("yes") || text_id.is_some_and(|text_id| field(id_column) != Some(text_id)) {
+            continue;
+        }
+        if let Some(wpm) = field(wpm_column).and_then(|wpm| wpm.parse::<f64>().ok()) {
+            best = Some(best.map_or(wpm, |best| best.max(wpm)));
+        }
     }
+    Ok(best)
+}
 
-    let total_records = records.len();
+/// Totals over every test in history
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct HistorySummary {
+    pub tests: usize,
+    // Seconds spent typing, without the tests saved before durations were recorded
+    pub practice_seconds: f64,
+    pub chars_typed: usize,
+    // Tests saved by older versions, left out of the time and the characters
+    pub tests_without_duration: usize,
+}
 
-    let number_of_records = match number_of_records {
-        NumberOfRecords::All => {
-            total_records
+/// Totals of the given history file, nothing typed yet when it doesn't exist
+pub fn summarize_history(history_file_path: &Path) -> Result<HistorySummary, HistoryError> {
+    match get_history_records_from_file(history_file_path, NumberOfRecords::All, &TextFilter::All) {
+        Err(HistoryError::FileDoesNotExist | HistoryError::FileIsEmpty) => Ok(HistorySummary::default()),
+        result => Ok(summarize(&result?)),
+    }
+}
+
+/// Total tests, time practiced and characters typed of the given history records
+///
+/// Records written before the duration was saved only count as tests.
+pub fn summarize(records: &[HistoryRecord]) -> HistorySummary {
+    records.iter()
+        .fold(HistorySummary::default(), |mut summary, record| {
+            summary.add(record);
+            summary
+        })
+}
+
+impl HistorySummary {
+    /// Count one more record in the totals
+    fn add(&mut self, record: &HistoryRecord) {
+        self.tests += 1;
+        match (record.elapsed, record.chars) {
+            (Some(elapsed), Some(chars)) => {
+                self.practice_seconds += elapsed;
+                self.chars_typed += chars;
+            }
+            _ => self.tests_without_duration += 1,
         }
-        NumberOfRecords::Last(n) => if n >= total_records {
-            total_records
-        } else { n },
-    };
+    }
+}
+
+/// Results of the tests of one ISO week
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeekSummary {
+    // ISO year and week number, the year can differ from the calendar year around New Year
+    pub year: i32,
+    pub week: u32,
+    // Monday the week starts on
+    pub start: NaiveDate,
+    pub tests: usize,
+    // None for a week without tests
+    pub average_wpm: Option<f64>,
+    pub best_wpm: Option<f64>,
+    pub average_accuracy: Option<f64>,
+}
+
+/// Group history records by ISO week, for the last `weeks` weeks up to the week of `today`
+///
+/// Every week of the range is returned, oldest first, weeks without tests included.
+/// Records flagged as suspect, older records and records without a readable date are left out.
+/// # Arguments:
+/// * `records` - Records returned by `get_history_records_from_file`
+/// * `today` - Day in the last week returned
+/// * `weeks` - Number of weeks returned
+pub fn summarize_weeks(records: &[HistoryRecord], today: NaiveDate, weeks: usize) -> Vec<WeekSummary> {
+    let monday = |date: NaiveDate| date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64);
+    let last_week_start = monday(today);
+    let mut results: Vec<Vec<(f64, Option<f64>)>> = vec![vec![]; weeks];
+    for record in records.iter().filter(|record| !record.suspect) {
+        let (Ok(date), Some(wpm)) = (NaiveDate::parse_from_str(&record.date, "%Y-%m-%d"), record.wpm) else {
+            continue;
+        };
+        let weeks_ago = (last_week_start - monday(date)).num_days() / 7;
+        if (0..weeks as i64).contains(&weeks_ago) {
+            results[weeks - 1 - weeks_ago as usize].push((wpm, record.accuracy));
+        }
+    }
+
+    results.into_iter()
+        .enumerate()
+        .map(|(index, results)| {
+            let start = last_week_start - chrono::Duration::weeks((weeks - 1 - index) as i64);
+            let average = |values: Vec<f64>| {
+                (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+            };
+            WeekSummary {
+                year: start.iso_week().year(),
+                week: start.iso_week().week(),
+                start,
+                tests: results.len(),
+                average_wpm: average(results.iter().map(|(wpm, _)| *wpm).collect()),
+                best_wpm: results.iter().map(|(wpm, _)| *wpm).reduce(f64::max),
+                average_accuracy: average(results.iter().filter_map(|(_, accuracy)| *accuracy).collect()),
+            }
+        })
+        .collect()
+}
+
+/// Width the history is printed for when the shell doesn't export COLUMNS
+const DEFAULT_TERMINAL_COLUMNS: usize = 80;
+
+/// Height the history is paged for when the shell doesn't export LINES
+const DEFAULT_TERMINAL_ROWS: usize = 24;
+
+/// Widest text id of the history table, longer ids are cut at their start
+pub const MAX_ID_WIDTH: usize = 30;
+
+/// Columns of the history table holding numbers, aligned to the right
+const NUMBER_COLUMNS: [&str; 12] = [
+    "#", "WPM", "ACCURACY", "CPM", "CONSISTENCY", "TIME_LIMIT", "SKIPPED", "DURATION_S", "CHARS", "ERRORS", "DIFFICULTY", "RETRY",
+];
+
+/// Shown after every page of a long history
+pub const PAGE_PROMPT: &str = "-- More -- Enter for the next page, q to stop ";
+
+/// Runs in each of the two groups compared by `HistoryStats::recent_trend`
+pub const TREND_RUNS: usize = 10;
+
+/// Speed and accuracy over the whole history
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HistoryStats {
+    pub tests: usize,
+    // Tests left out of the numbers below, flagged as suspect or without a readable speed
+    pub skipped: usize,
+    // None without a test to compute them from
+    pub mean_wpm: Option<f64>,
+    pub median_wpm: Option<f64>,
+    // Best speed with the date of the test, the first one on a tie
+    pub best_wpm: Option<(f64, String)>,
+    pub mean_accuracy: Option<f64>,
+    // Average speed of the last `TREND_RUNS` tests minus the one of the tests before them,
+    // None with fewer than twice `TREND_RUNS` tests
+    pub recent_trend: Option<f64>,
+}
+
+/// Compute the speed and accuracy stats of history records, oldest first
+///
+/// Records flagged as suspect or with a speed that doesn't parse, like a corrupted row, are skipped.
+pub fn history_stats(records: &[HistoryRecord]) -> HistoryStats {
+    records.iter()
+        .fold(HistoryStatsFold::default(), |mut fold, record| {
+            fold.add(record);
+            fold
+        })
+        .finish()
+}
+
+/// `HistoryStats` computed one record at a time, oldest first
+///
+/// Only the speeds are kept, the median and the trend need them.
+#[derive(Default)]
+struct HistoryStatsFold {
+    tests: usize,
+    speeds: Vec<f64>,
+    best_wpm: Option<(f64, String)>,
+    accuracy_sum: f64,
+    accuracies: usize,
+}
+
+impl HistoryStatsFold {
+    fn add(&mut self, record: &HistoryRecord) {
+        self.tests += 1;
+        let Some(wpm) = record.wpm.filter(|wpm| wpm.is_finite() && !record.suspect) else {
+            return;
+        };
+        self.speeds.push(wpm);
+        if self.best_wpm.as_ref().is_none_or(|(best, _)| wpm > *best) {
+            self.best_wpm = Some((wpm, record.date.clone()));
+        }
+        if let Some(accuracy) = record.accuracy.filter(|accuracy| accuracy.is_finite()) {
+            self.accuracy_sum += accuracy;
+            self.accuracies += 1;
+        }
+    }
+
+    fn finish(self) -> HistoryStats {
+        let speeds = self.speeds;
+        let mean = |values: &[f64]| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
 
-    let start_count = if number_of_records < total_records {
-        total_records - number_of_records
-    } else { 0 };
+        let mut sorted = speeds.clone();
+        sorted.sort_by(f64::total_cmp);
+        let median_wpm = match sorted.len() {
+            0 => None,
+            n if n % 2 == 0 => Some((sorted[n / 2 - 1] + sorted[n / 2]) / 2.0),
+            n => Some(sorted[n / 2]),
+        };
+        let recent_trend = (speeds.len() >= 2 * TREND_RUNS).then(|| {
+            let recent = &speeds[speeds.len() - 2 * TREND_RUNS..];
+            mean(&recent[TREND_RUNS..]).unwrap_or_default() - mean(&recent[..TREND_RUNS]).unwrap_or_default()
+        });
 
-    Ok(records[start_count..total_records].to_vec())
+        HistoryStats {
+            tests: self.tests,
+            skipped: self.tests - speeds.len(),
+            mean_wpm: mean(&speeds),
+            median_wpm,
+            best_wpm: self.best_wpm,
+            mean_accuracy: (self.accuracies > 0).then(|| self.accuracy_sum / self.accuracies as f64),
+            recent_trend,
+        }
+    }
+}
+
+/// Totals and stats of the records of a history file matching a filter, read one at a time
+/// # Arguments:
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `filter` - Texts to count the records of
+/// * `include_retries` - Count the tests retried with Tab in the stats, the totals always count them
+/// # Returns:
+/// * `(HistorySummary, HistoryStats, usize)` - Totals, stats and the number of retries left out of the stats
+pub fn history_totals_from_file(
+    history_file_path: &Path,
+    filter: &TextFilter,
+    include_retries: bool,
+) -> Result<(HistorySummary, HistoryStats, usize), HistoryError> {
+    let mut summary = HistorySummary::default();
+    let mut stats = HistoryStatsFold::default();
+    let mut retries = 0;
+    for_each_history_row(history_file_path, filter, |_, row| {
+        let record = HistoryRecord::from(row);
+        summary.add(&record);
+        if !include_retries && record.is_retry() {
+            retries += 1;
+        } else {
+            stats.add(&record);
+        }
+    })?;
+    Ok((summary, stats.finish(), retries))
+}
+
+/// Lines printing the stats of the history, missing numbers are shown as not enough data
+pub fn history_stats_lines(stats: &HistoryStats) -> Vec<String> {
+    let not_enough = || "not enough data".to_string();
+    let wpm = |value: Option<f64>| value.map_or_else(not_enough, |value| format!("{:.2}", value));
+    let mut lines = vec![
+        format!("Mean WPM: {}", wpm(stats.mean_wpm)),
+        format!("Median WPM: {}", wpm(stats.median_wpm)),
+        format!(
+            "Best WPM: {}",
+            stats.best_wpm.as_ref().map_or_else(not_enough, |(wpm, date)| format!("{:.2} on {}", wpm, date)),
+        ),
+        format!(
+            "Mean accuracy: {}",
+            stats.mean_accuracy.map_or_else(not_enough, |accuracy| format!("{:.2}%", accuracy)),
+        ),
+        format!(
+            "Last {} tests against the {} before: {}",
+            TREND_RUNS,
+            TREND_RUNS,
+            stats.recent_trend.map_or_else(
+                || format!("not enough data, it takes {} tests", 2 * TREND_RUNS),
+                |trend| format!("{:+.2} WPM", trend),
+            ),
+        ),
+    ];
+    if stats.skipped > 0 {
+        lines.push(format!(
+            "{} of {} tests are left out, flagged as suspect or without a readable speed",
+            stats.skipped, stats.tests,
+        ));
+    }
+    lines
+}
+
+/// Lines printing the totals of the history, with a note for the tests without a duration
+pub fn summary_lines(summary: &HistorySummary) -> Vec<String> {
+    let mut lines = vec![
+        format!("Total tests: {}", summary.tests),
+        format!("Total practice: {}", format_total(summary.practice_seconds)),
+        format!("Total characters typed: {}", summary.chars_typed),
+    ];
+    if summary.tests_without_duration > 0 {
+        lines.push(format!(
+            "{} older tests saved without a duration are left out of the time and the characters",
+            summary.tests_without_duration,
+        ));
+    }
+    lines
 }
 
-pub fn show_history(number_of_records: NumberOfRecords) -> Result<(), HistoryError> {
-    let records = get_history_records(number_of_records)?;
+/// Number of records with a speed of at least `goal_wpm`
+pub fn count_runs_meeting_goal(records: &[HistoryRecord], goal_wpm: f64) -> usize {
+    records.iter()
+        .filter_map(|record| record.wpm)
+        .filter(|wpm| *wpm >= goal_wpm)
+        .count()
+}
 
+/// Print history records as a table, `history_records_to_json` gives them to scripts
+/// # Arguments:
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `number_of_records` - Number of last records to print
+/// * `filter` - Texts to print the records of, numbers are kept from the whole history
+/// * `goal_wpm` - Speed to count the runs meeting it
+/// * `totals` - Also print the totals and the stats of every matching test
+/// * `newest_first` - List the records from the newest one
+/// * `include_retries` - Count the tests retried with Tab in the stats, the totals always count them
+pub fn show_history(
+    history_file_path: &Path,
+    number_of_records: NumberOfRecords,
+    filter: &TextFilter,
+    goal_wpm: Option<f64>,
+    totals: bool,
+    newest_first: bool,
+    include_retries: bool,
+) -> Result<(), HistoryError> {
+    let shown = select_history_records_from_file(history_file_path, number_of_records, filter, newest_first)?;
+    let records: Vec<HistoryRecord> = shown.iter().map(|(_, record)| record.clone()).collect();
+    let records = records.as_slice();
+
+    let mut lines = vec![];
     if records.is_empty() {
-        println!("0 records found");
+        lines.push("0 records found".to_string());
     }
 
-    println!("Last {} records:", records.len());
-    println!("ID\tWPM\tDATE\t\tTIME\t\tACCURACY");
-    for record in records {
-        let formatter_row_data = record.iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<String>>()
-            .join("\t");
-        println!("{}%", formatter_row_data);
+    let of_filter = match filter {
+        TextFilter::All => String::new(),
+        filter => format!(" of {}", filter),
+    };
+    let order = if newest_first { ", newest first" } else { "" };
+    lines.push(format!("Last {} records{}{}:", records.len(), of_filter, order));
+    lines.extend(history_table_lines(&shown));
+    // The trend always goes from the oldest record to the newest one
+    let mut speeds: Vec<f64> = records.iter().filter_map(|record| record.wpm).collect();
+    if newest_first {
+        speeds.reverse();
+    }
+    lines.extend(wpm_trend_line(&speeds, terminal_columns()));
+    if let Some(goal_wpm) = goal_wpm {
+        lines.push(format!(
+            "{} of {} runs met the goal of {:.2} WPM",
+            count_runs_meeting_goal(records, goal_wpm), records.len(), goal_wpm,
+        ));
+    }
+    if totals {
+        let (summary, stats, retries) = history_totals_from_file(history_file_path, filter, include_retries)?;
+        lines.extend(summary_lines(&summary));
+        lines.extend(history_stats_lines(&stats));
+        if retries > 0 {
+            lines.push(format!("Retries left out of the stats: {}", retries));
+        }
     }
+    // Long listings are shown a page at a time in a terminal, but not when piped
+    paginate(&lines, history_page_size(), &mut std::io::stdout().lock(), &mut std::io::stdin().lock())?;
     Ok(())
 }
 
-/// Save test stats to a history file
-pub fn save_history(text_id: &str, current_speed_wpm: f64, accuracy: f64) -> Result<(), HistoryError> {
-    let history_file_path = history_file_absolute_path()?;
+/// Values of a record as `show_history` prints them, with units and - for missing columns
+fn history_cells(record: &HistoryRecord) -> Vec<String> {
+    // Older records don't have every column
+    let cell = |value: Option<String>| value.unwrap_or_else(|| "-".to_string());
+    let decimal = |value: Option<f64>, unit: &str| cell(value.map(|value| format!("{:.2}{}", value, unit)));
+    let count = |value: Option<u64>| cell(value.map(|value| value.to_string()));
+    let yes_no = |value: bool| if value { "yes" } else { "no" }.to_string();
+    vec![
+        cell(Some(record.id.clone()).filter(|id| !id.is_empty())),
+        decimal(record.wpm, ""),
+        cell(Some(record.date.clone()).filter(|date| !date.is_empty())),
+        cell(Some(record.time.clone()).filter(|time| !time.is_empty())),
+        decimal(record.accuracy, "%"),
+        decimal(record.cpm, ""),
+        decimal(record.consistency, "%"),
+        cell(record.time_limit.map(|seconds| format!("{}s", seconds))),
+        count(record.skipped.map(|skipped| skipped as u64)),
+        yes_no(record.suspect),
+        yes_no(record.personal_best),
+        decimal(record.elapsed, "s"),
+        count(record.chars.map(|chars| chars as u64)),
+        count(record.errors.map(|errors| errors as u64)),
+        count(record.difficulty.map(u64::from)),
+        record.source.name().to_string(),
+        count(record.attempt.map(u64::from)),
+        cell(record.text_hash.clone()),
+    ]
+}
+
+/// Lines of the table `show_history` prints, the header and a row for every record
+///
+/// Every column is as wide as its widest cell, with the numbers aligned to the right.
+/// Ids wider than `MAX_ID_WIDTH` lose their start, the name of a text file is at the end of its path.
+/// # Arguments:
+/// * `records` - Records with their number in the whole history, as returned by `select_history_records_from_file`
+pub fn history_table_lines(records: &[(usize, HistoryRecord)]) -> Vec<String> {
+    let header: Vec<String> = std::iter::once("#").chain(HISTORY_HEADER).map(str::to_string).collect();
+    let rows: Vec<Vec<String>> = records.iter()
+        .map(|(number, record)| {
+            let mut cells = history_cells(record);
+            cells[0] = cut_start(&cells[0], MAX_ID_WIDTH);
+            std::iter::once(number.to_string()).chain(cells).collect()
+        })
+        .collect();
+    let widths: Vec<usize> = (0..header.len())
+        .map(|column| std::iter::once(&header).chain(&rows).map(|cells| cells[column].width()).max().unwrap_or(0))
+        .collect();
+
+    std::iter::once(&header)
+        .chain(&rows)
+        .map(|cells| {
+            let line: Vec<String> = cells.iter()
+                .zip(&header)
+                .zip(&widths)
+                .map(|((cell, column), width)| {
+                    let padding = " ".repeat(width - cell.width());
+                    if NUMBER_COLUMNS.contains(&column.as_str()) {
+                        format!("{}{}", padding, cell)
+                    } else {
+                        format!("{}{}", cell, padding)
+                    }
+                })
+                .collect();
+            line.join("  ").trim_end().to_string()
+        })
+        .collect()
+}
+
+/// Text cut to `max_width` columns, an ellipsis takes the place of its start
+fn cut_start(text: &str, max_width: usize) -> String {
+    if text.width() <= max_width {
+        return text.to_string();
+    }
+    let mut width = 1;
+    let mut kept: Vec<char> = text.chars()
+        .rev()
+        .take_while(|c| {
+            width += c.width().unwrap_or(0);
+            width <= max_width
+        })
+        .collect();
+    kept.push('…');
+    kept.into_iter().rev().collect()
+}
+
+/// File formats the history can be exported to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    // Array of `HistoryRecord`
+    Json,
+    // GitHub table with the columns of `show_history`
+    Markdown,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = HistoryError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "json" => Ok(ExportFormat::Json),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            _ => Err(HistoryError::UnknownExportFormat(name.to_string())),
+        }
+    }
+}
+
+/// Write history records to a file, replacing it
+///
+/// The file is written next to the path first and renamed, an existing export isn't left cut off.
+/// # Arguments:
+/// * `records` - Records with their number in the whole history, as returned by `select_history_records_from_file`
+/// * `format` - Format of the file
+/// * `path` - File written
+pub fn export_history<P: AsRef<Path>>(
+    records: &[(usize, HistoryRecord)],
+    format: ExportFormat,
+    path: P,
+) -> Result<(), HistoryError> {
+    let content = match format {
+        ExportFormat::Json => {
+            let records: Vec<&HistoryRecord> = records.iter().map(|(_, record)| record).collect();
+            format!("{}\n", serde_json::to_string_pretty(&records)?)
+        }
+        ExportFormat::Markdown => history_records_to_markdown(records),
+    };
+    write_file_atomically(path.as_ref(), content.as_bytes())
+}
+
+/// GitHub table of history records, with the columns `show_history` prints
+pub fn history_records_to_markdown(records: &[(usize, HistoryRecord)]) -> String {
+    let row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+    let mut table = row(std::iter::once("#").chain(HISTORY_HEADER).map(str::to_string).collect());
+    table.push_str(&row(vec!["---".to_string(); HISTORY_HEADER.len() + 1]));
+    for (number, record) in records {
+        // A pipe in the path of a text file would end the cell
+        let cells = history_cells(record).into_iter().map(|cell| cell.replace('|', "\\|"));
+        table.push_str(&row(std::iter::once(number.to_string()).chain(cells).collect()));
+    }
+    table
+}
+
+/// Line with a sparkline of the speeds of the listed records, oldest first, and their range
+///
+/// None with fewer than two speeds, there is no trend to show.
+/// # Arguments:
+/// * `speeds` - WPM of the records, oldest first
+/// * `width` - Columns the line can take, the sparkline is downsampled to fit
+pub fn wpm_trend_line(speeds: &[f64], width: usize) -> Option<String> {
+    if speeds.len() < 2 {
+        return None;
+    }
+    let min = speeds.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = speeds.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    let label = "WPM trend: ";
+    let range = format!(" {:.0}-{:.0}", min, max);
+    let graph_width = width.saturating_sub(label.len() + range.len()).max(1);
+    Some(format!("{}{}{}", label, trend_sparkline(speeds, graph_width), range))
+}
+
+/// Width of the terminal the history is printed to, from the COLUMNS variable of the shell
+fn terminal_columns() -> usize {
+    std::env::var("COLUMNS").ok()
+        .and_then(|columns| columns.parse().ok())
+        .unwrap_or(DEFAULT_TERMINAL_COLUMNS)
+}
+
+/// Rows of the terminal the history is printed to, from the LINES variable of the shell
+fn terminal_rows() -> usize {
+    std::env::var("LINES").ok()
+        .and_then(|rows| rows.parse().ok())
+        .unwrap_or(DEFAULT_TERMINAL_ROWS)
+}
+
+/// Lines of history shown at once, None to show everything when stdout or stdin isn't a terminal
+///
+/// A page leaves the last row of the terminal to the prompt.
+pub fn history_page_size() -> Option<usize> {
+    let interactive = std::io::stdout().is_terminal() && std::io::stdin().is_terminal();
+    interactive.then(|| terminal_rows().saturating_sub(1).max(1))
+}
+
+/// Write lines a page at a time, waiting for Enter between pages like `more`
+///
+/// Answering q stops before the remaining lines, and the rest is written at once
+/// when the input ends.
+/// # Arguments:
+/// * `lines` - Lines to write, without line breaks
+/// * `page_size` - Lines written before each pause, None to write every line without pausing
+/// * `output` - Where the lines and the prompt are written
+/// * `input` - Where the answers to the prompt are read
+pub fn paginate<W: Write, R: BufRead>(
+    lines: &[String],
+    page_size: Option<usize>,
+    output: &mut W,
+    input: &mut R,
+) -> std::io::Result<()> {
+    let page_size = page_size.unwrap_or(lines.len()).max(1);
+    let mut pages = lines.chunks(page_size).peekable();
+    let mut pausing = true;
+    while let Some(page) = pages.next() {
+        for line in page {
+            writeln!(output, "{}", line)?;
+        }
+        if !pausing || pages.peek().is_none() {
+            continue;
+        }
+        write!(output, "{}", PAGE_PROMPT)?;
+        output.flush()?;
+        let mut answer = String::new();
+        match input.read_line(&mut answer)? {
+            0 => pausing = false,
+            _ if answer.trim().eq_ignore_ascii_case("q") => break,
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Print two records of the history side by side
+/// # Arguments:
+/// * `history_file_path` - File written by `save_history_to_file`
+/// * `first` - Number of the first record, as numbered by `show_history` from 1 for the oldest
+/// * `second` - Number of the record compared to the first one
+pub fn show_history_comparison(history_file_path: &Path, first: usize, second: usize) -> Result<(), HistoryError> {
+    let (first, second) = pick_history_records_from_file(history_file_path, first, second)?;
+    for line in compare_history_records(&first, &second) {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// Get two records of the given history file by their numbers, from 1 for the oldest
+pub fn pick_history_records_from_file<P: AsRef<Path>>(
+    history_file_path: P,
+    first: usize,
+    second: usize,
+) -> Result<(HistoryRecord, HistoryRecord), HistoryError> {
+    let records = get_history_records_from_file(history_file_path, NumberOfRecords::All, &TextFilter::All)?;
+    Ok((record_by_number(&records, first)?, record_by_number(&records, second)?))
+}
+
+/// Get a record of the given history file by its number, from 1 for the oldest like in the --history listing
+pub fn history_record_from_file<P: AsRef<Path>>(history_file_path: P, number: usize) -> Result<HistoryRecord, HistoryError> {
+    let records = get_history_records_from_file(history_file_path, NumberOfRecords::All, &TextFilter::All)?;
+    record_by_number(&records, number)
+}
+
+fn record_by_number(records: &[HistoryRecord], number: usize) -> Result<HistoryRecord, HistoryError> {
+    number.checked_sub(1)
+        .and_then(|index| records.get(index))
+        .cloned()
+        .ok_or(HistoryError::RecordOutOfRange(number, records.len()))
+}
+
+/// Short hash of a text for the HASH column of history
+///
+/// Runs of whitespace count as one space, a text file rewrapped or saved with
+/// other line endings keeps its hash.
+pub fn text_hash(text: &str) -> String {
+    let normalized = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+    checksum(&normalized)[..8].to_string()
+}
+
+/// Where the text of a retaken test is loaded from
+#[derive(Clone, Debug, PartialEq)]
+pub enum RetakeText {
+    // Id of the text in the database, the one of the daily challenge for its records
+    Database(u32),
+    // Path of the text file
+    File(String),
+}
+
+/// How --retake types the text of a history record again
+///
+/// The options that changed the text are read back from the suffixes they added to its id.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Retake {
+    pub text: RetakeText,
+    pub no_punctuation: bool,
+    pub lowercase: bool,
+    // Number of words of a --words sample
+    pub words: Option<u32>,
+    // Hash the text had, None for records saved before the hash
+    pub text_hash: Option<String>,
+}
+
+impl Retake {
+    /// Retake of a record, an error for drills, marathons and endless tests
+    /// # Arguments:
+    /// * `number` - Number of the record in the whole history, for the error message
+    /// * `record` - Record to retake
+    pub fn of_record(number: usize, record: &HistoryRecord) -> Result<Retake, HistoryError> {
+        let not_retakeable = || HistoryError::NotRetakeable(number, record.id.clone());
+        let mut text_id = record.id.as_str();
+        let words = text_id.rsplit_once('#')
+            .and_then(|(rest, words)| Some((rest, words.strip_suffix('w')?.parse::<u32>().ok()?)))
+            .map(|(rest, words)| {
+                text_id = rest;
+                words
+            });
+        let mut strip_suffix = |suffix: &str| match text_id.strip_suffix(suffix) {
+            Some(rest) => {
+                text_id = rest;
+                true
+            }
+            None => false,
+        };
+        // The suffixes are added in this order, so they are taken off the other way around
+        let lowercase = strip_suffix(":lc");
+        let no_punctuation = strip_suffix(":np");
+
+        let text = match record.source {
+            TextSource::Drill => return Err(not_retakeable()),
+            TextSource::File => RetakeText::File(text_id.to_string()),
+            TextSource::Database => match text_id.parse::<u32>() {
+                Ok(id) => RetakeText::Database(id),
+                Err(_) => text_id.strip_prefix("daily-")
+                    .and_then(|date| NaiveDate::parse_from_str(date, "%Y-%m-%d").ok())
+                    .map(|date| RetakeText::Database(daily_text_id(date)))
+                    .ok_or_else(not_retakeable)?,
+            },
+        };
+        Ok(Retake { text, no_punctuation, lowercase, words, text_hash: record.text_hash.clone() })
+    }
+
+    /// Whether the text loaded again is the one of the record, always true without a hash to check
+    pub fn matches(&self, text: &str) -> bool {
+        self.text_hash.as_ref().is_none_or(|hash| *hash == text_hash(text))
+    }
+}
+
+/// Lines showing two history records side by side, with the change from the first to the second
+///
+/// Columns missing from records written by older versions are shown as n/a.
+pub fn compare_history_records(first: &HistoryRecord, second: &HistoryRecord) -> Vec<String> {
+    let row = |label: &str, first: String, second: String, change: String| {
+        format!("{:<12} {:<20} {:<20} {}", label, first, second, change).trim_end().to_string()
+    };
+    let value = |value: Option<f64>, unit: &str| value.map_or("n/a".to_string(), |value| format!("{:.2}{}", value, unit));
+    let change = |first: Option<f64>, second: Option<f64>| match (first, second) {
+        (Some(first), Some(second)) if first != 0.0 => format!("{:+.1}%", (second - first) / first * 100.0),
+        _ => String::new(),
+    };
+    let count = |value: Option<u64>, unit: &str| value.map_or("n/a".to_string(), |value| format!("{}{}", value, unit));
+
+    vec![
+        row("", "First".to_string(), "Second".to_string(), "Change".to_string()),
+        row("Text", first.id.clone(), second.id.clone(), String::new()),
+        row(
+            "Date",
+            format!("{} {}", first.date, first.time),
+            format!("{} {}", second.date, second.time),
+            String::new(),
+        ),
+        row("WPM", value(first.wpm, ""), value(second.wpm, ""), change(first.wpm, second.wpm)),
+        row("Accuracy", value(first.accuracy, "%"), value(second.accuracy, "%"), change(first.accuracy, second.accuracy)),
+        row("CPM", value(first.cpm, ""), value(second.cpm, ""), change(first.cpm, second.cpm)),
+        row(
+            "Consistency",
+            value(first.consistency, "%"),
+            value(second.consistency, "%"),
+            change(first.consistency, second.consistency),
+        ),
+        row("Duration", value(first.elapsed, "s"), value(second.elapsed, "s"), change(first.elapsed, second.elapsed)),
+        row("Time limit", count(first.time_limit, "s"), count(second.time_limit, "s"), String::new()),
+        row(
+            "Skipped",
+            count(first.skipped.map(|skipped| skipped as u64), ""),
+            count(second.skipped.map(|skipped| skipped as u64), ""),
+            String::new(),
+        ),
+        row(
+            "Errors",
+            count(first.errors.map(|errors| errors as u64), ""),
+            count(second.errors.map(|errors| errors as u64), ""),
+            String::new(),
+        ),
+        row("Source", first.source.name().to_string(), second.source.name().to_string(), String::new()),
+        row(
+            "Difficulty",
+            count(first.difficulty.map(u64::from), ""),
+            count(second.difficulty.map(u64::from), ""),
+            String::new(),
+        ),
+        row("Attempt", count(first.attempt.map(u64::from), ""), count(second.attempt.map(u64::from), ""), String::new()),
+    ]
+}
+
+/// Serialize history records as a JSON array of `HistoryRecord`
+pub fn history_records_to_json(records: &[HistoryRecord]) -> Result<String, HistoryError> {
+    Ok(serde_json::to_string(records)?)
+}
 
+/// Save test stats to the given history file
+///
+/// Files written by older versions are migrated to the current columns first.
+pub fn save_history_to_file<P: AsRef<Path>>(
+    history_file_path: P,
+    stats: &SessionStats,
+) -> Result<(), HistoryError> {
+    let history_file_path = history_file_path.as_ref();
     let file_exist = history_file_path.exists();
+    if file_exist {
+        migrate_history_file(history_file_path)?;
+    }
 
-    let file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(history_file_path)?;
+    let file = open_for_append(history_file_path)?;
 
     let mut writer = csv::Writer::from_writer(file);
     if !file_exist {
-        writer.write_record(["ID", "WPM", "DATE", "TIME", "ACCURACY"])?;
-    }
-    let current_time = chrono::Local::now();
-    let format_date = current_time.format("%Y-%m-%d").to_string();
-    let format_time = current_time.format("%H:%M:%S").to_string();
-
-    let test_data = [
-        text_id,
-        &format!("{:.2}", current_speed_wpm),
-        &format_date,
-        &format_time,
-        &format!("{:.2}", accuracy),
-    ];
-    writer.write_record(test_data)?;
+        writer.write_record(HISTORY_HEADER)?;
+    }
+    writer.write_record(&history_row(stats))?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Row of the history file with the stats of a session, in the columns of `HISTORY_HEADER`
+pub fn history_row(stats: &SessionStats) -> StringRecord {
+    let format_date = stats.timestamp.format("%Y-%m-%d").to_string();
+    let format_time = stats.timestamp.format("%H:%M:%S").to_string();
+
+    StringRecord::from(vec![
+        stats.text_id.clone(),
+        format!("{:.2}", stats.wpm),
+        format_date,
+        format_time,
+        format!("{:.2}", stats.accuracy),
+        format!("{:.2}", stats.cpm),
+        format!("{:.2}", stats.consistency),
+        stats.time_limit.map(|seconds| seconds.to_string()).unwrap_or_default(),
+        stats.skipped_words.to_string(),
+        if stats.suspect { "yes" } else { "no" }.to_string(),
+        if stats.personal_best { "yes" } else { "no" }.to_string(),
+        format!("{:.2}", stats.duration),
+        stats.chars_typed.to_string(),
+        stats.errors.to_string(),
+        stats.difficulty.map(|difficulty| difficulty.to_string()).unwrap_or_default(),
+        stats.source.name().to_string(),
+        stats.attempt.map(|attempt| attempt.to_string()).unwrap_or_default(),
+        stats.text_hash.clone().unwrap_or_default(),
+    ])
+}
+
+/// Open a file to add rows at its end, creating it and the directory it goes in
+fn open_for_append(path: &Path) -> Result<std::fs::File, HistoryError> {
+    if let Some(directory) = path.parent().filter(|directory| !directory.as_os_str().is_empty()) {
+        std::fs::create_dir_all(directory)?;
+    }
+    Ok(OpenOptions::new().append(true).create(true).open(path)?)
+}
+
+/// Rewrite the history file with the current header if it was written by an older version
+fn migrate_history_file(history_file_path: &Path) -> Result<(), HistoryError> {
+    let (headers, records) = {
+        let mut reader = csv::Reader::from_path(history_file_path)?;
+        let headers = reader.headers()?.clone();
+        if headers.iter().eq(HISTORY_HEADER) {
+            return Ok(());
+        }
+        let records = reader.records().collect::<Result<Vec<StringRecord>, csv::Error>>()?;
+        (headers, records)
+    };
+
+    let positions = column_positions(&headers);
+    let records: Vec<StringRecord> = records.iter()
+        .map(|record| {
+            let mut normalized = StringRecord::new();
+            normalize_record_into(&positions, record, &mut normalized);
+            normalized
+        })
+        .collect();
+    replace_history_file(history_file_path, &records)
+}
+
+/// Write the history file anew with the current header and the given records
+///
+/// The records are written to a temporary file next to it first, which then takes its place,
+/// so the history isn't left cut off if writing fails halfway.
+fn replace_history_file(history_file_path: &Path, records: &[StringRecord]) -> Result<(), HistoryError> {
+    let temporary_path = temporary_path(history_file_path);
+    let mut writer = csv::Writer::from_path(&temporary_path)?;
+    writer.write_record(HISTORY_HEADER)?;
+    for record in records {
+        writer.write_record(record)?;
+    }
+    writer.into_inner()
+        .map_err(|e| HistoryError::IoError(e.into_error()))?
+        .sync_all()?;
+    std::fs::rename(&temporary_path, history_file_path)?;
+    Ok(())
+}
+
+/// Write a file through a temporary file next to it, see `replace_history_file`
+fn write_file_atomically(path: &Path, content: &[u8]) -> Result<(), HistoryError> {
+    let temporary_path = temporary_path(path);
+    let mut file = std::fs::File::create(&temporary_path)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    std::fs::rename(&temporary_path, path)?;
+    Ok(())
+}
+
+/// Path a file is written to before it replaces the file at `path`
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut temporary_path = path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    PathBuf::from(temporary_path)
+}
+
+/// Remove every record of the given history file, keeping the header
+///
+/// There is nothing to remove when the file doesn't exist.
+/// # Returns:
+/// * `usize` - Number of records removed
+pub fn clear_history_file<P: AsRef<Path>>(history_file_path: P) -> Result<usize, HistoryError> {
+    prune_history_file(history_file_path, 0)
+}
+
+/// Remove the oldest records of the given history file, keeping the newest `keep` ones
+///
+/// The file is written with the current header, like after saving a result to it.
+/// There is nothing to remove when the file doesn't exist.
+pub fn prune_history_file<P: AsRef<Path>>(history_file_path: P, keep: usize) -> Result<usize, HistoryError> {
+    let history_file_path = history_file_path.as_ref();
+    let (kept, total) = match read_history_rows(history_file_path, NumberOfRecords::Last(keep), &TextFilter::All) {
+        Err(HistoryError::FileDoesNotExist | HistoryError::FileIsEmpty) => return Ok(0),
+        result => result?,
+    };
+    let removed = total - kept.len();
+    if removed > 0 {
+        let kept: Vec<StringRecord> = kept.into_iter().map(|(_, row)| row).collect();
+        replace_history_file(history_file_path, &kept)?;
+    }
+    Ok(removed)
+}
+
+/// Index of every column of `HISTORY_HEADER` in a file read with `headers`, None for missing columns
+/// Renamed columns are also found under the name older versions wrote
+fn column_positions(headers: &StringRecord) -> Vec<Option<usize>> {
+    let position = |name: &str| headers.iter().position(|header| header == name);
+    HISTORY_HEADER.iter()
+        .map(|column| {
+            position(column).or_else(|| {
+                RENAMED_HISTORY_COLUMNS.iter().find(|(name, _)| name == column).and_then(|(_, old_name)| position(old_name))
+            })
+        })
+        .collect()
+}
+
+/// Write a record into `normalized` in the columns of `HISTORY_HEADER`, reusing its memory
+/// # Arguments:
+/// * `positions` - Columns of the file the record was read from, from `column_positions`
+/// * `record` - Record as it was read
+/// * `normalized` - Record the columns are written to, its old fields are dropped
+fn normalize_record_into(positions: &[Option<usize>], record: &StringRecord, normalized: &mut StringRecord) {
+    normalized.clear();
+    for position in positions {
+        normalized.push_field(position.and_then(|index| record.get(index)).unwrap_or(""));
+    }
+}
+
+/// Aggregated speed of a word over every saved session
+#[derive(Debug, PartialEq)]
+pub struct WordStats {
+    pub word: String,
+    pub occurrences: usize,
+    pub average_wpm: f64,
+}
+
+/// Save the speed of every word of a test next to the history file
+pub fn save_word_timings(history_file_path: &Path, word_speeds: &[(&str, f64)]) -> Result<(), HistoryError> {
+    save_word_timings_to_file(word_timings_file_path(history_file_path), word_speeds)
+}
+
+/// Save the speed of every word of a test to the given file
+pub fn save_word_timings_to_file<P: AsRef<Path>>(
+    word_timings_file_path: P,
+    word_speeds: &[(&str, f64)],
+) -> Result<(), HistoryError> {
+    let file_exist = word_timings_file_path.as_ref().exists();
+    let file = open_for_append(word_timings_file_path.as_ref())?;
+
+    let mut writer = csv::Writer::from_writer(file);
+    if !file_exist {
+        writer.write_record(WORD_TIMINGS_HEADER)?;
+    }
+    for (word, wpm) in word_speeds {
+        writer.write_record([*word, format!("{:.2}", wpm).as_str()])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Get the words typed the slowest on average across all saved sessions
+/// # Arguments:
+/// * `word_timings_file_path` - File written by `save_word_timings_to_file`
+/// * `count` - Maximum number of words to return
+/// # Returns:
+/// * `Vec<WordStats>` - Slowest words first
+pub fn get_slowest_words_from_file<P: AsRef<Path>>(
+    word_timings_file_path: P,
+    count: usize,
+) -> Result<Vec<WordStats>, HistoryError> {
+    if !word_timings_file_path.as_ref().exists() {
+        return Err(HistoryError::FileDoesNotExist);
+    }
+
+    let mut reader = csv::Reader::from_path(word_timings_file_path)?;
+    let mut word_speeds: Vec<(String, f64)> = vec![];
+    for record in reader.records() {
+        let record = record?;
+        // Skip rows that can't be parsed instead of failing the whole report
+        if let (Some(word), Some(Ok(wpm))) = (record.get(0), record.get(1).map(str::parse::<f64>)) {
+            word_speeds.push((word.to_string(), wpm));
+        }
+    }
+
+    let mut stats = aggregate_word_speeds(&word_speeds);
+    stats.sort_by(|a, b| a.average_wpm.total_cmp(&b.average_wpm));
+    stats.truncate(count);
+    Ok(stats)
+}
+
+/// Merge the speeds of repeated words into one entry per word
+pub fn aggregate_word_speeds(word_speeds: &[(String, f64)]) -> Vec<WordStats> {
+    let mut totals: HashMap<&str, (usize, f64)> = HashMap::new();
+    for (word, wpm) in word_speeds {
+        let total = totals.entry(word.as_str()).or_insert((0, 0.0));
+        total.0 += 1;
+        total.1 += wpm;
+    }
+    totals.into_iter()
+        .map(|(word, (occurrences, wpm_sum))| WordStats {
+            word: word.to_string(),
+            occurrences,
+            average_wpm: wpm_sum / occurrences as f64,
+        })
+        .collect()
+}
+
+/// Save the words mistyped in a test next to the history file
+pub fn save_mistyped_words(history_file_path: &Path, word_errors: &[(String, u32)]) -> Result<(), HistoryError> {
+    save_mistyped_words_to_file(mistyped_words_file_path(history_file_path), word_errors)
+}
+
+/// Save the words mistyped in a test to the given file
+pub fn save_mistyped_words_to_file<P: AsRef<Path>>(
+    mistyped_words_file_path: P,
+    word_errors: &[(String, u32)],
+) -> Result<(), HistoryError> {
+    let file_exist = mistyped_words_file_path.as_ref().exists();
+    let file = open_for_append(mistyped_words_file_path.as_ref())?;
+
+    let mut writer = csv::Writer::from_writer(file);
+    if !file_exist {
+        writer.write_record(MISTYPED_WORDS_HEADER)?;
+    }
+    for (word, errors) in word_errors {
+        writer.write_record([word.as_str(), errors.to_string().as_str()])?;
+    }
     writer.flush()?;
     Ok(())
 }
 
-fn history_file_absolute_path() -> Result<PathBuf, HistoryError> {
-    let history_filename = ".rstype_history.csv";
-    Ok(
-        home::home_dir()
-            .take_if(|p| !p.as_os_str().is_empty())
-            .ok_or(HistoryError::HomeDirError(history_filename.to_string()))?
-            .join(history_filename)
-    )
+/// Get the words mistyped most often across all sessions saved next to the history file
+pub fn get_most_mistyped_words(history_file_path: &Path, count: usize) -> Result<Vec<(String, u32)>, HistoryError> {
+    get_most_mistyped_words_from_file(mistyped_words_file_path(history_file_path), count)
+}
+
+/// Get the words mistyped most often from the given file
+/// # Arguments:
+/// * `mistyped_words_file_path` - File written by `save_mistyped_words_to_file`
+/// * `count` - Maximum number of words to return
+/// # Returns:
+/// * `Vec<(String, u32)>` - Words with their total number of mistakes, most mistyped first
+pub fn get_most_mistyped_words_from_file<P: AsRef<Path>>(
+    mistyped_words_file_path: P,
+    count: usize,
+) -> Result<Vec<(String, u32)>, HistoryError> {
+    if !mistyped_words_file_path.as_ref().exists() {
+        return Err(HistoryError::FileDoesNotExist);
+    }
+
+    let mut reader = csv::Reader::from_path(mistyped_words_file_path)?;
+    let mut totals: HashMap<String, u32> = HashMap::new();
+    for record in reader.records() {
+        let record = record?;
+        // Skip rows that can't be parsed instead of failing the whole report
+        if let (Some(word), Some(Ok(errors))) = (record.get(0), record.get(1).map(str::parse::<u32>)) {
+            *totals.entry(word.to_string()).or_insert(0) += errors;
+        }
+    }
+
+    let mut word_errors: Vec<(String, u32)> = totals.into_iter().collect();
+    word_errors.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    word_errors.truncate(count);
+    Ok(word_errors)
+}
+
+/// Print the words typed the slowest across all sessions saved next to the history file
+pub fn show_slowest_words(history_file_path: &Path, count: usize) -> Result<(), HistoryError> {
+    let slowest = match get_slowest_words_from_file(word_timings_file_path(history_file_path), count) {
+        Err(HistoryError::FileDoesNotExist) => vec![],
+        result => result?,
+    };
+
+    if slowest.is_empty() {
+        println!("No word-level data recorded yet");
+        return Ok(());
+    }
+
+    println!("Slowest {} words:", slowest.len());
+    println!("WORD\t\tAVG WPM\tCOUNT");
+    for stats in slowest {
+        println!("{:<16}{:.2}\t{}", stats.word, stats.average_wpm, stats.occurrences);
+    }
+    Ok(())
+}
+
+/// Best run of a text, replayed as a ghost racing the typist
+#[derive(Debug, Clone, PartialEq)]
+pub struct GhostRun {
+    pub wpm: f64,
+    // Time since the start of the run and the number of characters typed correctly by then
+    pub timeline: Vec<(Duration, usize)>,
+}
+
+impl GhostRun {
+    /// Number of characters the ghost had typed correctly after `elapsed`
+    pub fn progress_at(&self, elapsed: Duration) -> usize {
+        let reached = self.timeline.partition_point(|(time, _)| *time <= elapsed);
+        match reached {
+            0 => 0,
+            _ => self.timeline[reached - 1].1,
+        }
+    }
+
+    fn encode_timeline(&self) -> String {
+        self.timeline.iter()
+            .map(|(time, progress)| format!("{}:{}", time.as_millis(), progress))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn decode_timeline(timeline: &str) -> Option<Vec<(Duration, usize)>> {
+        timeline.split_whitespace()
+            .map(|point| {
+                let (millis, progress) = point.split_once(':')?;
+                Some((Duration::from_millis(millis.parse().ok()?), progress.parse().ok()?))
+            })
+            .collect()
+    }
+}
+
+/// Load the best run saved for a text next to the history file
+pub fn load_ghost(history_file_path: &Path, text_id: &str) -> Result<Option<GhostRun>, HistoryError> {
+    load_ghost_from_file(ghosts_file_path(history_file_path), text_id)
+}
+
+/// Load the best run saved for a text from the given file
+/// # Arguments:
+/// * `ghosts_file_path` - File written by `save_ghost_to_file`
+/// * `text_id` - Id of the text
+/// # Returns:
+/// * `Option<GhostRun>` - None if no run of this text was saved
+pub fn load_ghost_from_file<P: AsRef<Path>>(
+    ghosts_file_path: P,
+    text_id: &str,
+) -> Result<Option<GhostRun>, HistoryError> {
+    Ok(read_ghosts(ghosts_file_path.as_ref())?.remove(text_id))
+}
+
+/// Save a run of a text next to the history file if it is faster than the saved one
+pub fn save_ghost(history_file_path: &Path, text_id: &str, run: &GhostRun) -> Result<bool, HistoryError> {
+    save_ghost_to_file(ghosts_file_path(history_file_path), text_id, run)
+}
+
+/// Save a run of a text to the given file if it is faster than the saved one
+/// # Arguments:
+/// * `ghosts_file_path` - File storing the best run of every text
+/// * `text_id` - Id of the text
+/// * `run` - Run to save
+/// # Returns:
+/// * `bool` - Whether the run replaced the saved one
+pub fn save_ghost_to_file<P: AsRef<Path>>(
+    ghosts_file_path: P,
+    text_id: &str,
+    run: &GhostRun,
+) -> Result<bool, HistoryError> {
+    let mut ghosts = read_ghosts(ghosts_file_path.as_ref())?;
+    if ghosts.get(text_id).is_some_and(|best| best.wpm >= run.wpm) {
+        return Ok(false);
+    }
+    ghosts.insert(text_id.to_string(), run.clone());
+
+    let mut text_ids: Vec<&String> = ghosts.keys().collect();
+    text_ids.sort();
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(GHOSTS_HEADER)?;
+    for text_id in text_ids {
+        let ghost = &ghosts[text_id];
+        writer.write_record([
+            text_id.as_str(),
+            format!("{:.2}", ghost.wpm).as_str(),
+            ghost.encode_timeline().as_str(),
+        ])?;
+    }
+    let content = writer.into_inner().map_err(|e| HistoryError::IoError(e.into_error()))?;
+    // The runs of the other texts aren't lost if writing fails halfway
+    write_file_atomically(ghosts_file_path.as_ref(), &content)?;
+    Ok(true)
+}
+
+/// Mark a lesson as completed in the file next to the history file
+pub fn save_completed_lesson(history_file_path: &Path, lesson_name: &str) -> Result<(), HistoryError> {
+    save_completed_lesson_to_file(lessons_file_path(history_file_path), lesson_name)
+}
+
+/// Mark a lesson as completed in the given file
+pub fn save_completed_lesson_to_file<P: AsRef<Path>>(
+    lessons_file_path: P,
+    lesson_name: &str,
+) -> Result<(), HistoryError> {
+    let file_exist = lessons_file_path.as_ref().exists();
+    let file = open_for_append(lessons_file_path.as_ref())?;
+
+    let mut writer = csv::Writer::from_writer(file);
+    if !file_exist {
+        writer.write_record(LESSONS_HEADER)?;
+    }
+    let date = chrono::Local::now().format("%Y-%m-%d").to_string();
+    writer.write_record([lesson_name, date.as_str()])?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Get the names of the lessons completed in the file next to the history file
+pub fn get_completed_lessons(history_file_path: &Path) -> Result<Vec<String>, HistoryError> {
+    get_completed_lessons_from_file(lessons_file_path(history_file_path))
+}
+
+/// Get the names of the completed lessons from the given file
+/// # Arguments:
+/// * `lessons_file_path` - File written by `save_completed_lesson_to_file`
+/// # Returns:
+/// * `Vec<String>` - Every lesson once, empty if none was completed yet
+pub fn get_completed_lessons_from_file<P: AsRef<Path>>(lessons_file_path: P) -> Result<Vec<String>, HistoryError> {
+    if !lessons_file_path.as_ref().exists() {
+        return Ok(vec![]);
+    }
+
+    let mut reader = csv::Reader::from_path(lessons_file_path)?;
+    let mut completed: Vec<String> = vec![];
+    for record in reader.records() {
+        let record = record?;
+        if let Some(lesson_name) = record.get(0) {
+            if !completed.iter().any(|name| name == lesson_name) {
+                completed.push(lesson_name.to_string());
+            }
+        }
+    }
+    Ok(completed)
+}
+
+fn read_ghosts(ghosts_file_path: &Path) -> Result<HashMap<String, GhostRun>, HistoryError> {
+    let mut ghosts = HashMap::new();
+    if !ghosts_file_path.exists() {
+        return Ok(ghosts);
+    }
+
+    let mut reader = csv::Reader::from_path(ghosts_file_path)?;
+    for record in reader.records() {
+        // Skip rows that can't be read or parsed, the next best run overwrites them
+        let Ok(record) = record else {
+            continue;
+        };
+        if let (Some(text_id), Some(Ok(wpm)), Some(Some(timeline))) = (
+            record.get(0),
+            record.get(1).map(str::parse::<f64>),
+            record.get(2).map(GhostRun::decode_timeline),
+        ) {
+            ghosts.insert(text_id.to_string(), GhostRun { wpm, timeline });
+        }
+    }
+    Ok(ghosts)
+}
+
+/// Companion files are kept next to the history file, with the names they always had in the home directory
+fn lessons_file_path(history_file_path: &Path) -> PathBuf {
+    history_file_path.with_file_name(".rstype_lessons.csv")
+}
+
+fn ghosts_file_path(history_file_path: &Path) -> PathBuf {
+    history_file_path.with_file_name(".rstype_ghosts.csv")
+}
+
+fn mistyped_words_file_path(history_file_path: &Path) -> PathBuf {
+    history_file_path.with_file_name(".rstype_mistakes.csv")
+}
+
+fn word_timings_file_path(history_file_path: &Path) -> PathBuf {
+    history_file_path.with_file_name(".rstype_words.csv")
+}
+
+/// Environment variable with the path of the history file
+pub const HISTORY_FILE_VARIABLE: &str = "RSTYPE_HISTORY";
+
+/// Path of the history file, from the first place that sets it
+///
+/// The --history-file flag comes first, then the `RSTYPE_HISTORY` environment variable,
+/// then `history_file` in the config file, and then `profile_history_file`.
+/// # Arguments:
+/// * `flag` - Path given on the command line
+/// * `config` - Path set in the config file of the profile
+/// * `profile` - Name of the profile the results are kept for
+pub fn resolve_history_file(
+    flag: Option<PathBuf>,
+    config: Option<PathBuf>,
+    profile: &str,
+) -> Result<PathBuf, HistoryError> {
+    let variable = std::env::var_os(HISTORY_FILE_VARIABLE).filter(|path| !path.is_empty()).map(PathBuf::from);
+    if let Some(path) = flag.or(variable).or(config) {
+        return Ok(path);
+    }
+    let (home, data_home) = user_directories().ok_or(HistoryError::HomeDirError("history.csv".to_string()))?;
+    Ok(profile_history_file(&home, data_home.as_deref(), profile))
+}
+
+/// History file of a profile when no other one is set
+///
+/// Profiles keep it in their directory, the default profile uses `default_history_file`.
+/// # Arguments:
+/// * `home` - Home directory
+/// * `data_home` - Value of `XDG_DATA_HOME`, `~/.local/share` when None
+/// * `profile` - Name of the profile
+pub fn profile_history_file(home: &Path, data_home: Option<&Path>, profile: &str) -> PathBuf {
+    match profile_directory(&data_directory(home, data_home), profile) {
+        Some(directory) => directory.join("history.csv"),
+        None => default_history_file(home, data_home),
+    }
+}
+
+/// History file of the default profile
+///
+/// A history written by older versions to the home directory stays there,
+/// new installs keep it in the XDG data directory.
+/// # Arguments:
+/// * `home` - Home directory
+/// * `data_home` - Value of `XDG_DATA_HOME`, `~/.local/share` when None
+pub fn default_history_file(home: &Path, data_home: Option<&Path>) -> PathBuf {
+    let legacy_file = home.join(".rstype_history.csv");
+    if legacy_file.exists() {
+        return legacy_file;
+    }
+    data_directory(home, data_home).join("history.csv")
 }
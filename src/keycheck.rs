@@ -22,6 +22,11 @@ pub fn is_ctrl_t(key: &pancurses::Input) -> bool {
     }
 }
 
+/// Detect Ctrl+Y, which copies the result on the end screen
+pub fn is_ctrl_y(key: &pancurses::Input) -> bool {
+    matches!(key, pancurses::Input::Character('\x19'))
+}
+
 pub fn is_enter(key: &pancurses::Input) -> bool {
     match key {
         pancurses::Input::Character(c) => *c == '\n',
@@ -30,6 +35,21 @@ pub fn is_enter(key: &pancurses::Input) -> bool {
     }
 }
 
+/// Detect the H key, which toggles the keyboard heatmap on the end screen
+pub fn is_h(key: &pancurses::Input) -> bool {
+    matches!(key, pancurses::Input::Character('h' | 'H'))
+}
+
+/// Detect the C key, which saves the result card on the end screen
+pub fn is_c(key: &pancurses::Input) -> bool {
+    matches!(key, pancurses::Input::Character('c' | 'C'))
+}
+
+/// Detect the S key, which saves the replay on the end screen
+pub fn is_s(key: &pancurses::Input) -> bool {
+    matches!(key, pancurses::Input::Character('s' | 'S'))
+}
+
 pub fn is_tab(key: &pancurses::Input) -> bool {
     match key {
         pancurses::Input::Character(c) => *c == '\t',
@@ -50,13 +70,50 @@ pub fn is_backspace(key: &pancurses::Input) -> bool {
     }
 }
 
-pub fn is_ctrl_backspace(key: &pancurses::Input) -> bool {
+/// Detect Ctrl+W, which erases the previous word in shells and editors
+pub fn is_ctrl_w(key: &pancurses::Input) -> bool {
     match key {
         pancurses::Input::Character(c) => *c == '\x17',
         _ => false,
     }
 }
 
+/// Detect Ctrl+Backspace
+///
+/// Terminals don't agree on what it sends. xterm, GNOME Terminal and the other VTE terminals,
+/// Konsole, Alacritty and kitty send `\x08` (Ctrl+H), while Windows Terminal and the Windows
+/// console send `\x17`, the same as Ctrl+W. Terminals whose plain Backspace sends `\x08` get it
+/// translated to `KeyBackspace` by curses, so a `\x08` character is only ever Ctrl+Backspace.
+pub fn is_ctrl_backspace(key: &pancurses::Input) -> bool {
+    match key {
+        pancurses::Input::Character(c) => *c == '\x08' || *c == '\x17',
+        _ => false,
+    }
+}
+
+/// Detect the keys that erase the previous word, Ctrl+W and Ctrl+Backspace
+pub fn is_erase_word(key: &pancurses::Input) -> bool {
+    is_ctrl_w(key) || is_ctrl_backspace(key)
+}
+
+/// Detect Ctrl+U, which clears the whole current word
+pub fn is_ctrl_u(key: &pancurses::Input) -> bool {
+    match key {
+        pancurses::Input::Character(c) => *c == '\x15',
+        _ => false,
+    }
+}
+
+/// Detect the key that skips the current word
+///
+/// Ctrl+N rather than Ctrl+S, which many terminals use to pause output.
+pub fn is_ctrl_n(key: &pancurses::Input) -> bool {
+    match key {
+        pancurses::Input::Character(c) => *c == '\x0e',
+        _ => false,
+    }
+}
+
 pub fn get_key_mapping(key: &pancurses::Input) -> String {
     match key {
         pancurses::Input::Character(c) => c.to_string(),
@@ -65,3 +122,15 @@ pub fn get_key_mapping(key: &pancurses::Input) -> String {
         }
     }
 }
+
+/// Readable name of a key for showing in stats
+pub fn get_key_name(key: &pancurses::Input) -> String {
+    match key {
+        pancurses::Input::Character(' ') => "space".to_string(),
+        key if is_backspace(key) => "backspace".to_string(),
+        pancurses::Input::Character(c) if !c.is_control() => c.to_string(),
+        c => {
+            format!("{:?}", c)
+        }
+    }
+}
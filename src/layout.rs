@@ -0,0 +1,65 @@
+/// Characters of the QWERTY keys, unshifted then shifted, row by row
+const QWERTY: &str = concat!(
+    "`1234567890-=", "qwertyuiop[]\\", "asdfghjkl;'", "zxcvbnm,./",
+    "~!@#$%^&*()_+", "QWERTYUIOP{}|", "ASDFGHJKL:\"", "ZXCVBNM<>?",
+);
+/// Characters of the same keys on the emulated layouts, in the same order as `QWERTY`
+const COLEMAK: &str = concat!(
+    "`1234567890-=", "qwfpgjluy;[]\\", "arstdhneio'", "zxcvbkm,./",
+    "~!@#$%^&*()_+", "QWFPGJLUY:{}|", "ARSTDHNEIO\"", "ZXCVBKM<>?",
+);
+const DVORAK: &str = concat!(
+    "`1234567890[]", "',.pyfgcrl/=\\", "aoeuidhtns-", ";qjkxbmwvz",
+    "~!@#$%^&*(){}", "\"<>PYFGCRL?+|", "AOEUIDHTNS_", ":QJKXBMWVZ",
+);
+const WORKMAN: &str = concat!(
+    "`1234567890-=", "qdrwbjfup;[]\\", "ashtgyneoi'", "zxmcvkl,./",
+    "~!@#$%^&*()_+", "QDRWBJFUP:{}|", "ASHTGYNEOI\"", "ZXMCVKL<>?",
+);
+
+/// Keyboard layout emulated on top of a QWERTY keyboard
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Layout {
+    Colemak,
+    Dvorak,
+    Workman,
+}
+
+impl Layout {
+    /// Name of the layout shown in the header
+    pub fn name(&self) -> &'static str {
+        match self {
+            Layout::Colemak => "colemak",
+            Layout::Dvorak => "dvorak",
+            Layout::Workman => "workman",
+        }
+    }
+
+    fn characters(&self) -> &'static str {
+        match self {
+            Layout::Colemak => COLEMAK,
+            Layout::Dvorak => DVORAK,
+            Layout::Workman => WORKMAN,
+        }
+    }
+}
+
+/// Unshifted QWERTY key that types `c`, so 'A' and 'a' are both the 'a' key.
+///
+/// Characters that aren't on the main keys give `None`.
+pub fn base_key(c: char) -> Option<char> {
+    let key_count = QWERTY.chars().count() / 2;
+    QWERTY.chars()
+        .position(|qwerty| qwerty == c)
+        .and_then(|index| QWERTY.chars().nth(index % key_count))
+}
+
+/// Character typed by a QWERTY key when the keyboard is set to `layout`.
+///
+/// Characters that aren't on the main keys, like space and control keys, are left as they are.
+pub fn remap(layout: Layout, c: char) -> char {
+    QWERTY.chars()
+        .position(|qwerty| qwerty == c)
+        .and_then(|index| layout.characters().chars().nth(index))
+        .unwrap_or(c)
+}
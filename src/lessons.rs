@@ -0,0 +1,129 @@
+use crate::generator::{generate_key_drill, DEFAULT_MAX_WORD_LENGTH};
+use crate::PreparedText;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// Number of words in the generated lessons
+pub const LESSON_WORDS: usize = 30;
+/// Accuracy needed to pass a lesson, in percent
+pub const LESSON_MIN_ACCURACY: f64 = 95.0;
+
+const LETTERS: &str = "abcdefghijklmnopqrstuvwxyz";
+const PUNCTUATION: &str = ",.;:!?";
+const SENTENCES: [&str; 8] = [
+    "The sun is up.",
+    "We sat by the lake.",
+    "She reads a book at night.",
+    "Tom has a red kite.",
+    "It is cold, so wear a coat.",
+    "Can you see the stars?",
+    "My dog likes long walks.",
+    "Rain fell all day.",
+];
+
+/// Kind of text practiced in a lesson
+enum LessonKind {
+    Keys(&'static str),
+    Capitals,
+    Punctuation,
+    Sentences,
+}
+
+/// One step of the lesson progression
+pub struct Lesson {
+    pub name: &'static str,
+    pub description: &'static str,
+    kind: LessonKind,
+}
+
+/// Lessons in the order they are meant to be completed
+pub const LESSONS: [Lesson; 6] = [
+    Lesson { name: "home-row", description: "Home row keys", kind: LessonKind::Keys("asdfghjkl;") },
+    Lesson { name: "top-row", description: "Top row keys", kind: LessonKind::Keys("qwertyuiop") },
+    Lesson { name: "bottom-row", description: "Bottom row keys", kind: LessonKind::Keys("zxcvbnm,./") },
+    Lesson { name: "capitals", description: "Words starting with a capital", kind: LessonKind::Capitals },
+    Lesson { name: "punctuation", description: "Words followed by punctuation", kind: LessonKind::Punctuation },
+    Lesson { name: "sentences", description: "Short sentences", kind: LessonKind::Sentences },
+];
+
+/// What a lesson taken in the app has to reach to be completed
+#[derive(Clone, Debug, PartialEq)]
+pub struct LessonGoal {
+    pub name: String,
+    pub min_wpm: f64,
+}
+
+/// First lesson of the progression that wasn't completed yet
+/// # Arguments
+/// * `completed` - Names of the completed lessons
+/// # Returns
+/// * `Option<&Lesson>` - None when every lesson is completed
+pub fn next_lesson(completed: &[String]) -> Option<&'static Lesson> {
+    LESSONS.iter().find(|lesson| !completed.iter().any(|name| name == lesson.name))
+}
+
+/// Whether the results of a test pass a lesson
+pub fn is_lesson_passed(accuracy: f64, wpm: f64, min_wpm: f64) -> bool {
+    accuracy >= LESSON_MIN_ACCURACY && wpm >= min_wpm
+}
+
+/// Generate the text of a lesson.
+/// # Arguments
+/// * `lesson` - Lesson to practice
+/// * `seed` - The same seed always generates the same text
+/// # Returns
+/// * `PreparedText` with an id like "lesson:home-row:42"
+pub fn lesson_text(lesson: &Lesson, seed: u64) -> PreparedText {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let letter_words = || {
+        let (text, _) = generate_key_drill(LETTERS, LESSON_WORDS, DEFAULT_MAX_WORD_LENGTH, seed)
+            .expect("the letters are never empty");
+        text
+    };
+    let text = match lesson.kind {
+        LessonKind::Keys(keys) => {
+            let (text, _) = generate_key_drill(keys, LESSON_WORDS, DEFAULT_MAX_WORD_LENGTH, seed)
+                .expect("lesson keys are never empty");
+            text
+        }
+        LessonKind::Capitals => letter_words()
+            .split(' ')
+            .map(|word| {
+                let mut chars = word.chars();
+                chars.next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            })
+            .collect::<Vec<String>>()
+            .join(" "),
+        LessonKind::Punctuation => {
+            let marks: Vec<char> = PUNCTUATION.chars().collect();
+            letter_words()
+                .split(' ')
+                .map(|word| format!("{}{}", word, marks[rng.gen_range(0..marks.len())]))
+                .collect::<Vec<String>>()
+                .join(" ")
+        }
+        LessonKind::Sentences => (0..SENTENCES.len() / 2)
+            .map(|_| SENTENCES[rng.gen_range(0..SENTENCES.len())])
+            .collect::<Vec<&str>>()
+            .join(" "),
+    };
+    (text, format!("lesson:{}:{}", lesson.name, seed))
+}
+
+/// Print the lesson progression with the completed lessons marked
+pub fn show_lessons(completed: &[String]) {
+    let next = next_lesson(completed).map(|lesson| lesson.name);
+    for (number, lesson) in LESSONS.iter().enumerate() {
+        let status = if completed.iter().any(|name| name == lesson.name) {
+            "done"
+        } else if next == Some(lesson.name) {
+            "next"
+        } else {
+            ""
+        };
+        let line = format!("{}. {:<12} {:<32} {}", number + 1, lesson.name, lesson.description, status);
+        println!("{}", line.trim_end());
+    }
+}
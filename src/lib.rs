@@ -1,69 +1,169 @@
 use std::fmt::Formatter;
 use std::path::Path;
-use std::time::SystemTimeError;
 use crate::database::DatabaseError;
 
 pub mod app;
+pub mod cast;
 pub mod calculations;
+pub mod card;
+pub mod challenge;
+pub mod clipboard;
+pub mod config;
 pub mod database;
+pub mod generator;
+pub mod heatmap;
 pub mod history;
 pub mod keycheck;
+pub mod layout;
+pub mod lessons;
+pub mod output;
+pub mod profile;
+pub mod replay;
+pub mod session;
+pub mod share;
+pub mod suspend;
+pub mod terminal;
+pub mod text_transform;
+pub mod theme;
 pub mod timer;
+pub mod webhook;
 
 pub type AppResult<T> = Result<T, AppError>;
 
 #[derive(Debug)]
 pub enum AppError {
-    NoIndexFoundError(usize),
-    NoCharFoundError(char),
-    ColorNotFoundError(app::Color),
-    TimeError(SystemTimeError),
     AppDatabaseError(DatabaseError),
-    ParsingError(std::num::ParseIntError),
     AppHistoryError(history::HistoryError),
-    TwitterError { url: String , error_description: String },
+    EmptyTextError(String),
+    AppGeneratorError(generator::GeneratorError),
+    AppConfigError(config::ConfigError),
+    AppThemeError(theme::ThemeError),
+    AppJsonError(serde_json::Error),
+    AppOutputError(output::OutputError),
+    AppReplayError(replay::ReplayError),
+    AppTemplateError(share::TemplateError),
+    AppChallengeError(challenge::ChallengeError),
+    AppProfileError(profile::ProfileError),
+    AppFileError(FileError),
+    WindowTooSmallError,
+    WindowTooNarrowError(i32),
 }
 
 impl std::fmt::Display for AppError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            AppError::NoIndexFoundError(index) => {
-                write!(f, "No index found in text: {}", index)
+            AppError::AppDatabaseError(e) => {
+                write!(f, "Database error: {}", e)
             }
-            AppError::NoCharFoundError(c) => {
-                write!(f, "No character found in text: {}", c)
+            AppError::AppHistoryError(e) => {
+                write!(f, "History error: {}", e)
             }
-            AppError::ColorNotFoundError(color) => {
-                write!(f, "Color for terminal not found: {:?}", color)
+            AppError::EmptyTextError(text_id) => {
+                write!(f, "Nothing left to type in text: {}", text_id)
             }
-            AppError::TimeError(e) => {
-                write!(f, "Time error: {}", e)
+            AppError::AppGeneratorError(e) => {
+                write!(f, "Generator error: {}", e)
             }
-            AppError::AppDatabaseError(e) => {
-                write!(f, "Database error: {}", e)
+            AppError::AppConfigError(e) => {
+                write!(f, "Config error: {}", e)
             }
-            AppError::ParsingError(e) => {
-                write!(f, "Parsing error: {}", e)
+            AppError::AppThemeError(e) => {
+                write!(f, "Theme error: {}", e)
             }
-            AppError::AppHistoryError(e) => {
-                write!(f, "History error: {}", e)
+            AppError::AppJsonError(e) => {
+                write!(f, "JSON error: {}", e)
+            }
+            AppError::AppOutputError(e) => {
+                write!(f, "Output error: {}", e)
+            }
+            AppError::AppReplayError(e) => {
+                write!(f, "Replay error: {}", e)
+            }
+            AppError::AppTemplateError(e) => {
+                write!(f, "Template error: {}", e)
+            }
+            AppError::AppChallengeError(e) => {
+                write!(f, "Challenge error: {}", e)
+            }
+            AppError::AppProfileError(e) => {
+                write!(f, "Profile error: {}", e)
+            }
+            AppError::AppFileError(e) => {
+                write!(f, "Text file error: {}", e)
             }
-            AppError::TwitterError { url, error_description } => {
-                write!(f, "Can't tweet result: {}\n{}", url, error_description)
+            AppError::WindowTooSmallError => {
+                write!(f, "Window too small to print given text")
+            }
+            AppError::WindowTooNarrowError(min_width) => {
+                write!(f, "Terminal too narrow, it needs at least {} columns", min_width)
             }
         }
     }
 }
 
-impl From<history::HistoryError> for AppError {
-    fn from(value: history::HistoryError) -> Self {
-        AppError::AppHistoryError(value)
+impl From<theme::ThemeError> for AppError {
+    fn from(value: theme::ThemeError) -> Self {
+        AppError::AppThemeError(value)
+    }
+}
+
+impl From<output::OutputError> for AppError {
+    fn from(value: output::OutputError) -> Self {
+        AppError::AppOutputError(value)
+    }
+}
+
+impl From<share::TemplateError> for AppError {
+    fn from(value: share::TemplateError) -> Self {
+        AppError::AppTemplateError(value)
+    }
+}
+
+impl From<replay::ReplayError> for AppError {
+    fn from(value: replay::ReplayError) -> Self {
+        AppError::AppReplayError(value)
     }
 }
 
-impl From<std::num::ParseIntError> for AppError {
-    fn from(value: std::num::ParseIntError) -> Self {
-        AppError::ParsingError(value)
+impl From<challenge::ChallengeError> for AppError {
+    fn from(value: challenge::ChallengeError) -> Self {
+        AppError::AppChallengeError(value)
+    }
+}
+
+impl From<profile::ProfileError> for AppError {
+    fn from(value: profile::ProfileError) -> Self {
+        AppError::AppProfileError(value)
+    }
+}
+
+impl From<FileError> for AppError {
+    fn from(value: FileError) -> Self {
+        AppError::AppFileError(value)
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(value: serde_json::Error) -> Self {
+        AppError::AppJsonError(value)
+    }
+}
+
+impl From<config::ConfigError> for AppError {
+    fn from(value: config::ConfigError) -> Self {
+        AppError::AppConfigError(value)
+    }
+}
+
+impl From<generator::GeneratorError> for AppError {
+    fn from(value: generator::GeneratorError) -> Self {
+        AppError::AppGeneratorError(value)
+    }
+}
+
+impl From<history::HistoryError> for AppError {
+    fn from(value: history::HistoryError) -> Self {
+        AppError::AppHistoryError(value)
     }
 }
 
@@ -73,11 +173,6 @@ impl From<DatabaseError> for AppError {
     }
 }
 
-impl From<SystemTimeError> for AppError {
-    fn from(value: SystemTimeError) -> Self {
-        AppError::TimeError(value)
-    }
-}
 
 
 #[derive(Debug)]
@@ -121,8 +216,26 @@ pub fn load_text_from_file<P: AsRef<Path>>(file_path: P) -> Result<PreparedText,
     }
 }
 
+/// Build a sample of exactly `word_count` words from a text
+///
+/// Longer texts are truncated, shorter ones are repeated from the start.
+/// # Arguments
+/// * `prepared_text` - Text and its id
+/// * `word_count` - Number of words in the sample
+/// # Returns
+/// * `PreparedText` with an id like "42#50w", so history shows where the sample came from
+pub fn prepare_word_count_text(prepared_text: PreparedText, word_count: usize) -> PreparedText {
+    let (text, text_id) = prepared_text;
+    let text = text.split_whitespace()
+        .cycle()
+        .take(word_count)
+        .collect::<Vec<&str>>()
+        .join(" ");
+    (text, format!("{}#{}w", text_id, word_count))
+}
+
 /// Safely close the terminal window and exit the program
 pub fn exit(code: i32) -> ! {
-    pancurses::endwin();
+    terminal::restore_terminal();
     std::process::exit(code);
 }
@@ -1,13 +1,41 @@
-use clap::Parser;
-use rstype::app::App;
+use clap::{ArgGroup, Parser};
+use rstype::app::{App, AppOptions, EndlessSource, ExitReason};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use rstype::database::{
-    load_text_from_database, load_text_from_database_based_on_difficulty,
-    load_text_from_database_with_random_difficulty,
+    load_daily_text, load_text_from_database, load_text_from_database_based_on_difficulty_with_rng,
+    load_text_from_database_with_random_difficulty_with_rng,
 };
+use rstype::generator::{generate_drill, generate_key_drill, generate_mistakes_drill, Drill, DEFAULT_DRILL_WORDS, DEFAULT_MAX_WORD_LENGTH};
 use rstype::{exit, load_text_from_file, AppError, AppResult, PreparedText};
-use rstype::history::{show_history, NumberOfRecords};
+use rstype::calculations::{difficulty_for_stats, WpmFormula};
+use rstype::config::{load_config, save_config_value};
+use rstype::layout::Layout;
+use rstype::theme::{ColorMode, Theme};
+use rstype::suspend;
+use rstype::terminal::{restore_terminal, start_terminal};
+use rstype::output::{output_format, refuse_existing_file};
+use rstype::profile::{check_profile_name, data_directory, list_profiles, user_directories, DEFAULT_PROFILE};
+use rstype::card::result_card;
+use rstype::challenge::load_challenge;
+use rstype::replay::load_replay;
+use rstype::share::{check_share_target, check_template, default_share_targets};
+use rstype::lessons::{lesson_text, next_lesson, show_lessons, LessonGoal};
+use rstype::history::{
+    average_wpm_and_accuracy, clear_history_file, export_history, find_history_record_in_file,
+    get_completed_lessons, get_history_records_from_file, get_most_mistyped_words, history_record_from_file,
+    history_records_to_json, profile_history_file, prune_history_file, resolve_history_file, select_history_records_from_file,
+    show_history, show_history_comparison, show_slowest_words, summarize_weeks, ExportFormat, HistoryError,
+    NumberOfRecords, Retake, RetakeText, TextFilter, WeekSummary,
+};
+use rstype::session::TextSource;
+use rstype::text_transform::TextTransforms;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
+#[clap(group(ArgGroup::new("history_listing").multiple(true).args(["history", "history_export"])))]
 struct Arguments {
     #[clap(short, long, action)]
     /// Show rstype version
@@ -24,31 +52,441 @@ struct Arguments {
     #[clap(short = 'H', long, default_missing_value="0", require_equals = false, num_args=0..=1)]
     /// Show rstype score history
     history: Option<u32>,
+    #[clap(long, action, requires = "history", conflicts_with = "json")]
+    /// Also print the totals, average and best speed and recent trend of the history with --history
+    stats: bool,
+    #[clap(long, action, requires = "stats")]
+    /// Leave the tests retried with Tab out of the stats printed by --stats
+    no_retries: bool,
+    #[clap(long, num_args = 2, value_names = ["FORMAT", "PATH"])]
+    /// Write the history to PATH as json or markdown
+    history_export: Option<Vec<String>>,
+    #[clap(long, value_name = "ID", requires = "history_listing")]
+    /// Only show or export the history of this text, a database id or the path of a text file
+    text_id: Option<String>,
+    #[clap(long, action, requires = "text_id")]
+    /// Show the texts whose id contains the one given with --text-id
+    substring: bool,
+    #[clap(long, value_enum, requires = "history_listing", conflicts_with = "text_id")]
+    /// Only show or export the history of texts from the database, files or drills
+    source: Option<TextSource>,
+    #[clap(long, action, requires = "history_listing")]
+    /// List or export the history from the newest record
+    reverse: bool,
+    #[clap(long, action)]
+    /// Remove every record of the history, after asking
+    history_clear: bool,
+    #[clap(long, action, requires = "history_clear")]
+    /// Don't ask before removing the history
+    yes: bool,
+    #[clap(long, action, requires = "keep", conflicts_with = "history_clear")]
+    /// Remove the oldest records of the history, keeping the number given with --keep
+    history_prune: bool,
+    #[clap(long, value_name = "N", requires = "history_prune")]
+    /// Number of newest records kept by --history-prune
+    keep: Option<usize>,
+    #[clap(long, num_args = 2, value_names = ["N", "M"])]
+    /// Compare two records of the history, numbered like in the --history listing
+    history_compare: Option<Vec<usize>>,
+    #[clap(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["file", "id", "daily", "count", "endless", "drill", "keys", "adaptive", "practice_mistakes", "lesson", "replay", "challenge"],
+    )]
+    /// Type the text of record N of the history again, with the options it was typed with
+    retake: Option<usize>,
+    #[clap(long, action, requires = "retake")]
+    /// Retake a test even when its text file changed since
+    force: bool,
+    #[clap(long, value_name = "N", default_missing_value = "8", require_equals = false, num_args = 0..=1)]
+    /// Show the results of the history week by week, for the last N weeks
+    history_week: Option<usize>,
+    #[clap(long, value_name = "NAME", default_value = DEFAULT_PROFILE)]
+    /// Keep the history, the lesson progress and the goal apart for this profile, it is made on first use
+    profile: String,
+    #[clap(long, action)]
+    /// List the profiles with the number of records in their history
+    profiles: bool,
+    #[clap(long, action)]
+    /// Print the results, or the history with --history, as JSON
+    json: bool,
+    #[clap(long, action, conflicts_with = "json")]
+    /// Print the results as a text card to paste anywhere
+    card: bool,
+    #[clap(short, long, value_name = "FILENAME")]
+    /// Also append the results to FILENAME, as CSV or JSON lines by its extension
+    output: Option<PathBuf>,
+    #[clap(long, action)]
+    /// Don't save the results to the history, `no_history = true` in the config file does the same
+    no_history: bool,
+    #[clap(long, value_name = "PATH")]
+    /// History file to read and save to, before RSTYPE_HISTORY and `history_file` in the config file
+    history_file: Option<PathBuf>,
+    #[clap(long, value_name = "FILENAME")]
+    /// Write every key of the test with its time and whether it was right to a new CSV file
+    dump_keystrokes: Option<PathBuf>,
+    #[clap(long, value_name = "FILENAME")]
+    /// Write the replay of the test to a new asciinema .cast file
+    export_cast: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "FILENAME",
+        conflicts_with_all = ["file", "id", "daily", "count", "endless", "drill", "keys", "adaptive", "practice_mistakes", "lesson"],
+    )]
+    /// Play a replay saved with S on the end screen
+    replay: Option<PathBuf>,
+    #[clap(long, value_name = "FILENAME", conflicts_with_all = ["replay", "challenge"])]
+    /// Write the text and the result of the test to a new challenge file for a friend to beat
+    export_challenge: Option<PathBuf>,
+    #[clap(
+        long,
+        value_name = "FILENAME",
+        conflicts_with_all = ["file", "id", "daily", "count", "endless", "drill", "keys", "adaptive", "practice_mistakes", "lesson", "replay"],
+    )]
+    /// Type the text of a challenge file, compare the result and add it to the file
+    challenge: Option<PathBuf>,
+    #[clap(long, value_name = "N", default_missing_value = "10", num_args = 0..=1)]
+    /// Show the N words typed the slowest across all sessions
+    slow_words: Option<usize>,
+    #[clap(long, value_enum, default_value = "words")]
+    /// Speed figure to save in history: words of the text, gross (every 5 typed characters, shown as Raw)
+    /// or net (gross minus the mistakes) per minute
+    wpm_formula: WpmFormula,
+    #[clap(long, action)]
+    /// Show speed over the last 10 seconds while typing
+    rolling_wpm: bool,
+    #[clap(long, value_name = "WPM")]
+    /// Mark where a typist going at WPM would be in the text
+    pace: Option<f64>,
+    #[clap(long, action)]
+    /// Race against the best saved run of the same text
+    ghost: bool,
+    #[clap(long, value_name = "SECONDS")]
+    /// End the test after SECONDS and score the text typed so far
+    time: Option<u64>,
+    #[clap(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    /// Use exactly N words of the text, repeating it if it is shorter
+    words: Option<u32>,
+    #[clap(long, action)]
+    /// Keep showing new text until Esc is pressed
+    endless: bool,
+    #[clap(long, value_name = "N", conflicts_with = "file", value_parser = clap::value_parser!(u32).range(1..))]
+    /// Type N database texts back to back as one test
+    count: Option<u32>,
+    #[clap(long, action, conflicts_with_all = ["file", "id"])]
+    /// Type today's challenge, the same text for everyone on the same day
+    daily: bool,
+    #[clap(long, value_name = "SEED")]
+    /// Seed for choosing random texts, the same seed always picks the same text
+    seed: Option<u64>,
+    #[clap(long, action)]
+    /// Don't accept mistyped characters, only count them as errors
+    strict: bool,
+    #[clap(long, action)]
+    /// Move on from a wrong word with space, counting it as errors
+    skip_wrong_words: bool,
+    #[clap(long, action)]
+    /// Let backspace go back into words that were already accepted
+    backtrack: bool,
+    #[clap(long, action)]
+    /// Hide typed text, mistakes and live speed until the test ends
+    blind: bool,
+    #[clap(long, action)]
+    /// Show only the text while typing, the stats appear at the end
+    zen: bool,
+    #[clap(long, action)]
+    /// Count the characters of words skipped with Ctrl+N as mistakes
+    count_skipped_as_errors: bool,
+    #[clap(long, action)]
+    /// Remove punctuation from the text
+    no_punctuation: bool,
+    #[clap(long, action)]
+    /// Lowercase the whole text
+    lowercase: bool,
+    #[clap(long, value_enum, value_name = "KIND", conflicts_with_all = ["file", "id", "daily", "count"])]
+    /// Practice a generated text of numbers or symbols instead of a database text
+    drill: Option<Drill>,
+    #[clap(long, value_name = "KEYS", conflicts_with_all = ["file", "id", "daily", "count", "drill"])]
+    /// Practice a generated text using only the given keys
+    keys: Option<String>,
+    #[clap(long, action, conflicts_with_all = ["file", "id", "difficulty", "daily", "drill", "keys"])]
+    /// Choose the difficulty from the speed and accuracy of recent tests
+    adaptive: bool,
+    #[clap(long, action, conflicts_with_all = ["file", "id", "daily", "count", "drill", "keys", "adaptive"])]
+    /// Practice a text made of the words mistyped most often in earlier tests
+    practice_mistakes: bool,
+    #[clap(long, value_enum, value_name = "LAYOUT")]
+    /// Type as if the QWERTY keyboard were set to another layout
+    emulate_layout: Option<Layout>,
+    #[clap(
+        long,
+        value_enum,
+        value_name = "COMMAND",
+        conflicts_with_all = ["file", "id", "daily", "count", "endless", "drill", "keys", "adaptive", "practice_mistakes"],
+    )]
+    /// Take the next lesson of the progression or list the lessons
+    lesson: Option<LessonCommand>,
+    #[clap(long, value_name = "WPM", default_value = "20")]
+    /// Speed needed to pass a lesson
+    lesson_wpm: f64,
+    #[clap(long, action)]
+    /// Don't flag very fast bursts of keys as pasted text, for steno
+    allow_fast_input: bool,
+    #[clap(long, value_name = "WPM")]
+    /// Speed to beat, saved in the config for the next runs, 0 turns it off
+    goal: Option<f64>,
+    #[clap(long, value_name = "NAME")]
+    /// Color theme: default, ocean, high-contrast, transparent or solarized
+    theme: Option<String>,
+    #[clap(long, action, conflicts_with = "colorblind")]
+    /// Draw with bold, dim and reverse text only
+    no_color: bool,
+    #[clap(long, action)]
+    /// Use blue and orange instead of green and red, and underline mistakes
+    colorblind: bool,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, clap::ValueEnum)]
+enum LessonCommand {
+    /// Take the first lesson not completed yet
+    Next,
+    /// Show the lessons and which ones are completed
+    List,
+}
+
+/// Number of recent history records the adaptive difficulty is based on
+const ADAPTIVE_HISTORY_RECORDS: usize = 10;
+
+/// Number of most mistyped words a mistakes drill picks from
+const PRACTICED_MISTYPED_WORDS: usize = 50;
+
 fn main() {
     let args = Arguments::parse();
 
     if let Err(e) = run_app_with_args(args) {
+        // The message would be cleared along with the screen
+        restore_terminal();
         eprintln!("{}", e);
         exit(1);
     }
 }
 
-fn run_app_with_args(args: Arguments) -> AppResult<()> {
+fn run_app_with_args(mut args: Arguments) -> AppResult<()> {
+    let mut options = AppOptions {
+        wpm_formula: args.wpm_formula,
+        rolling_wpm: args.rolling_wpm,
+        pace_wpm: args.pace,
+        ghost: args.ghost,
+        time_limit: args.time.map(Duration::from_secs),
+        endless: None,
+        marathon: vec![],
+        transforms: TextTransforms::default(),
+        strict: args.strict,
+        skip_wrong_words: args.skip_wrong_words,
+        backtrack: args.backtrack,
+        blind: args.blind,
+        zen: args.zen,
+        count_skipped_as_errors: args.count_skipped_as_errors,
+        difficulty: None,
+        text_source: TextSource::default(),
+        layout: args.emulate_layout,
+        lesson: None,
+        allow_fast_input: args.allow_fast_input,
+        goal_wpm: None,
+        theme: Theme::default(),
+        output: args.output.take(),
+        no_history: args.no_history,
+        history_file: PathBuf::new(),
+        webhook_url: None,
+        dump_keystrokes: args.dump_keystrokes.take(),
+        export_cast: args.export_cast.take(),
+        export_challenge: args.export_challenge.take(),
+        seed: None,
+        challenge: None,
+        share_template: None,
+        copy_template: None,
+        share_targets: default_share_targets(),
+        color_mode: if args.no_color {
+            ColorMode::Monochrome
+        } else if args.colorblind {
+            ColorMode::Colorblind
+        } else {
+            ColorMode::Full
+        },
+    };
+    // Checked before the curses screen hides error messages
+    if let Some(output) = &options.output {
+        output_format(output)?;
+    }
+    for path in options.dump_keystrokes.iter().chain(&options.export_cast).chain(&options.export_challenge) {
+        refuse_existing_file(path)?;
+    }
+    check_profile_name(&args.profile)?;
+    // A goal given on the command line is kept for the next runs
+    if let Some(goal) = args.goal {
+        save_config_value(&args.profile, "goal_wpm", &goal.to_string())?;
+    }
+    let config = load_config(&args.profile)?;
+    args.goal = args.goal.or(config.goal_wpm).filter(|goal| *goal > 0.0);
+    options.goal_wpm = args.goal;
+    options.webhook_url = config.webhook_url;
+    options.no_history |= config.no_history;
+    options.history_file = resolve_history_file(args.history_file.take(), config.history_file, &args.profile)?;
+    // Checked before the curses screen hides error messages
+    for template in config.share_template.iter().chain(&config.copy_template) {
+        check_template(template)?;
+    }
+    for target in &config.share_targets {
+        check_share_target(target)?;
+    }
+    options.share_template = config.share_template;
+    options.copy_template = config.copy_template;
+    if !config.share_targets.is_empty() {
+        options.share_targets = config.share_targets;
+    }
+    // Checked before the curses screen hides error messages
+    let theme_name = args.theme.take().or(config.theme).unwrap_or_else(|| "default".to_string());
+    options.theme = Theme::builtin(&theme_name)?;
+    for (role, colors) in &config.theme_colors {
+        options.theme.set_role(role, colors)?;
+    }
+    let format = ResultFormat::from_args(&args);
+    // A saved replay brings its own text, and nothing is saved once it is played
+    if let Some(path) = args.replay.take() {
+        let replay = load_replay(path)?;
+        return run_app(App::from_saved_replay(&replay, options), format);
+    }
+    // A challenge brings its own text, the database it came from isn't needed
+    if let Some(path) = args.challenge.take() {
+        let challenge = load_challenge(&path)?;
+        let prepared_text = (challenge.text.clone(), challenge.text_id.clone());
+        options.seed = challenge.seed;
+        // The file doesn't say where its text came from
+        options.text_source = TextSource::guess(&challenge.text_id);
+        options.challenge = Some((path, challenge));
+        return run_app(App::with_options(prepared_text, options), format);
+    }
+    // A retake loads the text of the record like it was given on the command line
+    let retake = args.retake.map(|number| retake_record(&mut args, &options.history_file, number)).transpose()?;
+    let endless = args.endless;
+    let count = args.count;
+    let seed = args.seed.unwrap_or_else(|| rand::thread_rng().random());
+    options.seed = Some(seed);
+    let mut rng = StdRng::seed_from_u64(seed);
+    let local_text = args.file.is_some() || args.drill.is_some() || args.keys.is_some() || args.practice_mistakes;
+    if args.adaptive {
+        let difficulty = adaptive_difficulty(&options.history_file, &mut rng).unwrap_or_else(|| rng.gen_range(1..=5));
+        args.difficulty = Some(difficulty);
+        options.difficulty = Some(difficulty);
+    }
+    let difficulty = args.difficulty.unwrap_or(2);
+    let lesson = if args.lesson == Some(LessonCommand::Next) {
+        let Some(lesson) = next_lesson(&get_completed_lessons(&options.history_file)?) else {
+            println!("All lessons completed, well done!");
+            exit(0)
+        };
+        options.lesson = Some(LessonGoal { name: lesson.name.to_string(), min_wpm: args.lesson_wpm });
+        Some(lesson)
+    } else {
+        None
+    };
+    options.text_source = if lesson.is_some() || args.drill.is_some() || args.keys.is_some() || args.practice_mistakes {
+        TextSource::Drill
+    } else if args.file.is_some() {
+        TextSource::File
+    } else {
+        TextSource::Database
+    };
     // Start the parser
-    let prepared_text = resolve_command_line_args(args)?;
+    let force = args.force;
+    options.transforms = text_transforms(&args);
+    let prepared_text = match lesson {
+        Some(lesson) => lesson_text(lesson, seed),
+        None => resolve_command_line_args(args, options.transforms, &options.history_file, seed, &mut rng)?,
+    };
+    if let Some((number, retake)) = retake {
+        if !retake.matches(&prepared_text.0) {
+            let changed = HistoryError::TextChanged(number, prepared_text.1.clone());
+            if !force {
+                return Err(changed.into());
+            }
+            // Printed before the curses screen, it is still there once the test is left
+            eprintln!("Warning: {}", changed);
+        }
+    }
+    // More text comes from the same place as the first one
+    if endless && local_text {
+        options.endless = Some(EndlessSource::Words(
+            prepared_text.0.split_whitespace().map(str::to_string).collect(),
+        ));
+    } else if endless {
+        options.endless = Some(EndlessSource::Database(difficulty));
+    }
+    for _ in 1..count.unwrap_or(1) {
+        let prepared_text = load_text_from_database_based_on_difficulty_with_rng(difficulty, "data.db", &mut rng)?;
+        options.marathon.push(options.transforms.apply(prepared_text)?);
+    }
+
+    run_app(App::with_options(prepared_text, options), format)
+}
+
+/// How the results are printed once the app is left
+enum ResultFormat {
+    Text,
+    Json,
+    Card,
+}
 
-    let mut app = App::from_prepared_text(prepared_text);
+impl ResultFormat {
+    fn from_args(args: &Arguments) -> ResultFormat {
+        if args.json {
+            ResultFormat::Json
+        } else if args.card {
+            ResultFormat::Card
+        } else {
+            ResultFormat::Text
+        }
+    }
+}
 
-    let window = pancurses::initscr();
+/// Run the app in the curses screen, then print the results
+fn run_app(mut app: App, format: ResultFormat) -> AppResult<()> {
+    let mut window = start_terminal();
     pancurses::start_color();
+    suspend::install_resume_handler();
     window.refresh();
-    app.run(&window)
+    let exit_reason = app.run(&mut window)?;
+    restore_terminal();
+    // Printed once the curses screen is gone, so it stays in the terminal
+    match (format, exit_reason) {
+        (ResultFormat::Json, exit_reason) => println!("{}", serde_json::to_string(exit_reason.stats())?),
+        (ResultFormat::Card, ExitReason::Completed(stats)) => println!("{}", result_card(&stats).join("\n")),
+        (ResultFormat::Text, ExitReason::Completed(stats)) => println!("{}", stats),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Changes made to every text of the test, from --no-punctuation, --lowercase and --words
+fn text_transforms(args: &Arguments) -> TextTransforms {
+    // Drills are generated with the requested number of words already
+    let generated = args.drill.is_some() || args.keys.is_some() || args.practice_mistakes;
+    TextTransforms {
+        no_punctuation: args.no_punctuation,
+        lowercase: args.lowercase,
+        words: args.words.filter(|_| !generated).map(|words| words as usize),
+    }
 }
 
-fn resolve_command_line_args(args: Arguments) -> Result<PreparedText, AppError> {
+fn resolve_command_line_args(
+    args: Arguments,
+    transforms: TextTransforms,
+    history_file: &Path,
+    seed: u64,
+    rng: &mut StdRng,
+) -> Result<PreparedText, AppError> {
     let database_file = "data.db";
+    let drill_words = args.words.map_or(DEFAULT_DRILL_WORDS, |words| words as usize);
     let prepared_text: PreparedText = if args.version {
         println!("Rstype version 0.1.0");
         exit(0)
@@ -57,16 +495,186 @@ fn resolve_command_line_args(args: Arguments) -> Result<PreparedText, AppError>
             0 => NumberOfRecords::All,
             _ => NumberOfRecords::Last(history as usize),
         };
-        show_history(number_of_records)?;
+        let filter = text_filter(args.text_id, args.substring, args.source);
+        if args.json {
+            let records = select_history_records_from_file(history_file, number_of_records, &filter, args.reverse)?;
+            let records: Vec<_> = records.into_iter().map(|(_, record)| record).collect();
+            println!("{}", history_records_to_json(&records)?);
+        } else {
+            show_history(history_file, number_of_records, &filter, args.goal, args.stats, args.reverse, !args.no_retries)?;
+        }
         exit(0)
+    } else if let Some(export) = args.history_export {
+        let (format, path): (ExportFormat, &str) = (export[0].parse()?, &export[1]);
+        let filter = text_filter(args.text_id, args.substring, args.source);
+        let records = select_history_records_from_file(history_file, NumberOfRecords::All, &filter, args.reverse)?;
+        export_history(&records, format, path)?;
+        println!("Exported {} records to {}", records.len(), path);
+        exit(0)
+    } else if let Some(numbers) = args.history_compare {
+        show_history_comparison(history_file, numbers[0], numbers[1])?;
+        exit(0)
+    } else if args.history_clear {
+        if args.yes || confirm("Remove every record of the history?") {
+            println!("Removed {} records from the history", clear_history_file(history_file)?);
+        }
+        exit(0)
+    } else if let (true, Some(keep)) = (args.history_prune, args.keep) {
+        println!("Removed {} records from the history", prune_history_file(history_file, keep)?);
+        exit(0)
+    } else if let Some(weeks) = args.history_week {
+        let records = get_history_records_from_file(history_file, NumberOfRecords::All, &TextFilter::All)?;
+        show_weekly_summary(&summarize_weeks(&records, chrono::Local::now().date_naive(), weeks));
+        exit(0)
+    } else if args.profiles {
+        show_profiles()?;
+        exit(0)
+    } else if args.lesson == Some(LessonCommand::List) {
+        show_lessons(&get_completed_lessons(history_file)?);
+        exit(0)
+    } else if let Some(count) = args.slow_words {
+        show_slowest_words(history_file, count)?;
+        exit(0)
+    } else if args.daily {
+        let prepared_text = load_daily_text(chrono::Local::now().date_naive(), database_file)?;
+        confirm_daily_retry(history_file, &prepared_text.1)?;
+        prepared_text
+    } else if let Some(drill) = args.drill {
+        generate_drill(drill, drill_words, DEFAULT_MAX_WORD_LENGTH, seed)
+    } else if let Some(keys) = args.keys {
+        generate_key_drill(&keys, drill_words, DEFAULT_MAX_WORD_LENGTH, seed)?
+    } else if args.practice_mistakes {
+        let word_errors = match get_most_mistyped_words(history_file, PRACTICED_MISTYPED_WORDS) {
+            Err(HistoryError::FileDoesNotExist) => vec![],
+            result => result?,
+        };
+        if word_errors.is_empty() {
+            println!("No mistakes recorded yet, finish a few tests first");
+            exit(0)
+        }
+        generate_mistakes_drill(&word_errors, drill_words, seed)?
     } else if let Some(file_path) = args.file {
-        load_text_from_file(file_path).unwrap()
+        load_text_from_file(file_path)?
     } else if let Some(id) = args.id {
         load_text_from_database(id, database_file)?
     } else if let Some(difficulty) = args.difficulty {
-        load_text_from_database_based_on_difficulty(difficulty, database_file)?
+        load_text_from_database_based_on_difficulty_with_rng(difficulty, database_file, rng)?
     } else {
-        load_text_from_database_with_random_difficulty(database_file)?
+        load_text_from_database_with_random_difficulty_with_rng(database_file, rng)?
+    };
+    transforms.apply(prepared_text)
+}
+
+/// Point the arguments at the text of a history record, with the options that changed it
+/// # Returns:
+/// * `(usize, Retake)` - Number of the record with its retake, to check the loaded text against
+fn retake_record(args: &mut Arguments, history_file: &Path, number: usize) -> Result<(usize, Retake), HistoryError> {
+    let retake = Retake::of_record(number, &history_record_from_file(history_file, number)?)?;
+    match &retake.text {
+        RetakeText::Database(id) => args.id = Some(*id),
+        RetakeText::File(path) => args.file = Some(path.clone()),
+    }
+    args.no_punctuation = retake.no_punctuation;
+    args.lowercase = retake.lowercase;
+    args.words = retake.words;
+    Ok((number, retake))
+}
+
+/// Texts the history is shown or exported for, from --text-id, --substring and --source
+fn text_filter(text_id: Option<String>, substring: bool, source: Option<TextSource>) -> TextFilter {
+    match (text_id, source) {
+        (Some(text_id), _) if substring => TextFilter::Contains(text_id),
+        (Some(text_id), _) => TextFilter::Exact(text_id),
+        (None, Some(source)) => TextFilter::Source(source),
+        (None, None) => TextFilter::All,
+    }
+}
+
+/// Choose a difficulty close to the level of the last tests in history
+///
+/// Only database texts have a difficulty, drills and files are left out.
+/// Returns `None` when there is no history to go by.
+fn adaptive_difficulty(history_file: &Path, rng: &mut StdRng) -> Option<u32> {
+    let database_texts = TextFilter::Source(TextSource::Database);
+    let last_records = NumberOfRecords::Last(ADAPTIVE_HISTORY_RECORDS);
+    let records = get_history_records_from_file(history_file, last_records, &database_texts).ok()?;
+    let (wpm, accuracy) = average_wpm_and_accuracy(&records)?;
+    // Now and then a step easier or harder, so the same level doesn't get stale
+    let jitter = match rng.gen_range(0..4) {
+        0 => -1,
+        1 => 1,
+        _ => 0,
     };
-    Ok(prepared_text)
+    Some(difficulty_for_stats(wpm, accuracy).saturating_add_signed(jitter).clamp(1, 5))
+}
+
+/// Print the results of every week, oldest first
+///
+/// Each week gets an arrow comparing its average speed to the last week with tests before it.
+fn show_weekly_summary(weeks: &[WeekSummary]) {
+    println!("{:<10} {:<11} {:>5} {:>8} {:>8} {:>9}", "WEEK", "FROM", "TESTS", "AVG WPM", "BEST WPM", "ACCURACY");
+    let mut previous_wpm: Option<f64> = None;
+    for week in weeks {
+        let label = format!("{}-W{:02}", week.year, week.week);
+        let (Some(average_wpm), Some(best_wpm)) = (week.average_wpm, week.best_wpm) else {
+            println!("{:<10} {:<11} {:>5}", label, week.start, "-");
+            continue;
+        };
+        let trend = match previous_wpm {
+            Some(previous) if average_wpm > previous => "↑",
+            Some(previous) if average_wpm < previous => "↓",
+            Some(_) => "→",
+            None => "",
+        };
+        let accuracy = week.average_accuracy.map_or("-".to_string(), |accuracy| format!("{:.1}%", accuracy));
+        let line = format!(
+            "{:<10} {:<11} {:>5} {:>8.2} {:>8.2} {:>9} {}",
+            label, week.start, week.tests, average_wpm, best_wpm, accuracy, trend,
+        );
+        println!("{}", line.trim_end());
+        previous_wpm = Some(average_wpm);
+    }
+}
+
+/// Print every profile with the number of records in its history
+fn show_profiles() -> AppResult<()> {
+    let (home, data_home) = user_directories().ok_or(HistoryError::HomeDirError("profiles".to_string()))?;
+    println!("{:<20} {:>7}", "PROFILE", "RECORDS");
+    for profile in list_profiles(&data_directory(&home, data_home.as_deref()))? {
+        // Where the profile keeps its history without --history-file or RSTYPE_HISTORY
+        let history_file = load_config(&profile)?.history_file
+            .unwrap_or_else(|| profile_history_file(&home, data_home.as_deref(), &profile));
+        let records = match get_history_records_from_file(history_file, NumberOfRecords::All, &TextFilter::All) {
+            Err(HistoryError::FileDoesNotExist | HistoryError::FileIsEmpty) => 0,
+            result => result?.len(),
+        };
+        println!("{:<20} {:>7}", profile, records);
+    }
+    Ok(())
+}
+
+/// Show the result of today's challenge if it was already typed and ask to retry it
+fn confirm_daily_retry(history_file: &Path, text_id: &str) -> AppResult<()> {
+    let Some(record) = find_history_record_in_file(history_file, text_id)? else {
+        return Ok(());
+    };
+    println!(
+        "You already completed today's challenge: {} WPM, {}% accuracy",
+        record.wpm.map_or("-".to_string(), |wpm| format!("{:.2}", wpm)),
+        record.accuracy.map_or("-".to_string(), |accuracy| format!("{:.2}", accuracy)),
+    );
+    if !confirm("Retry?") {
+        exit(0)
+    }
+    Ok(())
+}
+
+/// Ask a yes or no question, anything but y is a no
+fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).is_ok()
+        && answer.trim().eq_ignore_ascii_case("y")
 }
@@ -0,0 +1,146 @@
+use std::fmt::Formatter;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use crate::history::{history_row, HISTORY_HEADER};
+use crate::keycheck::get_key_name;
+use crate::session::{SessionStats, SimulatedKey};
+
+/// Columns of the keystroke log
+pub const KEYSTROKES_HEADER: [&str; 4] = ["offset_ms", "key", "expected_char", "correct"];
+
+/// Format of a results file, picked by its extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    // The columns of the history file
+    Csv,
+    // One JSON object per line
+    Json,
+}
+
+#[derive(Debug)]
+pub enum OutputError {
+    UnknownFormat(String),
+    FileExists(String),
+    IoError(String, std::io::Error),
+    CsvError(String, csv::Error),
+    JsonError(String, serde_json::Error),
+}
+
+impl std::fmt::Display for OutputError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OutputError::UnknownFormat(path) => {
+                write!(f, "Unknown format of results file: {}, use a .csv, .json or .jsonl file", path)
+            }
+            OutputError::FileExists(path) => {
+                write!(f, "The file already exists, choose another one or remove it: {}", path)
+            }
+            OutputError::IoError(path, e) => {
+                write!(f, "An IO error occurred for file: {}, {}", path, e)
+            }
+            OutputError::CsvError(path, e) => {
+                write!(f, "Error writing CSV to file: {}, {}", path, e)
+            }
+            OutputError::JsonError(path, e) => {
+                write!(f, "Error writing JSON to file: {}, {}", path, e)
+            }
+        }
+    }
+}
+
+/// Format of the results file at `path`
+pub fn output_format<P: AsRef<Path>>(path: P) -> Result<OutputFormat, OutputError> {
+    let path = path.as_ref();
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_lowercase);
+    match extension.as_deref() {
+        Some("csv") => Ok(OutputFormat::Csv),
+        Some("json" | "jsonl") => Ok(OutputFormat::Json),
+        _ => Err(OutputError::UnknownFormat(path.display().to_string())),
+    }
+}
+
+/// Append the stats of a session to a results file
+///
+/// A new or empty CSV file gets the header of the history file first.
+/// JSON files get one object per line, so a session can be added without reading the file.
+/// Everything is written with a single append, so sessions ending at once don't mix their lines.
+/// # Arguments:
+/// * `path` - File ending with .csv, .json or .jsonl
+/// * `stats` - Stats of the finished session
+pub fn save_results_to_file<P: AsRef<Path>>(path: P, stats: &SessionStats) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+
+    let content = match output_format(path)? {
+        OutputFormat::Csv => {
+            let is_empty = std::fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+            let mut writer = csv::Writer::from_writer(vec![]);
+            if is_empty {
+                writer.write_record(HISTORY_HEADER).map_err(|e| OutputError::CsvError(get_path(), e))?;
+            }
+            writer.write_record(&history_row(stats)).map_err(|e| OutputError::CsvError(get_path(), e))?;
+            writer.into_inner().map_err(|e| OutputError::IoError(get_path(), e.into_error()))?
+        }
+        OutputFormat::Json => {
+            let mut line = serde_json::to_vec(stats).map_err(|e| OutputError::JsonError(get_path(), e))?;
+            line.push(b'\n');
+            line
+        }
+    };
+
+    let mut file = OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(path)
+        .map_err(|e| OutputError::IoError(get_path(), e))?;
+    file.write_all(&content).map_err(|e| OutputError::IoError(get_path(), e))
+}
+
+/// Fail if the file is there already, so a file given on the command line isn't replaced by accident
+pub fn refuse_existing_file<P: AsRef<Path>>(path: P) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    match std::fs::exists(path) {
+        Ok(false) => Ok(()),
+        Ok(true) => Err(OutputError::FileExists(path.display().to_string())),
+        Err(e) => Err(OutputError::IoError(path.display().to_string(), e)),
+    }
+}
+
+/// Write every key of a session to a CSV file with the columns of `KEYSTROKES_HEADER`
+///
+/// The file is replaced, retrying a test keeps the keys of the last one.
+/// Keys are named like in the heatmap, "space" and "backspace" included,
+/// `expected_char` and `correct` are left empty for editing keys.
+/// # Arguments:
+/// * `path` - File to write
+/// * `keys` - Keys replayed against the text with `TypingSession::simulate`
+pub fn save_keystrokes_to_file<P: AsRef<Path>>(path: P, keys: &[SimulatedKey]) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+
+    let mut writer = csv::Writer::from_path(path).map_err(|e| OutputError::CsvError(get_path(), e))?;
+    writer.write_record(KEYSTROKES_HEADER).map_err(|e| OutputError::CsvError(get_path(), e))?;
+    for key in keys {
+        let correct = match key.correct {
+            Some(true) => "yes",
+            Some(false) => "no",
+            None => "",
+        };
+        writer.write_record([
+            key.time.as_millis().to_string(),
+            get_key_name(&key.key),
+            key.expected.map(|c| get_key_name(&pancurses::Input::Character(c))).unwrap_or_default(),
+            correct.to_string(),
+        ]).map_err(|e| OutputError::CsvError(get_path(), e))?;
+    }
+    writer.flush().map_err(|e| OutputError::IoError(get_path(), e))
+}
+
+/// Write a whole file at once, replacing it
+pub fn save_to_file<P: AsRef<Path>>(path: P, content: &str) -> Result<(), OutputError> {
+    let path = path.as_ref();
+    std::fs::write(path, content).map_err(|e| OutputError::IoError(path.display().to_string(), e))
+}
@@ -0,0 +1,83 @@
+use std::fmt::Formatter;
+use std::path::{Path, PathBuf};
+
+/// Profile used without --profile, it keeps the files of the versions before profiles
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Config file in the directory of a profile, read over the one in the home directory
+pub const PROFILE_CONFIG_FILE: &str = "config";
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidName(String),
+    IoError(std::io::Error),
+}
+
+impl std::fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProfileError::InvalidName(name) => {
+                write!(f, "Invalid profile name: {:?}, it can't be empty, start with a dot or hold a slash", name)
+            }
+            ProfileError::IoError(e) => {
+                write!(f, "An IO error occurred: {}", e)
+            }
+        }
+    }
+}
+
+impl From<std::io::Error> for ProfileError {
+    fn from(e: std::io::Error) -> Self {
+        ProfileError::IoError(e)
+    }
+}
+
+/// Check that a profile name can be the name of its directory
+pub fn check_profile_name(name: &str) -> Result<(), ProfileError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(ProfileError::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+/// Home directory of the user with the XDG data directory, None without a home directory
+pub fn user_directories() -> Option<(PathBuf, Option<PathBuf>)> {
+    let home = home::home_dir().take_if(|p| !p.as_os_str().is_empty())?;
+    // The XDG spec says to ignore a relative data directory
+    let data_home = std::env::var_os("XDG_DATA_HOME").map(PathBuf::from).filter(|path| path.is_absolute());
+    Some((home, data_home))
+}
+
+/// Directory rstype keeps its data in
+/// # Arguments:
+/// * `home` - Home directory
+/// * `data_home` - Value of `XDG_DATA_HOME`, `~/.local/share` when None
+pub fn data_directory(home: &Path, data_home: Option<&Path>) -> PathBuf {
+    data_home
+        .map_or_else(|| home.join(".local").join("share"), Path::to_path_buf)
+        .join("rstype")
+}
+
+/// Directory of a profile in the data directory, None for the default profile
+pub fn profile_directory(data_directory: &Path, name: &str) -> Option<PathBuf> {
+    (name != DEFAULT_PROFILE).then(|| data_directory.join(name))
+}
+
+/// Names of the profiles in the data directory, the default profile first
+///
+/// Every directory is a profile, it is made the first time its results are saved.
+pub fn list_profiles(data_directory: &Path) -> Result<Vec<String>, ProfileError> {
+    let mut names = vec![];
+    if data_directory.is_dir() {
+        for entry in std::fs::read_dir(data_directory)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.file_type()?.is_dir() && name != DEFAULT_PROFILE && check_profile_name(&name).is_ok() {
+                names.push(name);
+            }
+        }
+    }
+    names.sort();
+    names.insert(0, DEFAULT_PROFILE.to_string());
+    Ok(names)
+}
@@ -0,0 +1,131 @@
+use std::fmt::Formatter;
+use std::path::Path;
+use std::time::Duration;
+use pancurses::Input;
+use serde::{Deserialize, Serialize};
+use crate::keycheck::is_backspace;
+
+/// Version of the replay files written by this version, older and newer ones are refused
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum ReplayError {
+    IoError(String, std::io::Error),
+    JsonError(String, serde_json::Error),
+    UnsupportedVersion(String, u32),
+    TextMismatch(String),
+}
+
+impl std::fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplayError::IoError(path, e) => {
+                write!(f, "An IO error occurred for replay file: {}, {}", path, e)
+            }
+            ReplayError::JsonError(path, e) => {
+                write!(f, "Not a replay file: {}, {}", path, e)
+            }
+            ReplayError::UnsupportedVersion(path, version) => {
+                write!(f, "Replay file {} has version {}, this version of rstype plays version {}", path, version, REPLAY_VERSION)
+            }
+            ReplayError::TextMismatch(path) => {
+                write!(f, "The text in replay file {} was changed, it doesn't match the typed keys anymore", path)
+            }
+        }
+    }
+}
+
+/// Replay of a test kept in a JSON file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedReplay {
+    pub version: u32,
+    pub text_id: String,
+    pub text: String,
+    // FNV-1a hash of the text, the keys only make sense on the text they were typed on
+    pub text_checksum: String,
+    // Milliseconds since the previous key, or since the start of the test for the first one, with the key typed
+    pub keys: Vec<(u64, char)>,
+}
+
+impl SavedReplay {
+    /// Replay of the keys typed on a text
+    /// # Arguments:
+    /// * `text_id` - Id of the text
+    /// * `text` - Text the keys were typed on, without the padding of the wrapped lines
+    /// * `key_strokes` - Keys with the time since the start of the test, keys that don't type or erase anything are left out
+    pub fn new(text_id: &str, text: &str, key_strokes: &[(Duration, Input)]) -> SavedReplay {
+        let mut previous_time = Duration::ZERO;
+        let keys = key_strokes.iter()
+            .filter_map(|(time, key)| {
+                // Backspace is kept as the character some terminals send for it
+                let c = match key {
+                    key if is_backspace(key) => '\x7f',
+                    Input::Character(c) => *c,
+                    _ => return None,
+                };
+                let delay = time.saturating_sub(previous_time);
+                previous_time = *time;
+                Some((delay.as_millis() as u64, c))
+            })
+            .collect();
+        SavedReplay {
+            version: REPLAY_VERSION,
+            text_id: text_id.to_string(),
+            text: text.to_string(),
+            text_checksum: checksum(text),
+            keys,
+        }
+    }
+
+    /// Keys with the time since the start of the test, like `TypingSession::key_strokes`
+    pub fn key_strokes(&self) -> Vec<(Duration, Input)> {
+        let mut time = Duration::ZERO;
+        self.keys.iter()
+            .map(|(delay, c)| {
+                time += Duration::from_millis(*delay);
+                (time, Input::Character(*c))
+            })
+            .collect()
+    }
+}
+
+/// Write a replay to a JSON file, replacing it
+pub fn save_replay<P: AsRef<Path>>(path: P, replay: &SavedReplay) -> Result<(), ReplayError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+    let content = serde_json::to_string(replay).map_err(|e| ReplayError::JsonError(get_path(), e))?;
+    std::fs::write(path, content).map_err(|e| ReplayError::IoError(get_path(), e))
+}
+
+/// Read a replay file written by `save_replay`
+///
+/// Files of another version and files whose text doesn't match its checksum are refused.
+pub fn load_replay<P: AsRef<Path>>(path: P) -> Result<SavedReplay, ReplayError> {
+    let path = path.as_ref();
+    let get_path = || path.display().to_string();
+    let content = std::fs::read_to_string(path).map_err(|e| ReplayError::IoError(get_path(), e))?;
+
+    // The version is checked before the rest, later versions may change the other fields
+    #[derive(Deserialize)]
+    struct Version {
+        version: u32,
+    }
+    let Version { version } = serde_json::from_str(&content).map_err(|e| ReplayError::JsonError(get_path(), e))?;
+    if version != REPLAY_VERSION {
+        return Err(ReplayError::UnsupportedVersion(get_path(), version));
+    }
+
+    let replay: SavedReplay = serde_json::from_str(&content).map_err(|e| ReplayError::JsonError(get_path(), e))?;
+    if replay.text_checksum != checksum(&replay.text) {
+        return Err(ReplayError::TextMismatch(get_path()));
+    }
+    Ok(replay)
+}
+
+/// FNV-1a hash of a text, stable across versions and platforms unlike the hasher of std
+pub(crate) fn checksum(text: &str) -> String {
+    let hash = text.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
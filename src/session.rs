@@ -0,0 +1,836 @@
+use crate::calculations::{
+    accuracy, chars_per_minute, consistency, first_index_at_which_strings_differ, get_space_count_after_ith_word,
+    gross_wpm, latency_stats, net_wpm, slice_by_char_indices, speed_in_wpm_with_minutes, word_index_at, word_wrap,
+    words_per_minute, wpm_buckets, LatencyStats, WpmFormula, CONSISTENCY_MAX_PAUSE_SECONDS,
+};
+use crate::keycheck::{is_backspace, is_ctrl_n, is_ctrl_u, is_erase_word};
+use chrono::{DateTime, Local};
+use pancurses::Input;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Formatter;
+use std::time::Duration;
+
+/// Keystrokes kept for the replay and the stats of an endless test
+const MAX_KEY_STROKES: usize = 10_000;
+
+/// Length of the time slices of the speed over the course of a test
+const WPM_GRAPH_BUCKET: Duration = Duration::from_secs(5);
+
+/// Where the text of a session came from, saved to history
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, clap::ValueEnum)]
+pub enum TextSource {
+    /// Text of the database, picked by id, difficulty or date
+    #[default]
+    #[serde(rename = "db")]
+    #[value(name = "db")]
+    Database,
+    /// Text file given on the command line
+    #[serde(rename = "file")]
+    File,
+    /// Words generated for a drill or a lesson
+    #[serde(rename = "drill")]
+    Drill,
+}
+
+impl TextSource {
+    /// Name saved in the SOURCE column of the history
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextSource::Database => "db",
+            TextSource::File => "file",
+            TextSource::Drill => "drill",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TextSource> {
+        [TextSource::Database, TextSource::File, TextSource::Drill].into_iter().find(|source| source.name() == name)
+    }
+
+    /// Source of a text saved before the source was recorded, guessed from its id
+    ///
+    /// Database texts have numbers or the date of the daily challenge, drills and lessons
+    /// have a prefix, and any other id is the path of a file.
+    pub fn guess(text_id: &str) -> TextSource {
+        if text_id.parse::<u32>().is_ok() || text_id.starts_with("daily-") {
+            TextSource::Database
+        } else if text_id.starts_with("drill:") || text_id.starts_with("lesson:") {
+            TextSource::Drill
+        } else {
+            TextSource::File
+        }
+    }
+}
+
+/// Rules that change what the typed keys do and how the session is scored
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SessionRules {
+    // Mistyped characters are counted but not typed
+    pub strict: bool,
+    // Space moves on from a wrong word instead of waiting for it to be fixed
+    pub skip_wrong_words: bool,
+    // Backspace at the start of a word goes back into the previous one
+    pub backtrack: bool,
+    // Characters of skipped words count as mistakes instead of being left out
+    pub count_skipped_as_errors: bool,
+    // Speed figure reported as the result
+    pub wpm_formula: WpmFormula,
+}
+
+/// Results of a finished typing session
+///
+/// The same figures are shown on the end screen, saved to history and printed when the app exits.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct SessionStats {
+    // Id of the text, the one saved to history
+    pub text_id: String,
+    // When the session was finished
+    pub timestamp: DateTime<Local>,
+    // Speed chosen with the WPM formula, the result of the session
+    pub wpm: f64,
+    // Speed in words of the text, only the completed ones when the text wasn't finished
+    pub words_wpm: f64,
+    // Speed based on 5-character words
+    // Raw counts every typed character, net only the ones that weren't erased
+    pub raw_wpm: f64,
+    pub net_wpm: f64,
+    // Characters per minute, not counting mistakes
+    pub cpm: f64,
+    pub accuracy: f64,
+    // Steadiness of the intervals between keystrokes
+    pub consistency: f64,
+    // Seconds the session took
+    pub duration: f64,
+    // Characters typed, including the ones erased again
+    pub chars_typed: usize,
+    // Mistyped positions of the text
+    pub errors: usize,
+    // Words given up on with Ctrl+N
+    pub skipped_words: usize,
+    // Every character of the text was typed
+    pub completed: bool,
+    // Time limit of a timed test in seconds, set by the frontend that timed it
+    pub time_limit: Option<u64>,
+    // Difficulty of a text from the database, set by the frontend that loaded it
+    pub difficulty: Option<u32>,
+    // Where the text came from, set by the frontend that loaded it
+    pub source: TextSource,
+    // Attempt at the text since it was loaded, from 1, set by the frontend that counted the retries
+    pub attempt: Option<u32>,
+    // Short hash of the text, set by the frontend for tests on a single text
+    pub text_hash: Option<String>,
+    // Keys came in too fast to be typed by hand, set by the frontend that checked them
+    pub suspect: bool,
+    // Faster than every earlier result in history, set by the frontend that checked it
+    pub personal_best: bool,
+    // Time between keystrokes, None if fewer than two keys were pressed
+    #[serde(skip)]
+    pub latency: Option<LatencyStats>,
+    // Speed within every few seconds of the session
+    #[serde(skip)]
+    pub wpm_over_time: Vec<f64>,
+    // Number of characters typed correctly after every recorded key
+    #[serde(skip)]
+    pub timeline: Vec<(Duration, usize)>,
+}
+
+impl SessionStats {
+    /// Result in a few words for sharing, like "72.4 WPM, 97.1% accuracy, 54s"
+    ///
+    /// Copied with Ctrl+Y and shared with Ctrl+T on the end screen.
+    pub fn share_line(&self) -> String {
+        format!("{:.1} WPM, {:.1}% accuracy, {:.0}s", self.wpm, self.accuracy, self.duration)
+    }
+}
+
+impl std::fmt::Display for SessionStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {:.2} WPM, {:.2} raw WPM, {:.2}% accuracy, {} errors, {} characters in {:.1}s",
+            self.text_id, self.wpm, self.raw_wpm, self.accuracy, self.errors, self.chars_typed, self.duration,
+        )
+    }
+}
+
+/// How the typing moved past a word, undone when backspace goes back into it
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WordEnd {
+    // Typed right and accepted
+    Accepted,
+    // Moved on from while wrong, with `SessionRules::skip_wrong_words`
+    Skipped,
+    // Given up on with Ctrl+N
+    SkippedByKey,
+}
+
+/// A recorded key replayed against the text, see `TypingSession::simulate`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimulatedKey {
+    // Time since the start of the session
+    pub time: Duration,
+    pub key: Input,
+    // Character of the text at the cursor when a character was typed, None for editing keys
+    pub expected: Option<char>,
+    // Whether the typed character was the one expected, None for editing keys
+    pub correct: Option<bool>,
+}
+
+/// Typing logic of a test, without anything drawn.
+///
+/// Keys are applied to the typed text with `press`, `backspace` and the other
+/// editing methods, or with `process_key` for curses keys. Recording when they
+/// were pressed with `record_key` is up to the caller.
+pub struct TypingSession {
+    // Text to type, padded with spaces where it is wrapped, and the text as it was given
+    text: String,
+    original_text: String,
+    tokens: Vec<String>,
+
+    // Current typed word and entire string
+    current_word: String,
+    current_string: String,
+    // Restrict current word length to a limit
+    // limit is set to the length of largest word in string + 5 for buffer
+    current_word_limit: usize,
+
+    // Stores keypress, time since start of the test tuple
+    key_strokes: Vec<(Duration, Input)>,
+    // Maps mistyped text positions to the number of times they were mistyped
+    mistyped_keys: HashMap<usize, u32>,
+    // Stores completed word, seconds taken to type it tuple
+    word_timings: Vec<(String, f64)>,
+
+    // Keep track of the token index in text
+    token_index: usize,
+
+    total_chars_typed: usize,
+    // Typed characters removed again with backspace or ctrl-backspace
+    chars_erased: usize,
+    // Words of the current page moved on from without fixing them, and the characters
+    // typed in every word skipped so far, earlier pages included
+    skipped_words: usize,
+    chars_in_skipped_words: usize,
+    // What was typed for every word accepted on the current page, and how it was moved past
+    committed_words: Vec<(String, WordEnd)>,
+    // Words given up on with Ctrl+N and the text positions they left untyped
+    words_skipped_by_key: usize,
+    skipped_keys: HashSet<usize>,
+
+    // Endless mode, progress on the pages finished before the current one
+    earlier_words: usize,
+    earlier_errors: usize,
+    earlier_mistyped_words: Vec<(String, u32)>,
+    // Index of the first keystroke typed on the current page
+    page_start_key: usize,
+
+    rules: SessionRules,
+    // The session was scored, typing it again for a replay doesn't time the words again
+    finished: bool,
+}
+
+impl TypingSession {
+    pub fn new(text: &str) -> Self {
+        Self::with_rules(text, SessionRules::default())
+    }
+
+    pub fn with_rules(text: &str, rules: SessionRules) -> Self {
+        let mut session = Self {
+            text: String::new(),
+            original_text: String::new(),
+            tokens: vec![],
+            current_word: String::new(),
+            current_string: String::new(),
+            current_word_limit: 0,
+            key_strokes: vec![],
+            mistyped_keys: HashMap::new(),
+            word_timings: vec![],
+            token_index: 0,
+            total_chars_typed: 0,
+            chars_erased: 0,
+            skipped_words: 0,
+            chars_in_skipped_words: 0,
+            committed_words: vec![],
+            words_skipped_by_key: 0,
+            skipped_keys: HashSet::new(),
+            earlier_words: 0,
+            earlier_errors: 0,
+            earlier_mistyped_words: vec![],
+            page_start_key: 0,
+            rules,
+            finished: false,
+        };
+        session.load_text(text);
+        session
+    }
+
+    /// Replace the text to type, with its words separated by single spaces
+    fn load_text(&mut self, text: &str) {
+        self.tokens = text
+            .split_ascii_whitespace()
+            .map(|s| s.to_string())
+            .collect();
+        self.current_word_limit = self.tokens.iter()
+            .map(|s| s.chars().count())
+            .max()
+            .unwrap_or(0) + 5;
+        self.text = self.tokens.join(" ");
+        self.original_text = self.text.clone();
+    }
+
+    /// Pad the text with spaces so no word is split at the end of a line
+    ///
+    /// The padding has to be typed like the rest of the text, accepting a word types it automatically.
+    /// # Arguments:
+    /// * `width` - Width of the lines the text is shown in
+    pub fn wrap_text(&mut self, width: i32) {
+        self.text = word_wrap(&self.original_text, width);
+    }
+
+    /// Text to type, with the padding added by `wrap_text`
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Text to type as it was given, without any padding
+    pub fn original_text(&self) -> &str {
+        &self.original_text
+    }
+
+    /// Words of the text
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// Text typed so far, including mistakes not erased yet
+    pub fn typed_text(&self) -> &str {
+        &self.current_string
+    }
+
+    /// Text typed since the last accepted word
+    pub fn current_word(&self) -> &str {
+        &self.current_word
+    }
+
+    /// Whether the current word is as long as a word can get, longer ones aren't typed
+    pub fn is_word_limit_reached(&self) -> bool {
+        self.current_word.chars().count() >= self.current_word_limit
+    }
+
+    /// Number of characters typed correctly before the first mistake
+    pub fn correct_length(&self) -> usize {
+        first_index_at_which_strings_differ(&self.current_string, &self.text)
+    }
+
+    /// Check if every character of the text has been typed correctly
+    pub fn is_complete(&self) -> bool {
+        self.correct_length() == self.text.chars().count()
+    }
+
+    /// Apply an editing or character key to the typed text
+    /// # Returns:
+    /// * `bool` - False when the key was refused in strict mode
+    pub fn process_key(&mut self, key: &Input) -> bool {
+        if is_backspace(key) {
+            self.backspace();
+        } else if is_erase_word(key) {
+            self.erase_word();
+        } else if is_ctrl_u(key) {
+            self.clear_word();
+        } else if is_ctrl_n(key) {
+            self.skip_word();
+        } else if let Input::Character(c) = key {
+            return self.press(*c);
+        }
+        true
+    }
+
+    /// Type a character, a space accepts the current word
+    /// # Returns:
+    /// * `bool` - False when the character was refused in strict mode
+    pub fn press(&mut self, c: char) -> bool {
+        if self.rules.strict && !self.is_expected(c) {
+            self.reject_key();
+            return false;
+        }
+        // Ignore spaces at the start of the word (Plover support)
+        if c == ' ' && !self.is_word_limit_reached() {
+            self.commit_word();
+            return true;
+        }
+        self.append_key(c);
+        self.total_chars_typed += 1;
+        self.record_mistake();
+        // The last word is finished without a space
+        if self.is_complete() {
+            self.record_word_timing(self.current_word.clone());
+        }
+        true
+    }
+
+    /// Type a space after the current word, accepting it if it matches the text
+    ///
+    /// A wrong word keeps the space and has to be fixed before the next one,
+    /// unless wrong words are skipped.
+    pub fn commit_word(&mut self) {
+        self.total_chars_typed += 1;
+        if !self.current_word.is_empty() {
+            self.check_word();
+            self.record_mistake();
+        }
+    }
+
+    /// Whether a character is the next one of the text
+    ///
+    /// Spaces at the start of a word are ignored anyway, so they are accepted.
+    fn is_expected(&self, c: char) -> bool {
+        if c == ' ' && self.current_word.is_empty() {
+            return true;
+        }
+        self.text.chars().nth(self.current_string.chars().count()) == Some(c)
+    }
+
+    /// Count a mistyped key without typing it, used in strict mode
+    fn reject_key(&mut self) {
+        self.total_chars_typed += 1;
+        *self.mistyped_keys.entry(self.current_string.chars().count()).or_insert(0) += 1;
+    }
+
+    fn append_key(&mut self, c: char) {
+        if !self.is_word_limit_reached() {
+            self.current_word.push(c);
+            self.current_string.push(c);
+        }
+    }
+
+    /// Remember the position of the last typed character if it doesn't match the text
+    ///
+    /// Only called after characters are added, so erasing or redrawing
+    /// never records the same mistake twice.
+    fn record_mistake(&mut self) {
+        let typed_length = self.current_string.chars().count();
+        let text_length = self.text.chars().count();
+        let index = self.correct_length();
+        // Check if difference was found
+        if index < typed_length && typed_length <= text_length {
+            *self.mistyped_keys.entry(typed_length - 1).or_insert(0) += 1;
+        }
+    }
+
+    /// Accept finalized word
+    fn check_word(&mut self) {
+        let spc = get_space_count_after_ith_word(self.current_string.chars().count(), &self.text);
+        if self.tokens.get(self.token_index) == Some(&self.current_word) {
+            self.token_index += 1;
+            let word = std::mem::take(&mut self.current_word);
+            self.committed_words.push((word.clone(), WordEnd::Accepted));
+            self.record_word_timing(word);
+            self.current_string += " ".repeat(spc).as_str();
+        } else if self.rules.skip_wrong_words {
+            self.skip_wrong_word();
+        } else {
+            self.current_word = format!("{} ", self.current_word);
+            self.current_string = format!("{} ", self.current_string);
+        }
+    }
+
+    /// Erase the last typed character
+    ///
+    /// At the start of a word nothing is erased, unless backtracking is allowed.
+    pub fn backspace(&mut self) {
+        if self.current_word.is_empty() && self.rules.backtrack {
+            self.step_back_into_previous_word();
+        } else if self.current_word.pop().is_some() {
+            self.current_string.pop();
+            self.chars_erased += 1;
+        }
+    }
+
+    /// Erase the last typed word
+    ///
+    /// A mistyped word ending in a space only loses the space, otherwise the
+    /// whole word is erased. Every erased character counts as erased.
+    pub fn erase_word(&mut self) {
+        let kept_length = self.current_word.rfind(' ').unwrap_or(0);
+        let erased = self.current_word.split_off(kept_length);
+        self.current_string.truncate(self.current_string.len() - erased.len());
+        self.chars_erased += erased.chars().count();
+    }
+
+    /// Erase everything typed since the last accepted word
+    ///
+    /// Unlike `erase_word`, a mistyped word ending in a space is erased along with
+    /// whatever was typed after it. Every erased character counts as erased, the same
+    /// as erasing them one by one.
+    pub fn clear_word(&mut self) {
+        let erased = std::mem::take(&mut self.current_word);
+        self.current_string.truncate(self.current_string.len() - erased.len());
+        self.chars_erased += erased.chars().count();
+    }
+
+    /// Go back into the last accepted word, erasing the spaces after it
+    ///
+    /// The word is restored as it was typed, so a skipped word can be fixed.
+    fn step_back_into_previous_word(&mut self) {
+        let Some((word, end)) = self.committed_words.pop() else {
+            return;
+        };
+        self.token_index -= 1;
+        let expected = self.tokens.get(self.token_index).cloned().unwrap_or_default();
+        match end {
+            WordEnd::Accepted => {
+                // The word is timed again once it is accepted again
+                if self.word_timings.last().is_some_and(|(timed_word, _)| *timed_word == word) {
+                    self.word_timings.pop();
+                }
+            }
+            // Counted as skipped even when the word was typed right
+            WordEnd::Skipped | WordEnd::SkippedByKey => {
+                self.skipped_words -= 1;
+                self.chars_in_skipped_words -= word.chars().count();
+            }
+        }
+        if end == WordEnd::SkippedByKey {
+            self.words_skipped_by_key -= 1;
+        }
+
+        // The typed text is lined up with the text after an accepted word
+        let next_word_start = self.current_string.chars().count();
+        let spaces = slice_by_char_indices(&self.text, 0, next_word_start)
+            .chars()
+            .rev()
+            .take_while(|c| *c == ' ')
+            .count();
+        let word_start = next_word_start - spaces - expected.chars().count();
+        self.skipped_keys.retain(|index| *index < word_start);
+        self.current_string = slice_by_char_indices(&self.text, 0, word_start).to_string() + &word;
+        self.current_word = word;
+        // Only the space typed after the word was erased, the padding was added automatically,
+        // and Ctrl+N moves past a word without typing a space
+        if end != WordEnd::SkippedByKey {
+            self.chars_erased += 1;
+        }
+    }
+
+    /// Move on from a wrong word, counting every wrong character in it as a mistake
+    ///
+    /// The typed text is lined up with the start of the next word of the text.
+    fn skip_wrong_word(&mut self) {
+        let expected = self.tokens.get(self.token_index).cloned().unwrap_or_default();
+        let word_start = self.current_word_start();
+        let mut typed_chars = self.current_word.chars();
+        for (offset, expected_char) in expected.chars().enumerate() {
+            if typed_chars.next() != Some(expected_char) {
+                self.mistyped_keys.entry(word_start + offset).or_insert(1);
+            }
+        }
+        self.move_past_current_word(WordEnd::Skipped);
+    }
+
+    /// Give up on the current word, for example one with characters that can't be typed
+    ///
+    /// The untyped rest of the word is marked as skipped and only counts as
+    /// mistakes if asked to.
+    pub fn skip_word(&mut self) {
+        let Some(expected) = self.tokens.get(self.token_index).cloned() else {
+            return;
+        };
+        let word_start = self.current_word_start();
+        let typed_correctly = first_index_at_which_strings_differ(&self.current_word, &expected);
+        let skipped = word_start + typed_correctly..word_start + expected.chars().count();
+        if self.rules.count_skipped_as_errors {
+            for index in skipped.clone() {
+                self.mistyped_keys.entry(index).or_insert(1);
+            }
+        }
+        self.skipped_keys.extend(skipped);
+        self.words_skipped_by_key += 1;
+        self.move_past_current_word(WordEnd::SkippedByKey);
+    }
+
+    /// Index of the text at which the current word starts
+    fn current_word_start(&self) -> usize {
+        self.current_string.chars().count() - self.current_word.chars().count()
+    }
+
+    /// Accept the current word as skipped and line up the typed text with the next word
+    fn move_past_current_word(&mut self, end: WordEnd) {
+        let expected_length = self.tokens.get(self.token_index).map_or(0, |word| word.chars().count());
+        let typed_length = self.current_word.chars().count();
+        let word_start = self.current_word_start();
+
+        // Characters typed past the end of the word don't belong to any position of the text
+        self.mistyped_keys.retain(|index, _| {
+            *index < word_start + expected_length || *index >= word_start + typed_length
+        });
+
+        self.skipped_words += 1;
+        self.chars_in_skipped_words += typed_length;
+        self.token_index += 1;
+        self.committed_words.push((std::mem::take(&mut self.current_word), end));
+        let word_end = word_start + expected_length;
+        let next_word_start = word_end + get_space_count_after_ith_word(word_end, &self.text);
+        self.current_string = slice_by_char_indices(&self.text, 0, next_word_start).to_string();
+    }
+
+    /// Store a key pressed during the session with the time since it started
+    pub fn record_key(&mut self, key: &Input, elapsed: Duration) {
+        self.key_strokes.push((elapsed, *key));
+        // Only endless tests get this long, the oldest keys are dropped
+        if self.key_strokes.len() > MAX_KEY_STROKES {
+            let excess = self.key_strokes.len() - MAX_KEY_STROKES;
+            self.key_strokes.drain(..excess);
+            self.page_start_key = self.page_start_key.saturating_sub(excess);
+        }
+    }
+
+    /// Every recorded key with the time since the start of the session
+    pub fn key_strokes(&self) -> &[(Duration, Input)] {
+        &self.key_strokes
+    }
+
+    /// Keys recorded since the current page was started
+    pub fn page_key_strokes(&self) -> &[(Duration, Input)] {
+        &self.key_strokes[self.page_start_key..]
+    }
+
+    /// Time since the start of the session of every recorded key
+    pub fn keystroke_times(&self) -> Vec<Duration> {
+        self.key_strokes.iter()
+            .map(|(time, _)| *time)
+            .collect()
+    }
+
+    /// Remember how long the just completed word took to type
+    ///
+    /// The time is measured from the completion of the previous word to the
+    /// last recorded keystroke. Nothing is recorded once the session is finished.
+    fn record_word_timing(&mut self, word: String) {
+        if self.finished {
+            return;
+        }
+        let Some((completed_at, _)) = self.key_strokes.last() else {
+            return;
+        };
+        let previous_completed_at: f64 = self.word_timings.iter()
+            .map(|(_, seconds)| seconds)
+            .sum();
+        let seconds = (completed_at.as_secs_f64() - previous_completed_at).max(0.0);
+        self.word_timings.push((word, seconds));
+    }
+
+    /// Every completed word with the seconds it took to type
+    pub fn word_timings(&self) -> &[(String, f64)] {
+        &self.word_timings
+    }
+
+    /// Replace the finished text with the next page of an endless test
+    ///
+    /// Words and mistakes of the finished page keep counting toward the stats.
+    pub fn start_next_page(&mut self, text: &str) {
+        self.earlier_words = self.completed_words();
+        self.earlier_errors = self.error_count();
+        self.earlier_mistyped_words = self.mistyped_words();
+        self.mistyped_keys.clear();
+        self.page_start_key = self.key_strokes.len();
+
+        self.load_text(text);
+        self.current_string.clear();
+        self.current_word.clear();
+        self.committed_words.clear();
+        self.skipped_keys.clear();
+        // Skipped words were taken off the earlier words already
+        self.skipped_words = 0;
+        self.token_index = 0;
+    }
+
+    /// Number of words of the text typed correctly so far
+    ///
+    /// The last word is completed without a trailing space, so it counts once the text is complete.
+    pub fn completed_words(&self) -> usize {
+        // A skipped last word was already counted when it was skipped
+        let last_word = self.is_complete() && self.token_index < self.tokens.len();
+        (self.earlier_words + self.token_index + usize::from(last_word))
+            .saturating_sub(self.skipped_words)
+    }
+
+    /// Number of characters typed, including the ones erased again
+    pub fn raw_chars_typed(&self) -> usize {
+        self.total_chars_typed
+    }
+
+    /// Number of typed characters that weren't erased again or typed in skipped words
+    pub fn net_chars_typed(&self) -> usize {
+        self.total_chars_typed
+            .saturating_sub(self.chars_erased)
+            .saturating_sub(self.chars_in_skipped_words)
+    }
+
+    /// Number of distinct text positions that were mistyped
+    pub fn error_count(&self) -> usize {
+        self.earlier_errors + self.mistyped_keys.len()
+    }
+
+    /// Mistyped positions of the text on the current page with the number of times they were mistyped
+    pub fn mistyped_keys(&self) -> &HashMap<usize, u32> {
+        &self.mistyped_keys
+    }
+
+    /// Number of times the character at `index` of the text was mistyped
+    pub fn mistakes_at(&self, index: usize) -> u32 {
+        self.mistyped_keys.get(&index).copied().unwrap_or(0)
+    }
+
+    /// Words of the text that were mistyped, with the number of mistakes in each
+    ///
+    /// A mistake on the space after a word counts toward that word.
+    pub fn mistyped_words(&self) -> Vec<(String, u32)> {
+        let mut word_errors: HashMap<String, u32> = self.earlier_mistyped_words.iter().cloned().collect();
+        for (index, errors) in &self.mistyped_keys {
+            if let Some(word) = self.tokens.get(word_index_at(&self.text, *index)) {
+                *word_errors.entry(word.clone()).or_insert(0) += errors;
+            }
+        }
+        let mut word_errors: Vec<(String, u32)> = word_errors.into_iter().collect();
+        word_errors.sort();
+        word_errors
+    }
+
+    /// Accuracy of the typing so far, based on the recorded mistakes
+    pub fn current_accuracy(&self) -> f64 {
+        accuracy(self.total_chars_typed, self.error_count())
+    }
+
+    /// Number of words given up on with Ctrl+N
+    pub fn words_skipped_by_key(&self) -> usize {
+        self.words_skipped_by_key
+    }
+
+    /// Positions of the text on the current page left untyped by skipping their word
+    pub fn skipped_keys(&self) -> &HashSet<usize> {
+        &self.skipped_keys
+    }
+
+    /// Whether the character at `index` of the text was left untyped by skipping its word
+    pub fn is_skipped_at(&self, index: usize) -> bool {
+        self.skipped_keys.contains(&index)
+    }
+
+    /// Number of characters typed correctly after every recorded key
+    ///
+    /// The keys are replayed on the text before word wrapping, so the progress
+    /// doesn't depend on the width the text is shown in.
+    pub fn progress_timeline(&self) -> Vec<(Duration, usize)> {
+        let mut replay = TypingSession::with_rules(&self.original_text, self.rules);
+        self.key_strokes.iter()
+            .map(|(time, key)| {
+                replay.process_key(key);
+                (*time, replay.correct_length())
+            })
+            .collect()
+    }
+
+    /// Replay recorded keys on a fresh session and check every typed character against the text
+    /// # Arguments:
+    /// * `text` - Text the keys were typed on
+    /// * `rules` - Rules of the session the keys were recorded in
+    /// * `key_strokes` - Keys with the time since the start of the session, like `key_strokes`
+    pub fn simulate(text: &str, rules: SessionRules, key_strokes: &[(Duration, Input)]) -> Vec<SimulatedKey> {
+        let mut replay = TypingSession::with_rules(text, rules);
+        key_strokes.iter()
+            .map(|(time, key)| {
+                let (expected, correct) = match key {
+                    Input::Character(c) if !c.is_control() => {
+                        let expected = replay.text.chars().nth(replay.current_string.chars().count());
+                        (expected, Some(replay.is_expected(*c)))
+                    }
+                    _ => (None, None),
+                };
+                replay.process_key(key);
+                SimulatedKey { time: *time, key: *key, expected, correct }
+            })
+            .collect()
+    }
+
+    /// Keys of the current page replayed against its text
+    pub fn simulated_keys(&self) -> Vec<SimulatedKey> {
+        TypingSession::simulate(&self.original_text, self.rules, self.page_key_strokes())
+    }
+
+    /// Score the session
+    ///
+    /// The typed text and the stats are kept, words typed after this aren't timed anymore.
+    /// # Arguments:
+    /// * `text_id` - Id of the text the results are saved under
+    /// * `duration` - How long the session took
+    /// # Returns:
+    /// * `SessionStats` - Speed, accuracy and the other results
+    pub fn finish(&mut self, text_id: &str, duration: Duration) -> SessionStats {
+        self.finished = true;
+        let minutes = duration.as_secs_f64() / 60.0;
+        let completed = self.is_complete();
+        let keystroke_times = self.keystroke_times();
+        let intervals: Vec<f64> = keystroke_times
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).as_secs_f64())
+            .filter(|interval| *interval <= CONSISTENCY_MAX_PAUSE_SECONDS)
+            .collect();
+        // A timed test only scores the words completed before the time ran out
+        let words_wpm = if completed {
+            speed_in_wpm_with_minutes(&self.tokens, minutes)
+        } else {
+            words_per_minute(self.completed_words(), minutes)
+        };
+        let raw_wpm = gross_wpm(self.raw_chars_typed(), minutes);
+        let net_wpm = net_wpm(self.raw_chars_typed(), self.error_count(), minutes);
+        SessionStats {
+            text_id: text_id.to_string(),
+            timestamp: Local::now(),
+            wpm: match self.rules.wpm_formula {
+                WpmFormula::Words => words_wpm,
+                WpmFormula::Gross => raw_wpm,
+                WpmFormula::Net => net_wpm,
+            },
+            words_wpm,
+            raw_wpm,
+            net_wpm,
+            cpm: chars_per_minute(self.total_chars_typed.saturating_sub(self.error_count()), minutes),
+            accuracy: self.current_accuracy(),
+            consistency: consistency(&intervals),
+            duration: duration.as_secs_f64(),
+            chars_typed: self.raw_chars_typed(),
+            errors: self.error_count(),
+            skipped_words: self.words_skipped_by_key,
+            completed,
+            time_limit: None,
+            difficulty: None,
+            source: TextSource::default(),
+            attempt: None,
+            text_hash: None,
+            suspect: false,
+            personal_best: false,
+            latency: latency_stats(&keystroke_times),
+            wpm_over_time: wpm_buckets(&keystroke_times, WPM_GRAPH_BUCKET),
+            timeline: self.progress_timeline(),
+        }
+    }
+
+    /// Session with the same page and rules and nothing typed yet, to type the keys again in a replay
+    ///
+    /// Keys typed on the copy don't change the recorded keys or the stats of this session.
+    pub fn replay_copy(&self) -> TypingSession {
+        let mut copy = Self::with_rules(&self.original_text, self.rules);
+        copy.text = self.text.clone();
+        copy.finished = true;
+        copy
+    }
+
+    /// Clear the typed text so it can be typed again, like in a replay
+    ///
+    /// The mistakes and the other stats are kept.
+    pub fn rewind(&mut self) {
+        self.current_string.clear();
+        self.current_word.clear();
+        self.token_index = 0;
+    }
+}
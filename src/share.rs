@@ -0,0 +1,154 @@
+use std::fmt::Formatter;
+use crate::session::SessionStats;
+
+/// Text shared with Ctrl+T, `share_template` in the config file replaces it
+pub const DEFAULT_SHARE_TEMPLATE: &str = "My typing speed is {result}!\n\
+    Know yours on rstype.\n\
+    \"https://github.com/CyberDogFK/rstype\" by @CyberDogFK\n\
+    #TypingTest #Rust";
+
+/// Text copied with Ctrl+Y, `copy_template` in the config file replaces it
+pub const DEFAULT_COPY_TEMPLATE: &str = "{result} — rstype";
+
+/// Placeholders a share template can use
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &["wpm", "accuracy", "time", "text_id", "result"];
+
+/// Placeholders the URL of a share target can use, the message is percent-encoded
+pub const URL_PLACEHOLDERS: &[&str] = &["text"];
+
+/// Where Ctrl+T shares the result, `share_target.<name> = <url>` in the config file
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShareTarget {
+    pub name: String,
+    // URL opened in the browser, with the message in place of {text}
+    pub url_template: String,
+}
+
+impl ShareTarget {
+    pub fn new(name: &str, url_template: &str) -> ShareTarget {
+        ShareTarget { name: name.to_string(), url_template: url_template.to_string() }
+    }
+
+    /// URL sharing the message
+    pub fn url(&self, message: &str) -> Result<String, TemplateError> {
+        fill(&self.url_template, URL_PLACEHOLDERS, |_| percent_encode(message))
+    }
+}
+
+/// Target used when the config file has none, the tweet rstype always had
+pub fn default_share_targets() -> Vec<ShareTarget> {
+    vec![ShareTarget::new("Twitter", "https://twitter.com/intent/tweet?text={text}")]
+}
+
+/// Opens the URL sharing a result
+///
+/// The app uses the browser of the system, tests can keep the URL instead.
+pub trait Browser {
+    fn open(&mut self, url: &str) -> std::io::Result<()>;
+}
+
+/// Default browser of the system, missing over SSH or on headless machines
+pub struct SystemBrowser;
+
+impl Browser for SystemBrowser {
+    fn open(&mut self, url: &str) -> std::io::Result<()> {
+        open::that(url)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum TemplateError {
+    UnknownPlaceholder { name: String, known: &'static [&'static str] },
+    UnclosedPlaceholder(String),
+}
+
+impl std::fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateError::UnknownPlaceholder { name, known } => {
+                let placeholders = known.join("}, {");
+                write!(f, "Unknown placeholder {{{}}} in share template, use one of {{{}}}", name, placeholders)
+            }
+            TemplateError::UnclosedPlaceholder(template) => {
+                write!(f, "Placeholder without a closing }} in share template: {}, write {{{{ for a brace", template)
+            }
+        }
+    }
+}
+
+/// Fill the placeholders of a share template with the results of a test
+///
+/// `{wpm}`, `{accuracy}` and `{time}` are rounded like on the end screen,
+/// `{result}` is the whole `SessionStats::share_line`. `{{` and `}}` stand for braces.
+/// # Arguments:
+/// * `template` - Text with placeholders in braces
+/// * `stats` - Results of the test
+pub fn fill_template(template: &str, stats: &SessionStats) -> Result<String, TemplateError> {
+    fill(template, TEMPLATE_PLACEHOLDERS, |name| match name {
+        "wpm" => format!("{:.1}", stats.wpm),
+        "accuracy" => format!("{:.1}", stats.accuracy),
+        "time" => format!("{:.0}", stats.duration),
+        "text_id" => stats.text_id.clone(),
+        _ => stats.share_line(),
+    })
+}
+
+/// Check a template before the test, so a typo shows up before the end screen
+pub fn check_template(template: &str) -> Result<(), TemplateError> {
+    fill_template(template, &SessionStats::default()).map(|_| ())
+}
+
+/// Check the URL of a share target like `check_template`
+pub fn check_share_target(target: &ShareTarget) -> Result<(), TemplateError> {
+    target.url("").map(|_| ())
+}
+
+/// Replace the placeholders in braces with their values, `{{` and `}}` stand for braces
+/// # Arguments:
+/// * `template` - Text with placeholders in braces
+/// * `known` - Placeholders the template can use, any other one is an error
+/// * `value` - Value of a known placeholder
+fn fill(template: &str, known: &'static [&'static str], value: impl Fn(&str) -> String) -> Result<String, TemplateError> {
+    let mut filled = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                filled.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                filled.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(TemplateError::UnclosedPlaceholder(template.to_string())),
+                    }
+                }
+                if !known.contains(&name.as_str()) {
+                    return Err(TemplateError::UnknownPlaceholder { name, known });
+                }
+                filled.push_str(&value(&name));
+            }
+            c => filled.push(c),
+        }
+    }
+    Ok(filled)
+}
+
+/// Percent-encode text for a query parameter
+///
+/// Everything but the unreserved characters of RFC 3986 is encoded, UTF-8 byte by byte.
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => char::from(byte).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
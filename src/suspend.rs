@@ -0,0 +1,27 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by the SIGCONT handler, cleared once the app has recovered
+static RESUMED: AtomicBool = AtomicBool::new(false);
+
+/// Notice when the process is resumed after being suspended with Ctrl+Z.
+///
+/// curses stops the process on Ctrl+Z and restores the terminal on `fg`, but the
+/// screen can come back blank and the test clock keeps running while suspended.
+/// The handler only sets a flag, the main loop redraws and fixes the timing.
+/// Does nothing outside Unix.
+pub fn install_resume_handler() {
+    #[cfg(unix)]
+    unsafe {
+        libc::signal(libc::SIGCONT, on_resume as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+}
+
+/// Whether the process was resumed since the last call
+pub fn take_resumed() -> bool {
+    RESUMED.swap(false, Ordering::SeqCst)
+}
+
+#[cfg(unix)]
+extern "C" fn on_resume(_signal: libc::c_int) {
+    RESUMED.store(true, Ordering::SeqCst);
+}
@@ -0,0 +1,342 @@
+use pancurses::{chtype, Input};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use unicode_width::UnicodeWidthChar;
+
+/// Whether the curses session is running, so it is only ended once
+static CURSES_ACTIVE: AtomicBool = AtomicBool::new(false);
+/// Whether the panic hook is in place, so the previous hook is only wrapped once
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+/// Start the curses session, making sure a panic ends it before the panic is printed
+/// # Returns:
+/// * `pancurses::Window` - The whole screen
+pub fn start_terminal() -> pancurses::Window {
+    install_panic_hook();
+    let window = pancurses::initscr();
+    CURSES_ACTIVE.store(true, Ordering::SeqCst);
+    window
+}
+
+/// End the curses session, restoring the cursor and the terminal modes.
+///
+/// Does nothing when the session isn't running, so it is safe to call more than once.
+pub fn restore_terminal() {
+    if CURSES_ACTIVE.swap(false, Ordering::SeqCst) {
+        pancurses::curs_set(1);
+        pancurses::endwin();
+    }
+}
+
+/// Restore the terminal before a panic is printed.
+///
+/// Without it a panic mid-test leaves the terminal in raw mode with no echo,
+/// and the message is drawn over by curses. The previous hook prints the panic
+/// afterwards, with a backtrace when RUST_BACKTRACE asks for one. Installing it again does nothing.
+pub fn install_panic_hook() {
+    if PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let previous_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        previous_hook(info);
+    }));
+}
+
+/// Whether the panic hook restoring the terminal is in place
+pub fn is_panic_hook_installed() -> bool {
+    PANIC_HOOK_INSTALLED.load(Ordering::SeqCst)
+}
+
+/// Drawing and input calls the app makes on the terminal
+///
+/// The app draws through this trait so the screen can be checked in tests
+/// with `FakeTerminal` instead of a real terminal.
+pub trait TerminalUi {
+    fn addstr<T: AsRef<str>>(&mut self, string: T);
+    fn mvaddstr<T: AsRef<str>>(&mut self, y: i32, x: i32, string: T);
+    fn attrset(&mut self, attributes: chtype);
+    fn mv(&mut self, y: i32, x: i32);
+    fn clear(&mut self);
+    fn clrtoeol(&mut self);
+    // Redraw the whole screen on the next refresh
+    fn clearok(&mut self, clear: bool);
+    fn refresh(&mut self);
+    fn get_cur_yx(&self) -> (i32, i32);
+    fn get_max_yx(&self) -> (i32, i32);
+    fn getch(&mut self) -> Option<Input>;
+    fn keypad(&mut self, use_keypad: bool);
+    fn nodelay(&mut self, enabled: bool);
+    // Milliseconds getch waits for a key, -1 to wait until there is one
+    fn timeout(&mut self, milliseconds: i32);
+    fn curs_set(&mut self, visibility: i32);
+    fn beep(&mut self);
+    fn has_colors(&self) -> bool;
+    // Number of colors the terminal can show
+    fn colors(&self) -> i32;
+    // Whether the default colors of the terminal can be used as color -1
+    fn use_default_colors(&mut self) -> bool;
+    fn init_pair(&mut self, pair: i16, foreground: i16, background: i16);
+}
+
+impl TerminalUi for pancurses::Window {
+    fn addstr<T: AsRef<str>>(&mut self, string: T) {
+        pancurses::Window::addstr(self, string);
+    }
+
+    fn mvaddstr<T: AsRef<str>>(&mut self, y: i32, x: i32, string: T) {
+        pancurses::Window::mvaddstr(self, y, x, string);
+    }
+
+    fn attrset(&mut self, attributes: chtype) {
+        pancurses::Window::attrset(self, attributes);
+    }
+
+    fn mv(&mut self, y: i32, x: i32) {
+        pancurses::Window::mv(self, y, x);
+    }
+
+    fn clear(&mut self) {
+        pancurses::Window::clear(self);
+    }
+
+    fn clrtoeol(&mut self) {
+        pancurses::Window::clrtoeol(self);
+    }
+
+    fn clearok(&mut self, clear: bool) {
+        pancurses::Window::clearok(self, clear);
+    }
+
+    fn refresh(&mut self) {
+        pancurses::Window::refresh(self);
+    }
+
+    fn get_cur_yx(&self) -> (i32, i32) {
+        pancurses::Window::get_cur_yx(self)
+    }
+
+    fn get_max_yx(&self) -> (i32, i32) {
+        pancurses::Window::get_max_yx(self)
+    }
+
+    fn getch(&mut self) -> Option<Input> {
+        pancurses::Window::getch(self)
+    }
+
+    fn keypad(&mut self, use_keypad: bool) {
+        pancurses::Window::keypad(self, use_keypad);
+    }
+
+    fn nodelay(&mut self, enabled: bool) {
+        pancurses::Window::nodelay(self, enabled);
+    }
+
+    fn timeout(&mut self, milliseconds: i32) {
+        pancurses::Window::timeout(self, milliseconds);
+    }
+
+    fn curs_set(&mut self, visibility: i32) {
+        pancurses::curs_set(visibility);
+    }
+
+    fn beep(&mut self) {
+        pancurses::beep();
+    }
+
+    fn has_colors(&self) -> bool {
+        pancurses::has_colors()
+    }
+
+    fn colors(&self) -> i32 {
+        pancurses::COLORS()
+    }
+
+    fn use_default_colors(&mut self) -> bool {
+        pancurses::use_default_colors() == pancurses::OK
+    }
+
+    fn init_pair(&mut self, pair: i16, foreground: i16, background: i16) {
+        pancurses::init_pair(pair, foreground, background);
+    }
+}
+
+/// Terminal kept in memory, recording the character and attributes of every cell
+///
+/// Keys pushed with `push_keys` are returned by `getch` one by one.
+pub struct FakeTerminal {
+    height: i32,
+    width: i32,
+    cells: Vec<Vec<(char, chtype)>>,
+    cursor: (i32, i32),
+    attributes: chtype,
+    keys: VecDeque<Input>,
+    // Number of times the terminal beeped
+    pub beeps: usize,
+    // Last visibility set with curs_set
+    pub cursor_visibility: i32,
+}
+
+/// Second cell of a double width character
+pub const WIDE_CHAR_CONTINUATION: char = '\0';
+
+impl FakeTerminal {
+    pub fn new(height: i32, width: i32) -> FakeTerminal {
+        FakeTerminal {
+            height,
+            width,
+            cells: vec![vec![(' ', pancurses::A_NORMAL); width as usize]; height as usize],
+            cursor: (0, 0),
+            attributes: pancurses::A_NORMAL,
+            keys: VecDeque::new(),
+            beeps: 0,
+            cursor_visibility: 1,
+        }
+    }
+
+    /// Queue keys for `getch`
+    pub fn push_keys<I: IntoIterator<Item = Input>>(&mut self, keys: I) {
+        self.keys.extend(keys);
+    }
+
+    /// Text of a row, without the blanks at its end
+    pub fn line(&self, row: i32) -> String {
+        self.cells[row as usize].iter()
+            .map(|(c, _)| *c)
+            .filter(|c| *c != WIDE_CHAR_CONTINUATION)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    /// Text of every row, without the blanks at their ends
+    pub fn screen(&self) -> Vec<String> {
+        (0..self.height).map(|row| self.line(row)).collect()
+    }
+
+    /// Character of a cell, '\0' in the second cell of a double width character
+    pub fn char_at(&self, row: i32, column: i32) -> char {
+        self.cells[row as usize][column as usize].0
+    }
+
+    /// Attributes the cell was last drawn with
+    pub fn attributes_at(&self, row: i32, column: i32) -> chtype {
+        self.cells[row as usize][column as usize].1
+    }
+
+    fn put_char(&mut self, c: char) {
+        let (row, column) = self.cursor;
+        if row >= self.height {
+            return;
+        }
+        let char_width = c.width().unwrap_or(0) as i32;
+        if char_width == 0 {
+            return;
+        }
+        let (row, column) = if column + char_width > self.width { (row + 1, 0) } else { (row, column) };
+        if row >= self.height {
+            self.cursor = (row, column);
+            return;
+        }
+        self.cells[row as usize][column as usize] = (c, self.attributes);
+        if char_width == 2 {
+            self.cells[row as usize][column as usize + 1] = (WIDE_CHAR_CONTINUATION, self.attributes);
+        }
+        let column = column + char_width;
+        // Like curses, the cursor stays in the last cell of the screen
+        self.cursor = if column < self.width {
+            (row, column)
+        } else if row + 1 < self.height {
+            (row + 1, 0)
+        } else {
+            (row, self.width - 1)
+        };
+    }
+}
+
+impl TerminalUi for FakeTerminal {
+    fn addstr<T: AsRef<str>>(&mut self, string: T) {
+        for c in string.as_ref().chars() {
+            if c == '\n' {
+                self.clrtoeol();
+                self.cursor = (self.cursor.0 + 1, 0);
+            } else {
+                self.put_char(c);
+            }
+        }
+    }
+
+    fn mvaddstr<T: AsRef<str>>(&mut self, y: i32, x: i32, string: T) {
+        self.mv(y, x);
+        self.addstr(string);
+    }
+
+    fn attrset(&mut self, attributes: chtype) {
+        self.attributes = attributes;
+    }
+
+    fn mv(&mut self, y: i32, x: i32) {
+        self.cursor = (y, x);
+    }
+
+    fn clear(&mut self) {
+        for row in self.cells.iter_mut() {
+            row.fill((' ', pancurses::A_NORMAL));
+        }
+        self.cursor = (0, 0);
+    }
+
+    fn clrtoeol(&mut self) {
+        let (row, column) = self.cursor;
+        if let Some(cells) = self.cells.get_mut(row as usize) {
+            for cell in cells.iter_mut().skip(column.max(0) as usize) {
+                *cell = (' ', pancurses::A_NORMAL);
+            }
+        }
+    }
+
+    fn clearok(&mut self, _clear: bool) {}
+
+    fn refresh(&mut self) {}
+
+    fn get_cur_yx(&self) -> (i32, i32) {
+        self.cursor
+    }
+
+    fn get_max_yx(&self) -> (i32, i32) {
+        (self.height, self.width)
+    }
+
+    fn getch(&mut self) -> Option<Input> {
+        self.keys.pop_front()
+    }
+
+    fn keypad(&mut self, _use_keypad: bool) {}
+
+    fn nodelay(&mut self, _enabled: bool) {}
+
+    fn timeout(&mut self, _milliseconds: i32) {}
+
+    fn curs_set(&mut self, visibility: i32) {
+        self.cursor_visibility = visibility;
+    }
+
+    fn beep(&mut self) {
+        self.beeps += 1;
+    }
+
+    fn has_colors(&self) -> bool {
+        true
+    }
+
+    fn colors(&self) -> i32 {
+        256
+    }
+
+    fn use_default_colors(&mut self) -> bool {
+        true
+    }
+
+    fn init_pair(&mut self, _pair: i16, _foreground: i16, _background: i16) {}
+}
@@ -0,0 +1,104 @@
+use crate::{prepare_word_count_text, AppError, AppResult, PreparedText};
+
+/// Changes made to every text of a test before it is typed
+///
+/// They are made to the first text, to the next texts of a marathon and to the database
+/// pages of an endless test, so every text of a test is typed the same way.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TextTransforms {
+    // Punctuation is removed, see `strip_punctuation`
+    pub no_punctuation: bool,
+    // Letters are lowercased after the punctuation is removed, see `lowercase`
+    pub lowercase: bool,
+    // Texts are cut or repeated to this many words last, see `prepare_word_count_text`
+    pub words: Option<usize>,
+}
+
+impl TextTransforms {
+    /// Change a text, each transform adds its suffix to the id
+    /// # Returns
+    /// * `PreparedText` changed, an error if nothing was left of it
+    pub fn apply(&self, prepared_text: PreparedText) -> AppResult<PreparedText> {
+        let prepared_text = if self.no_punctuation {
+            strip_punctuation(prepared_text)?
+        } else {
+            prepared_text
+        };
+        let prepared_text = if self.lowercase { lowercase(prepared_text) } else { prepared_text };
+        Ok(match self.words {
+            Some(words) => prepare_word_count_text(prepared_text, words),
+            None => prepared_text,
+        })
+    }
+
+    /// Id a text had before `apply` added the suffixes of the transforms to it
+    /// # Returns
+    /// * `Option<&str>` - None when the id doesn't end with the suffixes
+    pub fn original_id<'a>(&self, text_id: &'a str) -> Option<&'a str> {
+        let mut text_id = text_id;
+        if let Some(words) = self.words {
+            text_id = text_id.strip_suffix(&format!("#{}w", words))?;
+        }
+        if self.lowercase {
+            text_id = text_id.strip_suffix(":lc")?;
+        }
+        if self.no_punctuation {
+            text_id = text_id.strip_suffix(":np")?;
+        }
+        Some(text_id)
+    }
+}
+
+/// Remove punctuation from a text before the test starts
+///
+/// Apostrophes are removed like any other punctuation, so "don't" becomes "dont".
+/// A hyphen joining two words is replaced with a space, so "well-known" becomes
+/// the two words "well known". Spaces left doubled are collapsed.
+/// # Arguments
+/// * `prepared_text` - Text and its id
+/// # Returns
+/// * `PreparedText` with ":np" added to the id, an error if nothing but punctuation was left
+pub fn strip_punctuation(prepared_text: PreparedText) -> AppResult<PreparedText> {
+    let (text, text_id) = prepared_text;
+    let chars: Vec<char> = text.chars().collect();
+    let stripped: String = chars.iter()
+        .enumerate()
+        .filter_map(|(index, c)| {
+            let joins_words = matches!(c, '-' | '‐' | '–')
+                && index > 0
+                && chars[index - 1].is_alphanumeric()
+                && chars.get(index + 1).is_some_and(|next| next.is_alphanumeric());
+            if joins_words {
+                Some(' ')
+            } else if is_punctuation(*c) {
+                None
+            } else {
+                Some(*c)
+            }
+        })
+        .collect();
+
+    let stripped = stripped.split_whitespace().collect::<Vec<&str>>().join(" ");
+    if stripped.is_empty() {
+        return Err(AppError::EmptyTextError(text_id));
+    }
+    Ok((stripped, format!("{}:np", text_id)))
+}
+
+/// Lowercase a text before the test starts
+///
+/// Lowercasing follows Unicode, so the text can get longer, "İ" becomes "i̇".
+/// # Arguments
+/// * `prepared_text` - Text and its id
+/// # Returns
+/// * `PreparedText` with ":lc" added to the id
+pub fn lowercase(prepared_text: PreparedText) -> PreparedText {
+    let (text, text_id) = prepared_text;
+    (text.to_lowercase(), format!("{}:lc", text_id))
+}
+
+/// ASCII punctuation and the typographic marks common in the database texts
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation()
+        || matches!(c, '‘' | '’' | '“' | '”' | '‐' | '–' | '—' | '…' | '«' | '»' | '¿' | '¡')
+}
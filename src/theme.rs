@@ -0,0 +1,284 @@
+use crate::app::Color;
+use pancurses::{chtype, ColorPair};
+use std::collections::HashMap;
+use std::fmt::Formatter;
+
+/// Role of every color pair in the app, in the order of the curses pair numbers
+pub const ROLES: [(Color, &str); 7] = [
+    (Color::Green, "progress"),
+    (Color::Red, "error"),
+    (Color::Blue, "title"),
+    (Color::Yellow, "warning"),
+    (Color::Cyan, "header"),
+    (Color::Magenta, "speed"),
+    (Color::Black, "key"),
+];
+
+/// Names of the built-in themes
+pub const THEMES: [&str; 5] = ["default", "ocean", "high-contrast", "transparent", "solarized"];
+/// Color number keeping the default foreground or background of the terminal
+pub const DEFAULT_COLOR: i16 = -1;
+
+/// How colors are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Full,
+    // Blue and orange instead of green and red, with mistakes underlined
+    Colorblind,
+    // Bold, dim and reverse text only
+    Monochrome,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ThemeError {
+    UnknownTheme(String),
+    UnknownRole(String),
+    UnknownColor(String),
+}
+
+impl std::fmt::Display for ThemeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ThemeError::UnknownTheme(name) => {
+                write!(f, "Unknown theme: {}, choose one of {}", name, THEMES.join(", "))
+            }
+            ThemeError::UnknownRole(name) => {
+                let roles: Vec<&str> = ROLES.iter().map(|(_, role)| *role).collect();
+                write!(f, "Unknown theme color: {}, choose one of {}", name, roles.join(", "))
+            }
+            ThemeError::UnknownColor(name) => {
+                write!(f, "Unknown color: {}, use a color name, default or a number in range [-1,255]", name)
+            }
+        }
+    }
+}
+
+/// Foreground and background curses colors of every color pair
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    colors: HashMap<Color, (i16, i16)>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        use pancurses::{
+            COLOR_BLACK, COLOR_BLUE, COLOR_CYAN, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_WHITE, COLOR_YELLOW,
+        };
+        Theme::from_pairs([
+            (COLOR_WHITE, COLOR_GREEN),
+            (COLOR_WHITE, COLOR_RED),
+            (COLOR_WHITE, COLOR_BLUE),
+            (COLOR_WHITE, COLOR_YELLOW),
+            (COLOR_WHITE, COLOR_CYAN),
+            (COLOR_WHITE, COLOR_MAGENTA),
+            (COLOR_BLACK, COLOR_WHITE),
+        ])
+    }
+}
+
+impl Theme {
+    /// Get one of the built-in themes by name
+    pub fn builtin(name: &str) -> Result<Theme, ThemeError> {
+        use pancurses::{
+            COLOR_BLACK, COLOR_BLUE, COLOR_CYAN, COLOR_GREEN, COLOR_MAGENTA, COLOR_RED, COLOR_WHITE, COLOR_YELLOW,
+        };
+        match name {
+            "default" => Ok(Theme::default()),
+            "ocean" => Ok(Theme::from_pairs([
+                (COLOR_BLACK, COLOR_CYAN),
+                (COLOR_WHITE, COLOR_MAGENTA),
+                (COLOR_WHITE, COLOR_BLUE),
+                (COLOR_BLACK, COLOR_YELLOW),
+                (COLOR_WHITE, COLOR_BLUE),
+                (COLOR_BLACK, COLOR_CYAN),
+                (COLOR_BLUE, COLOR_WHITE),
+            ])),
+            "high-contrast" => Ok(Theme::from_pairs([
+                (COLOR_BLACK, COLOR_GREEN),
+                (COLOR_BLACK, COLOR_RED),
+                (COLOR_BLACK, COLOR_WHITE),
+                (COLOR_BLACK, COLOR_YELLOW),
+                (COLOR_BLACK, COLOR_WHITE),
+                (COLOR_BLACK, COLOR_YELLOW),
+                (COLOR_WHITE, COLOR_BLACK),
+            ])),
+            "transparent" => Ok(Theme::from_pairs([
+                (COLOR_GREEN, DEFAULT_COLOR),
+                (COLOR_RED, DEFAULT_COLOR),
+                (COLOR_BLUE, DEFAULT_COLOR),
+                (COLOR_YELLOW, DEFAULT_COLOR),
+                (COLOR_CYAN, DEFAULT_COLOR),
+                (COLOR_MAGENTA, DEFAULT_COLOR),
+                (COLOR_BLACK, COLOR_WHITE),
+            ])),
+            // Accent colors of the solarized palette from the 256 colors
+            "solarized" => Ok(Theme::from_pairs([
+                (64, DEFAULT_COLOR),
+                (160, DEFAULT_COLOR),
+                (33, DEFAULT_COLOR),
+                (136, DEFAULT_COLOR),
+                (37, DEFAULT_COLOR),
+                (125, DEFAULT_COLOR),
+                (235, 187),
+            ])),
+            _ => Err(ThemeError::UnknownTheme(name.to_string())),
+        }
+    }
+
+    fn from_pairs(pairs: [(i16, i16); 7]) -> Theme {
+        Theme {
+            colors: ROLES.iter().map(|(color, _)| *color).zip(pairs).collect(),
+        }
+    }
+
+    /// Replace the colors of one role
+    /// # Arguments
+    /// * `role` - Role name from `ROLES`, like "error"
+    /// * `value` - Foreground and background separated by a comma, like "white,red"
+    pub fn set_role(&mut self, role: &str, value: &str) -> Result<(), ThemeError> {
+        let color = ROLES.iter()
+            .find(|(_, name)| *name == role)
+            .map(|(color, _)| *color)
+            .ok_or(ThemeError::UnknownRole(role.to_string()))?;
+        let (foreground, background) = value.split_once(',')
+            .ok_or(ThemeError::UnknownColor(value.to_string()))?;
+        self.colors.insert(color, (parse_color(foreground)?, parse_color(background)?));
+        Ok(())
+    }
+
+    /// The same theme with blue progress and orange-ish mistakes instead of green and red
+    pub fn colorblind(&self) -> Theme {
+        let mut theme = self.clone();
+        theme.colors.insert(Color::Green, (pancurses::COLOR_WHITE, pancurses::COLOR_BLUE));
+        theme.colors.insert(Color::Red, (pancurses::COLOR_BLACK, pancurses::COLOR_YELLOW));
+        // The warnings were yellow too
+        theme.colors.insert(Color::Yellow, (pancurses::COLOR_YELLOW, pancurses::COLOR_BLACK));
+        theme
+    }
+
+    /// The same theme using only the colors the terminal can show.
+    ///
+    /// Colors past the ones of the terminal fall back to the closest of the 8 basic colors,
+    /// and the default colors fall back to white on black.
+    /// # Arguments
+    /// * `colors` - Number of colors of the terminal, from `pancurses::COLORS()`
+    /// * `default_colors` - Whether the terminal accepts `DEFAULT_COLOR`, from `pancurses::use_default_colors()`
+    pub fn for_terminal(&self, colors: i32, default_colors: bool) -> Theme {
+        let fit = |color: i16, fallback: i16| {
+            if color == DEFAULT_COLOR {
+                if default_colors { color } else { fallback }
+            } else if i32::from(color) >= colors {
+                basic_color(color)
+            } else {
+                color
+            }
+        };
+        Theme {
+            colors: self.colors.iter()
+                .map(|(role, (foreground, background))| {
+                    (*role, (fit(*foreground, pancurses::COLOR_WHITE), fit(*background, pancurses::COLOR_BLACK)))
+                })
+                .collect(),
+        }
+    }
+
+    /// Curses pair number with its foreground and background for every color of the app
+    pub fn pairs(&self) -> Vec<(i16, Color, i16, i16)> {
+        ROLES.iter()
+            .enumerate()
+            .map(|(index, (color, _))| {
+                let (foreground, background) = self.colors[color];
+                (index as i16 + 1, *color, foreground, background)
+            })
+            .collect()
+    }
+}
+
+/// Attribute drawing every color of the app in the given mode.
+///
+/// The color pairs of the theme have to be set up with `init_pair` unless the mode is monochrome.
+pub fn color_attributes(theme: &Theme, color_mode: ColorMode) -> HashMap<Color, chtype> {
+    theme.pairs()
+        .into_iter()
+        .map(|(pair, color, ..)| {
+            let attribute = match color_mode {
+                ColorMode::Full => chtype::from(ColorPair(pair as u8)),
+                ColorMode::Colorblind => chtype::from(ColorPair(pair as u8)) | colorblind_attribute(color),
+                ColorMode::Monochrome => monochrome_attribute(color),
+            };
+            (color, attribute)
+        })
+        .collect()
+}
+
+/// Attribute added to a color pair so mistakes don't depend on the color alone
+fn colorblind_attribute(color: Color) -> chtype {
+    match color {
+        Color::Red => pancurses::A_UNDERLINE | pancurses::A_BOLD,
+        Color::Yellow => pancurses::A_REVERSE,
+        _ => pancurses::A_NORMAL,
+    }
+}
+
+/// Attribute standing in for a color on terminals without colors
+fn monochrome_attribute(color: Color) -> chtype {
+    match color {
+        Color::Green => pancurses::A_REVERSE,
+        Color::Red => pancurses::A_REVERSE | pancurses::A_BOLD | pancurses::A_UNDERLINE,
+        Color::Blue => pancurses::A_BOLD,
+        Color::Yellow => pancurses::A_DIM | pancurses::A_UNDERLINE,
+        Color::Cyan => pancurses::A_REVERSE,
+        Color::Magenta => pancurses::A_REVERSE | pancurses::A_BOLD,
+        Color::Black => pancurses::A_REVERSE,
+    }
+}
+
+/// Closest of the 8 basic colors to one of the 256 colors
+pub fn basic_color(color: i16) -> i16 {
+    match color {
+        0..=7 => color,
+        // Bright versions of the basic colors
+        8..=15 => color - 8,
+        // 6x6x6 color cube, keep the components at least half as strong as the strongest one
+        16..=231 => {
+            let index = color - 16;
+            let (red, green, blue) = (index / 36, index / 6 % 6, index % 6);
+            let strongest = red.max(green).max(blue);
+            if strongest == 0 {
+                return pancurses::COLOR_BLACK;
+            }
+            if red == green && green == blue {
+                return if strongest >= 3 { pancurses::COLOR_WHITE } else { pancurses::COLOR_BLACK };
+            }
+            let threshold = (strongest + 1) / 2;
+            [(red, pancurses::COLOR_RED), (green, pancurses::COLOR_GREEN), (blue, pancurses::COLOR_BLUE)]
+                .iter()
+                .filter(|(component, _)| *component >= threshold)
+                .fold(pancurses::COLOR_BLACK, |basic, (_, bit)| basic | bit)
+        }
+        // Grayscale ramp
+        232..=243 => pancurses::COLOR_BLACK,
+        _ => pancurses::COLOR_WHITE,
+    }
+}
+
+/// Parse a curses color from its name, like "red", "default" for the color of the terminal, or its number in range 0-255
+pub fn parse_color(name: &str) -> Result<i16, ThemeError> {
+    let name = name.trim();
+    let color = match name.to_lowercase().as_str() {
+        "black" => pancurses::COLOR_BLACK,
+        "red" => pancurses::COLOR_RED,
+        "green" => pancurses::COLOR_GREEN,
+        "yellow" => pancurses::COLOR_YELLOW,
+        "blue" => pancurses::COLOR_BLUE,
+        "magenta" => pancurses::COLOR_MAGENTA,
+        "cyan" => pancurses::COLOR_CYAN,
+        "white" => pancurses::COLOR_WHITE,
+        "default" | "-1" => DEFAULT_COLOR,
+        number => number.parse::<u8>()
+            .map(i16::from)
+            .map_err(|_| ThemeError::UnknownColor(name.to_string()))?,
+    };
+    Ok(color)
+}
@@ -1,18 +1,27 @@
-use std::time;
-use std::time::{SystemTime, SystemTimeError};
+use std::time::{Duration, Instant};
 
 /// Get time elapsed since initial keypress.
 /// This is required to calculate speed.
 /// # Arguments:
 /// * `start_time` - The time when user starts typing the sample text.
 /// # Returns:
-/// * `f64` - The time elapsed since initial keypress.
-pub fn get_elapsed_minutes_since_first_keypress(start_time: SystemTime) -> Result<f64, SystemTimeError> {
-    let system_time = SystemTime::now()
-        .duration_since(time::UNIX_EPOCH)?
-        .as_secs_f64()
-        - start_time
-        .duration_since(time::UNIX_EPOCH)?
-        .as_secs_f64();
-    Ok(system_time / 60.0)
+/// * `f64` - The minutes elapsed since initial keypress.
+pub fn get_elapsed_minutes_since_first_keypress(start_time: Instant) -> f64 {
+    start_time.elapsed().as_secs_f64() / 60.0
+}
+
+/// Format a duration as `MM:SS` for the live timer.
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    format!("{:02}:{:02}", seconds / 60, seconds % 60)
+}
+
+/// Format a long total of practice as `6h 12m`, `12m` or `40s`.
+pub fn format_total(seconds: f64) -> String {
+    let seconds = seconds as u64;
+    match (seconds / 3600, seconds % 3600 / 60) {
+        (0, 0) => format!("{}s", seconds),
+        (0, minutes) => format!("{}m", minutes),
+        (hours, minutes) => format!("{}h {}m", hours, minutes),
+    }
 }
@@ -0,0 +1,76 @@
+use std::fmt::Formatter;
+use std::time::Duration;
+use crate::session::SessionStats;
+
+/// Longest wait for the webhook to answer, the end screen waits for it
+pub const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug)]
+pub enum WebhookError {
+    JsonError(serde_json::Error),
+    RequestError(String),
+    // Built without the webhook feature
+    Unsupported,
+}
+
+impl std::fmt::Display for WebhookError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WebhookError::JsonError(e) => {
+                write!(f, "Can't write results as JSON: {}", e)
+            }
+            WebhookError::RequestError(e) => {
+                write!(f, "Webhook request failed: {}", e)
+            }
+            WebhookError::Unsupported => {
+                write!(f, "Webhook not sent, rstype was built without the webhook feature")
+            }
+        }
+    }
+}
+
+impl From<serde_json::Error> for WebhookError {
+    fn from(e: serde_json::Error) -> Self {
+        WebhookError::JsonError(e)
+    }
+}
+
+/// Sends a JSON body to a webhook
+///
+/// The app posts over HTTP, tests can record the requests instead.
+pub trait WebhookTransport {
+    fn post_json(&mut self, url: &str, body: &str) -> Result<(), WebhookError>;
+}
+
+/// Posts over HTTP with `WEBHOOK_TIMEOUT`
+#[cfg(feature = "webhook")]
+pub struct HttpTransport;
+
+#[cfg(feature = "webhook")]
+impl WebhookTransport for HttpTransport {
+    fn post_json(&mut self, url: &str, body: &str) -> Result<(), WebhookError> {
+        ureq::post(url)
+            .timeout(WEBHOOK_TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(body)
+            .map(|_| ())
+            .map_err(|e| WebhookError::RequestError(e.to_string()))
+    }
+}
+
+/// Stands in for HTTP when the webhook feature is off, every request fails
+#[cfg(not(feature = "webhook"))]
+pub struct HttpTransport;
+
+#[cfg(not(feature = "webhook"))]
+impl WebhookTransport for HttpTransport {
+    fn post_json(&mut self, _url: &str, _body: &str) -> Result<(), WebhookError> {
+        Err(WebhookError::Unsupported)
+    }
+}
+
+/// Post the stats of a finished session as JSON
+pub fn post_stats(transport: &mut dyn WebhookTransport, url: &str, stats: &SessionStats) -> Result<(), WebhookError> {
+    let body = serde_json::to_string(stats)?;
+    transport.post_json(url, &body)
+}
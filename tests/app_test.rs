@@ -0,0 +1,90 @@
+use pancurses::Input;
+use rstype::app::{App, AppOptions};
+use std::time::Duration;
+
+fn type_text(app: &mut App, text: &str) {
+    for c in text.chars() {
+        app.process_key(&Input::Character(c));
+    }
+}
+
+#[test]
+fn test_header_layout() {
+    use rstype::app::{header_layout, HeaderLayout};
+
+    // Wide windows fit every header item
+    assert_eq!(header_layout(80, 8), HeaderLayout { title: Some(36), timer: Some(59), wpm: 66, short_wpm: false });
+    // The title goes first when space runs out, then the timer
+    assert_eq!(header_layout(32, 8), HeaderLayout { title: None, timer: Some(11), wpm: 18, short_wpm: false });
+    assert_eq!(header_layout(26, 8), HeaderLayout { title: None, timer: None, wpm: 12, short_wpm: false });
+    // Then the live WPM is shortened
+    assert_eq!(header_layout(20, 8), HeaderLayout { title: None, timer: None, wpm: 13, short_wpm: true });
+    assert_eq!(header_layout(40, 30), HeaderLayout { title: None, timer: None, wpm: 33, short_wpm: true });
+}
+
+#[test]
+fn test_time_limit() {
+    let options = AppOptions { time_limit: Some(Duration::from_secs(60)), ..AppOptions::default() };
+    let app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+
+    assert!(!app.is_time_up(Duration::from_secs(59)));
+    assert!(app.is_time_up(Duration::from_secs(60)));
+    // The time after the limit isn't counted
+    assert_eq!(app.test_duration(Duration::from_millis(60_080)), Duration::from_secs(60));
+    assert_eq!(app.test_duration(Duration::from_secs(30)), Duration::from_secs(30));
+}
+
+#[test]
+fn test_without_time_limit() {
+    let app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+
+    assert!(!app.is_time_up(Duration::from_secs(3600)));
+    assert_eq!(app.test_duration(Duration::from_secs(90)), Duration::from_secs(90));
+}
+
+#[test]
+fn test_marathon_moves_through_texts() {
+    let options = AppOptions {
+        marathon: vec![("three".to_string(), "2".to_string()), ("four five".to_string(), "3".to_string())],
+        ..AppOptions::default()
+    };
+    let mut app = App::with_options(("one two".to_string(), "1".to_string()), options);
+    assert_eq!(app.marathon_progress().as_deref(), Some("1/3"));
+
+    type_text(&mut app, "one two");
+    assert!(app.start_next_marathon_text());
+    assert_eq!(app.marathon_progress().as_deref(), Some("2/3"));
+
+    type_text(&mut app, "three");
+    assert!(app.start_next_marathon_text());
+    assert_eq!(app.marathon_progress().as_deref(), Some("3/3"));
+
+    type_text(&mut app, "four five");
+    assert!(app.session().is_complete());
+    assert!(!app.start_next_marathon_text());
+    assert_eq!(app.session().completed_words(), 5);
+    // The whole marathon is saved as one history row
+    assert_eq!(app.history_id(), "1+2+3");
+}
+
+#[test]
+fn test_single_text_is_not_a_marathon() {
+    let app = App::from_prepared_text(("one two".to_string(), "1".to_string()));
+    assert_eq!(app.marathon_progress(), None);
+    assert_eq!(app.history_id(), "1");
+}
+
+#[test]
+fn test_blind_mode_still_tracks_mistakes() {
+    let options = AppOptions { blind: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hi".to_string(), "test".to_string()), options);
+
+    type_text(&mut app, "hx");
+    app.process_key(&Input::KeyBackspace);
+    type_text(&mut app, "i");
+
+    assert!(app.session().is_complete());
+    assert_eq!(app.session().mistakes_at(1), 1);
+    assert_eq!(app.session().error_count(), 1);
+}
+
@@ -0,0 +1,510 @@
+use rstype::calculations;
+
+#[test]
+fn test_first_index_at_which_strings_differ_with_multibyte_chars() {
+    assert_eq!(calculations::first_index_at_which_strings_differ("caf", "café"), 3);
+    assert_eq!(calculations::first_index_at_which_strings_differ("café", "café au lait"), 4);
+    assert_eq!(calculations::first_index_at_which_strings_differ("прибут", "привіт"), 3);
+}
+
+#[test]
+fn test_slice_by_char_indices() {
+    let text = "café au lait";
+    assert_eq!(calculations::slice_by_char_indices(text, 0, 4), "café");
+    assert_eq!(calculations::slice_by_char_indices(text, 3, 5), "é ");
+    assert_eq!(calculations::slice_by_char_indices(text, 5, 100), "au lait");
+    assert_eq!(calculations::slice_by_char_indices(text, 6, 2), "");
+}
+
+#[test]
+fn test_char_index_to_byte_offset() {
+    let text = "привіт";
+    assert_eq!(calculations::char_index_to_byte_offset(text, 0), 0);
+    assert_eq!(calculations::char_index_to_byte_offset(text, 2), 4);
+    assert_eq!(calculations::char_index_to_byte_offset(text, 6), text.len());
+    assert_eq!(calculations::char_index_to_byte_offset(text, 10), text.len());
+}
+
+#[test]
+fn test_word_wrap_with_multibyte_chars() {
+    let text = "Über die Brücke gehen wir nach Hause";
+    let wrapped = calculations::word_wrap(text, 10);
+    assert_eq!(wrapped, "Über die  Brücke    gehen wir nach Hause");
+}
+
+#[test]
+fn test_wrap_lines() {
+    let lines = calculations::wrap_lines("Über die Brücke gehen wir nach Hause", 10);
+    assert_eq!(lines, vec!["Über die", "Brücke", "gehen wir", "nach Hause"]);
+    assert_eq!(calculations::wrap_lines("short", 40), vec!["short"]);
+}
+
+#[test]
+fn test_word_wrap_keeps_lines_within_width() {
+    let texts = [
+        "Falsches Üben von Xylophonmusik quält jeden größeren Zwerg, \
+        während Fuchs und Bär über die große Brücke nach Hause gehen.",
+        "Щастям б'єш жук їх глицю в фон й ґедзь пріч. Чуєш їх, доцю, \
+        га? Кумедна ж ти, прощайся без ґольфів!",
+    ];
+    let width = 40;
+    for text in texts {
+        let wrapped = calculations::word_wrap(text, width);
+        let positions = calculations::char_positions(&wrapped, width);
+        let chars: Vec<char> = wrapped.chars().collect();
+
+        // Every line is filled up to the window width and no further
+        for line in wrapped_lines(&chars, &positions) {
+            assert!(line.chars().count() <= width as usize, "{:?}", line);
+        }
+        // No word is split between two lines
+        let mut words = 0;
+        for (i, c) in chars.iter().enumerate() {
+            if *c != ' ' && (i == 0 || chars[i - 1] == ' ') {
+                words += 1;
+            } else if *c != ' ' {
+                assert_eq!(positions[i].0, positions[i - 1].0);
+            }
+        }
+        assert_eq!(words, text.split_whitespace().count());
+    }
+}
+
+#[test]
+fn test_char_positions_with_wide_chars() {
+    // Full-width characters take two cells and never straddle a line break
+    let positions = calculations::char_positions("日本語abc", 5);
+    assert_eq!(positions, vec![(0, 0), (0, 2), (1, 0), (1, 2), (1, 3), (1, 4)]);
+    assert_eq!(calculations::number_of_lines_to_fit_text_in_window("日本語abc", 5), 2);
+}
+
+fn wrapped_lines(chars: &[char], positions: &[(i32, i32)]) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for (c, (row, _)) in chars.iter().zip(positions) {
+        if lines.len() <= *row as usize {
+            lines.push(String::new());
+        }
+        lines[*row as usize].push(*c);
+    }
+    lines
+}
+
+#[test]
+fn test_first_index_at_which_strings_differ() {
+    assert_eq!(calculations::first_index_at_which_strings_differ("", ""), 0);
+    assert_eq!(calculations::first_index_at_which_strings_differ("", "hello"), 0);
+    assert_eq!(calculations::first_index_at_which_strings_differ("hello", ""), 0);
+    assert_eq!(calculations::first_index_at_which_strings_differ("hello", "hello"), 5);
+    assert_eq!(calculations::first_index_at_which_strings_differ("hel", "hello"), 3);
+    assert_eq!(calculations::first_index_at_which_strings_differ("hello world", "hello"), 5);
+    assert_eq!(calculations::first_index_at_which_strings_differ("hallo", "hello"), 1);
+}
+
+#[test]
+fn test_get_space_count_after_ith_word() {
+    let text = "one two";
+    assert_eq!(calculations::get_space_count_after_ith_word(3, text), 1);
+    assert_eq!(calculations::get_space_count_after_ith_word(2, text), 0);
+    assert_eq!(calculations::get_space_count_after_ith_word(7, text), 0);
+    assert_eq!(calculations::get_space_count_after_ith_word(100, text), 0);
+}
+
+#[test]
+fn test_get_space_count_after_ith_word_in_wrapped_text() {
+    let wrapped = calculations::word_wrap("Übung macht den Meister", 10);
+    assert_eq!(wrapped, "Übung     macht den Meister");
+    // Padding spaces after the first word fill the rest of the line
+    assert_eq!(calculations::get_space_count_after_ith_word(5, &wrapped), 5);
+    assert_eq!(calculations::get_space_count_after_ith_word(15, &wrapped), 1);
+}
+
+#[test]
+fn test_accuracy() {
+    assert_eq!(calculations::accuracy(100, 0), 100.0);
+    assert_eq!(calculations::accuracy(100, 25), 75.0);
+    assert_eq!(calculations::accuracy(10, 20), 0.0);
+    assert_eq!(calculations::accuracy(0, 0), 0.0);
+}
+
+#[test]
+fn test_gross_wpm() {
+    assert_eq!(calculations::gross_wpm(250, 1.0), 50.0);
+    assert_eq!(calculations::gross_wpm(250, 0.5), 100.0);
+    assert_eq!(calculations::gross_wpm(250, 0.0), 0.0);
+}
+
+#[test]
+fn test_net_wpm() {
+    assert_eq!(calculations::net_wpm(250, 0, 1.0), 50.0);
+    assert_eq!(calculations::net_wpm(250, 10, 1.0), 40.0);
+    assert_eq!(calculations::net_wpm(250, 10, 2.0), 20.0);
+    assert_eq!(calculations::net_wpm(50, 100, 1.0), 0.0);
+    assert_eq!(calculations::net_wpm(250, 10, 0.0), 0.0);
+}
+
+#[test]
+fn test_chars_per_minute() {
+    assert_eq!(calculations::chars_per_minute(300, 1.0), 300.0);
+    assert_eq!(calculations::chars_per_minute(300, 0.5), 600.0);
+    assert_eq!(calculations::chars_per_minute(300, 0.0), 0.0);
+}
+
+#[test]
+fn test_consistency_of_regular_input() {
+    assert_eq!(calculations::consistency(&[0.2, 0.2, 0.2, 0.2]), 100.0);
+}
+
+#[test]
+fn test_consistency_of_bursty_input() {
+    let bursty = calculations::consistency(&[0.05, 0.05, 1.5, 0.05, 0.05, 2.0]);
+    let steady = calculations::consistency(&[0.2, 0.25, 0.2, 0.15, 0.2]);
+    assert!(bursty < 20.0, "{}", bursty);
+    assert!(steady > 80.0, "{}", steady);
+    assert_eq!(calculations::consistency(&[0.01, 0.01, 0.01, 10.0]), 0.0);
+}
+
+#[test]
+fn test_consistency_with_too_few_intervals() {
+    assert_eq!(calculations::consistency(&[]), 100.0);
+    assert_eq!(calculations::consistency(&[0.3]), 100.0);
+}
+
+#[test]
+fn test_speed_in_wpm_with_minutes() {
+    let tokens: Vec<String> = "the quick brown fox jumps over the lazy dog now"
+        .split(' ')
+        .map(|s| s.to_string())
+        .collect();
+    assert_eq!(calculations::speed_in_wpm_with_minutes(&tokens, 1.0), 10.0);
+    assert_eq!(calculations::speed_in_wpm_with_minutes(&tokens, 0.25), 40.0);
+    // Finishing instantly must not report an infinite speed
+    assert_eq!(calculations::speed_in_wpm_with_minutes(&tokens, 0.0), 0.0);
+    assert_eq!(calculations::speed_in_wpm_with_minutes(&[], 1.0), 0.0);
+}
+
+#[test]
+fn test_words_per_minute() {
+    assert_eq!(calculations::words_per_minute(30, 0.5), 60.0);
+    assert_eq!(calculations::words_per_minute(0, 0.5), 0.0);
+    assert_eq!(calculations::words_per_minute(30, 0.0), 0.0);
+}
+
+#[test]
+fn test_rolling_wpm() {
+    use std::time::Duration;
+    let window = Duration::from_secs(10);
+
+    // 50 keystrokes in the last 10 seconds make 10 words in 1/6 of a minute
+    let keystrokes: Vec<Duration> = (0..100)
+        .map(|i| Duration::from_millis(i * 200))
+        .collect();
+    let wpm = calculations::rolling_wpm(&keystrokes, Duration::from_secs(20), window);
+    assert!((wpm - 60.0).abs() < 1.5, "{}", wpm);
+}
+
+#[test]
+fn test_rolling_wpm_edge_cases() {
+    use std::time::Duration;
+    let window = Duration::from_secs(10);
+
+    // No keystrokes in the window
+    let keystrokes = [Duration::from_secs(1), Duration::from_secs(2)];
+    assert_eq!(calculations::rolling_wpm(&keystrokes, Duration::from_secs(30), window), 0.0);
+    assert_eq!(calculations::rolling_wpm(&[], Duration::from_secs(30), window), 0.0);
+    assert_eq!(calculations::rolling_wpm(&[], Duration::ZERO, window), 0.0);
+
+    // The window is shorter than 10 seconds at the start of the test
+    let keystrokes: Vec<Duration> = (0..10).map(|i| Duration::from_millis(i * 100)).collect();
+    assert_eq!(calculations::rolling_wpm(&keystrokes, Duration::from_secs(1), window), 120.0);
+}
+
+#[test]
+fn test_progress_percent() {
+    assert_eq!(calculations::progress_percent(0, 200), 0);
+    assert_eq!(calculations::progress_percent(84, 200), 42);
+    // Rounded down so 100% is only shown at the very end
+    assert_eq!(calculations::progress_percent(199, 200), 99);
+    assert_eq!(calculations::progress_percent(200, 200), 100);
+    assert_eq!(calculations::progress_percent(0, 0), 0);
+}
+
+#[test]
+fn test_progress_bar() {
+    assert_eq!(calculations::progress_bar(5, 10, 17), "[=====     ]  50%");
+    assert_eq!(calculations::progress_bar(0, 10, 17), "[          ]   0%");
+    assert_eq!(calculations::progress_bar(10, 10, 17), "[==========] 100%");
+    assert_eq!(calculations::progress_bar(5, 10, 17).len(), 17);
+    // Too narrow for the bar itself
+    assert_eq!(calculations::progress_bar(5, 10, 6), "50%");
+    assert_eq!(calculations::progress_bar(5, 10, 2), "50");
+}
+
+#[test]
+fn test_slowest_words() {
+    let timings = vec![
+        ("quick".to_string(), 1.0),
+        ("the".to_string(), 0.3),
+        ("brown".to_string(), 2.0),
+        ("fox".to_string(), 0.6),
+    ];
+    let slowest = calculations::slowest_words(&timings, 2);
+    assert_eq!(slowest, vec![("brown", 30.0), ("quick", 60.0)]);
+    assert_eq!(calculations::slowest_words(&timings, 10).len(), 4);
+    assert!(calculations::slowest_words(&[], 5).is_empty());
+}
+
+#[test]
+fn test_latency_stats() {
+    use std::time::Duration;
+    let times: Vec<Duration> = [0, 100, 300, 400, 1400, 1500]
+        .iter()
+        .map(|ms| Duration::from_millis(*ms))
+        .collect();
+    let stats = calculations::latency_stats(&times).unwrap();
+    assert!((stats.mean_ms - 300.0).abs() < 1e-9);
+    assert!((stats.median_ms - 100.0).abs() < 1e-9);
+    assert!((stats.longest_pause_ms - 1000.0).abs() < 1e-9);
+    // The pause came right before the fifth keystroke
+    assert_eq!(stats.longest_pause_index, 4);
+}
+
+#[test]
+fn test_latency_stats_with_even_number_of_intervals() {
+    use std::time::Duration;
+    let times: Vec<Duration> = [0, 100, 300, 600, 1000]
+        .iter()
+        .map(|ms| Duration::from_millis(*ms))
+        .collect();
+    let stats = calculations::latency_stats(&times).unwrap();
+    assert!((stats.median_ms - 250.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_latency_stats_with_too_few_keystrokes() {
+    use std::time::Duration;
+    assert_eq!(calculations::latency_stats(&[]), None);
+    assert_eq!(calculations::latency_stats(&[Duration::from_millis(5)]), None);
+}
+
+#[test]
+fn test_wpm_buckets() {
+    use std::time::Duration;
+    // 50 keystrokes in the first 5 seconds, then 25 in the 4.8 seconds up to the last one
+    let mut times: Vec<Duration> = (0..50).map(|i| Duration::from_millis(i * 100)).collect();
+    times.extend((0..25).map(|i| Duration::from_millis(5000 + i * 200)));
+    let buckets = calculations::wpm_buckets(&times, Duration::from_secs(5));
+    assert_eq!(buckets.len(), 2);
+    assert!((buckets[0] - 120.0).abs() < 1e-9);
+    assert!((buckets[1] - 62.5).abs() < 1e-9);
+
+    assert!(calculations::wpm_buckets(&[], Duration::from_secs(5)).is_empty());
+}
+
+#[test]
+fn test_sparkline() {
+    assert_eq!(calculations::sparkline(&[0.0, 50.0, 100.0], 3), "▁▅█");
+    // Values are stretched or averaged to fill the width
+    assert_eq!(calculations::sparkline(&[10.0, 20.0], 4), "▅▅██");
+    assert_eq!(calculations::sparkline(&[0.0, 50.0, 100.0, 100.0], 2), "▃█");
+    assert_eq!(calculations::sparkline(&[0.0, 0.0], 2), "▁▁");
+    assert_eq!(calculations::sparkline(&[], 10), "");
+    assert_eq!(calculations::sparkline(&[1.0], 0), "");
+}
+
+#[test]
+fn test_trend_sparkline() {
+    // Scaled between the lowest and the highest value, not from zero
+    assert_eq!(calculations::trend_sparkline(&[50.0, 55.0, 60.0], 10), "▁▅█");
+    // More values than columns are averaged, fewer aren't stretched
+    assert_eq!(calculations::trend_sparkline(&[50.0, 50.0, 60.0, 60.0, 70.0, 70.0], 3), "▁▅█");
+    assert_eq!(calculations::trend_sparkline(&[50.0, 51.0, 70.0, 80.0], 2), "▁█");
+    // A single value or equal values make a flat line
+    assert_eq!(calculations::trend_sparkline(&[42.0], 5), "▅");
+    assert_eq!(calculations::trend_sparkline(&[42.0, 42.0, 42.0], 5), "▅▅▅");
+    assert_eq!(calculations::trend_sparkline(&[], 5), "");
+    assert_eq!(calculations::trend_sparkline(&[1.0, 2.0], 0), "");
+}
+
+#[test]
+fn test_pace_char_index() {
+    // 60 WPM is 300 characters per minute, 5 per second
+    assert_eq!(calculations::pace_char_index(60.0, 0.0, 100), 0);
+    assert_eq!(calculations::pace_char_index(60.0, 1.0 / 60.0, 100), 5);
+    assert_eq!(calculations::pace_char_index(60.0, 0.1, 100), 30);
+    // Never past the end of the text
+    assert_eq!(calculations::pace_char_index(60.0, 1.0, 100), 100);
+    assert_eq!(calculations::pace_char_index(0.0, 1.0, 100), 0);
+}
+
+#[test]
+fn test_pace_position() {
+    let text = calculations::word_wrap("the quick brown fox", 10);
+
+    assert_eq!(calculations::pace_position(&text, 10, 60.0, 0.0), Some((0, (0, 0))));
+    // 12 characters in, on the second line
+    assert_eq!(calculations::pace_position(&text, 10, 60.0, 0.04), Some((12, (1, 2))));
+    // The whole text has been typed at that pace
+    assert_eq!(calculations::pace_position(&text, 10, 60.0, 1.0), None);
+}
+
+#[test]
+fn test_wrapped_char_index() {
+    let original = "the quick brown fox";
+    let wrapped = calculations::word_wrap(original, 10);
+
+    assert_eq!(calculations::wrapped_char_index(original, &wrapped, 0), 0);
+    assert_eq!(calculations::wrapped_char_index(original, &wrapped, 9), 9);
+    // "brown" starts after the spaces added by wrapping
+    assert_eq!(calculations::wrapped_char_index(original, &wrapped, 10), 10 + wrapped.len() - original.len());
+    assert_eq!(calculations::wrapped_char_index(original, &wrapped, 19), wrapped.chars().count());
+}
+
+#[test]
+fn test_difficulty_for_stats() {
+    assert_eq!(calculations::difficulty_for_stats(0.0, 100.0), 1);
+    assert_eq!(calculations::difficulty_for_stats(29.9, 98.0), 1);
+    assert_eq!(calculations::difficulty_for_stats(30.0, 98.0), 2);
+    assert_eq!(calculations::difficulty_for_stats(55.0, 98.0), 3);
+    assert_eq!(calculations::difficulty_for_stats(79.0, 98.0), 4);
+    assert_eq!(calculations::difficulty_for_stats(120.0, 98.0), 5);
+    // Low accuracy takes a step down, but never below the easiest texts
+    assert_eq!(calculations::difficulty_for_stats(120.0, 85.0), 4);
+    assert_eq!(calculations::difficulty_for_stats(20.0, 85.0), 1);
+}
+
+#[test]
+fn test_word_index_at() {
+    let text = calculations::word_wrap("the quick brown fox", 10);
+    assert_eq!(calculations::word_index_at(&text, 0), 0);
+    assert_eq!(calculations::word_index_at(&text, 3), 0);
+    assert_eq!(calculations::word_index_at(&text, 4), 1);
+    // The padding added by wrapping belongs to the word before it
+    assert_eq!(calculations::word_index_at(&text, 9), 1);
+    assert_eq!(calculations::word_index_at(&text, text.chars().count() - 1), 3);
+}
+
+#[test]
+fn test_problem_bigrams() {
+    let text = "the then other";
+    // "th" at 1 and 11, "he" at 6, a mistake on the first character and one after a space
+    let mistakes = [1, 1, 11, 6, 0, 4];
+    assert_eq!(calculations::problem_bigrams(text, &mistakes, 5), vec![
+        ("th".to_string(), 3),
+        ("he".to_string(), 1),
+    ]);
+    assert_eq!(calculations::problem_bigrams(text, &mistakes, 1), vec![("th".to_string(), 3)]);
+    assert!(calculations::problem_bigrams(text, &[], 5).is_empty());
+    // Positions past the end of the text are ignored
+    assert!(calculations::problem_bigrams(text, &[100], 5).is_empty());
+}
+
+#[test]
+fn test_problem_bigrams_with_multibyte_chars() {
+    assert_eq!(calculations::problem_bigrams("café", &[3], 5), vec![("fé".to_string(), 1)]);
+}
+
+#[test]
+fn test_is_pasted_input_with_human_typing() {
+    use std::time::Duration;
+    // Around 100 WPM with some jitter, and a fast roll of a few keys
+    let mut keystroke_times: Vec<Duration> = (0..200u64)
+        .map(|i| Duration::from_millis(i * 120 + (i * 37) % 50))
+        .collect();
+    keystroke_times.extend((0..5).map(|i| Duration::from_millis(30_000 + i * 10)));
+    assert!(!calculations::is_pasted_input(
+        &keystroke_times,
+        calculations::PASTE_MAX_INTERVAL,
+        calculations::PASTE_MIN_BURST,
+    ));
+}
+
+#[test]
+fn test_is_pasted_input_with_pasted_text() {
+    use std::time::Duration;
+    // A few typed keys, then the rest of the text arriving all at once
+    let mut keystroke_times: Vec<Duration> = (0..5).map(|i| Duration::from_millis(i * 150)).collect();
+    keystroke_times.extend((0..50).map(|i| Duration::from_micros(1_000_000 + i * 20)));
+    assert!(calculations::is_pasted_input(
+        &keystroke_times,
+        calculations::PASTE_MAX_INTERVAL,
+        calculations::PASTE_MIN_BURST,
+    ));
+    // A burst shorter than the limit isn't enough
+    assert!(!calculations::is_pasted_input(&keystroke_times[..12], calculations::PASTE_MAX_INTERVAL, 10));
+    assert!(!calculations::is_pasted_input(&[], calculations::PASTE_MAX_INTERVAL, 10));
+}
+
+#[test]
+fn test_looks_like_caps_lock() {
+    let text = "hello world";
+    assert!(!calculations::looks_like_caps_lock("HE", text));
+    assert!(calculations::looks_like_caps_lock("HEL", text));
+    assert!(calculations::looks_like_caps_lock("HELLO W", text));
+    // A correctly cased letter clears the warning
+    assert!(!calculations::looks_like_caps_lock("HELLO w", text));
+    // Wrong letters are ordinary mistakes
+    assert!(!calculations::looks_like_caps_lock("HEK", text));
+    assert!(!calculations::looks_like_caps_lock("hel", text));
+}
+
+#[test]
+fn test_looks_like_caps_lock_with_mixed_case_text() {
+    // Capitals typed where the text has capitals are correct
+    assert!(!calculations::looks_like_caps_lock("NASA", "NASA rocks"));
+    assert!(!calculations::looks_like_caps_lock("NASA RO", "NASA rocks"));
+    assert!(calculations::looks_like_caps_lock("NASA ROC", "NASA rocks"));
+    assert!(calculations::looks_like_caps_lock("HELLO", "Hello"));
+}
+
+#[test]
+fn test_looks_like_caps_lock_with_non_alphabetic_start() {
+    assert!(!calculations::looks_like_caps_lock("123, ", "123, go on"));
+    assert!(!calculations::looks_like_caps_lock("123, GO", "123, go on"));
+    assert!(calculations::looks_like_caps_lock("123, GO O", "123, go on"));
+}
+
+#[test]
+fn test_line_ranges() {
+    let wrapped = calculations::word_wrap("Über die Brücke gehen wir nach Hause", 10);
+    let lines = calculations::line_ranges(&wrapped, 10);
+    assert_eq!(lines, vec![0..10, 10..20, 20..30, 30..40]);
+    assert!(calculations::line_ranges("", 10).is_empty());
+}
+
+#[test]
+fn test_viewport_start() {
+    // Texts that fit never scroll
+    assert_eq!(calculations::viewport_start(4, 10, 8, 1), 0);
+    // The row being typed stays second from the top
+    assert_eq!(calculations::viewport_start(0, 3, 20, 1), 0);
+    assert_eq!(calculations::viewport_start(1, 3, 20, 1), 0);
+    assert_eq!(calculations::viewport_start(5, 3, 20, 1), 4);
+    // The last rows of the text stay at the bottom
+    assert_eq!(calculations::viewport_start(19, 3, 20, 1), 17);
+    // A single visible row has no room for context
+    assert_eq!(calculations::viewport_start(5, 1, 20, 1), 5);
+}
+
+#[test]
+fn test_replay_delays() {
+    use std::time::Duration;
+    let keystroke_times: Vec<Duration> = [1500, 1620, 1800, 9000, 9100].into_iter().map(Duration::from_millis).collect();
+    let delays = calculations::replay_delays(&keystroke_times, Duration::from_secs(3));
+    let millis: Vec<u128> = delays.iter().map(Duration::as_millis).collect();
+    // The first key is played at once, the long pause is cut
+    assert_eq!(millis, vec![0, 120, 180, 3000, 100]);
+    // The delays only depend on the keys, replaying again waits the same
+    assert_eq!(calculations::replay_delays(&keystroke_times, Duration::from_secs(3)), delays);
+    assert!(calculations::replay_delays(&[], Duration::from_secs(3)).is_empty());
+}
+
+#[test]
+fn test_replay_delays_of_keys_out_of_order() {
+    use std::time::Duration;
+    let keystroke_times: Vec<Duration> = [500, 400, 700].into_iter().map(Duration::from_millis).collect();
+    let millis: Vec<u128> = calculations::replay_delays(&keystroke_times, calculations::REPLAY_MAX_DELAY)
+        .iter()
+        .map(Duration::as_millis)
+        .collect();
+    assert_eq!(millis, vec![0, 0, 300]);
+}
@@ -0,0 +1,46 @@
+use chrono::TimeZone;
+use rstype::card::{result_card, RESULT_CARD_WIDTH};
+use rstype::session::SessionStats;
+use unicode_width::UnicodeWidthStr;
+
+fn stats() -> SessionStats {
+    SessionStats {
+        text_id: "42".to_string(),
+        timestamp: chrono::Local.with_ymd_and_hms(2026, 10, 16, 9, 30, 0).unwrap(),
+        wpm: 72.44,
+        accuracy: 97.06,
+        duration: 53.6,
+        wpm_over_time: vec![60.0, 70.0, 80.0, 75.0],
+        ..SessionStats::default()
+    }
+}
+
+#[test]
+fn test_result_card() {
+    let card = result_card(&stats()).join("\n");
+
+    assert_eq!(card, "\
+╭──────────────────────────────────────╮
+│ rstype              2026-10-16 09:30 │
+│                                      │
+│ Speed                       72.4 WPM │
+│ Accuracy                       97.1% │
+│ Time                             54s │
+│ Text                              42 │
+│                                      │
+│ ▆▆▆▆▆▆▆▆▆▇▇▇▇▇▇▇▇▇██████████████████ │
+╰──────────────────────────────────────╯");
+}
+
+#[test]
+fn test_result_card_has_a_fixed_width() {
+    let long_id = SessionStats { text_id: "practice: ".repeat(10), wpm_over_time: vec![], ..stats() };
+
+    let card = result_card(&long_id);
+    // Without a speed graph there is no room kept for it
+    assert_eq!(card.len(), 8);
+    for line in &card {
+        assert_eq!(line.width(), RESULT_CARD_WIDTH, "{}", line);
+    }
+    assert!(card[6].starts_with("│ Text practice: "), "{}", card[6]);
+}
@@ -0,0 +1,99 @@
+use pancurses::Input;
+use rstype::app::AppOptions;
+use rstype::cast::record_cast;
+use rstype::session::SessionStats;
+use std::time::Duration;
+
+fn scripted_keys() -> Vec<(Duration, Input)> {
+    [(2000, 'h'), (2150, 'i'), (2300, ' '), (2420, 'y'), (2500, 'x'), (2650, '\x7f'), (2800, 'o')].into_iter()
+        .map(|(millis, c)| (Duration::from_millis(millis), Input::Character(c)))
+        .collect()
+}
+
+fn scripted_stats() -> SessionStats {
+    SessionStats {
+        text_id: "7".to_string(),
+        timestamp: chrono::DateTime::from_timestamp(1_700_000_000, 0).unwrap().with_timezone(&chrono::Local),
+        wpm: 21.43,
+        words_wpm: 42.86,
+        raw_wpm: 25.71,
+        net_wpm: 21.43,
+        cpm: 107.14,
+        accuracy: 83.33,
+        consistency: 75.0,
+        duration: 2.8,
+        chars_typed: 6,
+        errors: 1,
+        completed: true,
+        ..SessionStats::default()
+    }
+}
+
+#[test]
+fn test_cast_of_scripted_session() {
+    let cast = record_cast(
+        ("hi yo".to_string(), "7".to_string()),
+        &AppOptions::default(),
+        &scripted_stats(),
+        &scripted_keys(),
+        (12, 60),
+    ).unwrap();
+
+    assert_eq!(cast, include_str!("data/scripted_session.cast"));
+}
+
+#[test]
+fn test_cast_is_timed_like_the_replay() {
+    let cast = record_cast(
+        ("hi yo".to_string(), "7".to_string()),
+        &AppOptions::default(),
+        &scripted_stats(),
+        &scripted_keys(),
+        (12, 60),
+    ).unwrap();
+    let mut lines = cast.lines();
+
+    let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["version"], 2);
+    assert_eq!(header["width"], 60);
+    assert_eq!(header["height"], 12);
+    assert_eq!(header["timestamp"], 1_700_000_000);
+
+    // The screen before the first key, then one frame per key from the first one
+    let times: Vec<f64> = lines
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .inspect(|event| assert_eq!(event[1], "o"))
+        .map(|event| event[0].as_f64().unwrap())
+        .collect();
+    assert_eq!(times, vec![0.0, 0.0, 0.15, 0.3, 0.42, 0.5, 0.65, 0.8]);
+}
+
+#[test]
+fn test_cast_cuts_long_pauses() {
+    let mut keys = scripted_keys();
+    // A coffee break before the last key
+    keys.last_mut().unwrap().0 = Duration::from_secs(600);
+    let cast = record_cast(
+        ("hi yo".to_string(), "7".to_string()),
+        &AppOptions::default(),
+        &scripted_stats(),
+        &keys,
+        (12, 60),
+    ).unwrap();
+
+    let last_event: serde_json::Value = serde_json::from_str(cast.lines().last().unwrap()).unwrap();
+    assert_eq!(last_event[0].as_f64().unwrap(), 0.65 + rstype::calculations::REPLAY_MAX_DELAY.as_secs_f64());
+}
+
+#[test]
+fn test_cast_of_too_small_window_fails() {
+    let result = record_cast(
+        ("hi yo".to_string(), "7".to_string()),
+        &AppOptions::default(),
+        &scripted_stats(),
+        &scripted_keys(),
+        (5, 40),
+    );
+    assert!(matches!(result, Err(rstype::AppError::WindowTooSmallError)), "{:?}", result.err());
+}
+
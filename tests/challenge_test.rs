@@ -0,0 +1,76 @@
+use rstype::challenge::{
+    comparison_lines, load_challenge, save_challenge, Challenge, ChallengeError, ChallengeResult, CHALLENGE_VERSION,
+};
+use rstype::session::SessionStats;
+use uuid::Uuid;
+
+fn stats(wpm: f64, accuracy: f64, duration: f64) -> SessionStats {
+    SessionStats { text_id: "42".to_string(), wpm, accuracy, duration, completed: true, ..SessionStats::default() }
+}
+
+#[test]
+fn test_save_and_load_challenge() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let challenge = Challenge::new("42", "héllo wörld", Some(7), &stats(72.4, 97.1, 54.0));
+
+    save_challenge(&path, &challenge).unwrap();
+    let loaded = load_challenge(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded, challenge);
+    assert_eq!(loaded.version, CHALLENGE_VERSION);
+    assert_eq!(loaded.challenger().wpm, 72.4);
+}
+
+#[test]
+fn test_other_version_is_refused() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let mut challenge = Challenge::new("42", "hello", None, &stats(72.4, 97.1, 54.0));
+    challenge.version = CHALLENGE_VERSION + 1;
+    save_challenge(&path, &challenge).unwrap();
+
+    let result = load_challenge(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        matches!(&result, Err(ChallengeError::UnsupportedVersion(error_path, version)) if *error_path == path && *version == CHALLENGE_VERSION + 1),
+        "{:?}", result,
+    );
+}
+
+#[test]
+fn test_changed_or_corrupt_challenge_is_refused() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let mut challenge = Challenge::new("42", "hello", None, &stats(72.4, 97.1, 54.0));
+    challenge.text = "jello".to_string();
+    save_challenge(&path, &challenge).unwrap();
+    let changed = load_challenge(&path);
+
+    challenge = Challenge { results: vec![], ..Challenge::new("42", "hello", None, &stats(72.4, 97.1, 54.0)) };
+    save_challenge(&path, &challenge).unwrap();
+    let empty = load_challenge(&path);
+
+    std::fs::write(&path, "{\"version\": 1, \"text\": ").unwrap();
+    let corrupt = load_challenge(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(&changed, Err(ChallengeError::TextMismatch(error_path)) if *error_path == path), "{:?}", changed);
+    assert!(matches!(&empty, Err(ChallengeError::NoResults(_))), "{:?}", empty);
+    let error = corrupt.unwrap_err();
+    assert!(matches!(&error, ChallengeError::JsonError(error_path, _) if *error_path == path));
+    assert!(rstype::AppError::from(error).to_string().contains(&path));
+}
+
+#[test]
+fn test_comparison_lines() {
+    let challenger = ChallengeResult::from_stats(&stats(72.4, 97.06, 53.6));
+    let player = ChallengeResult::from_stats(&stats(65.25, 95.0, 60.2));
+
+    assert_eq!(comparison_lines(&challenger, &player), vec![
+        " Challenge        Them        You",
+        " WPM             72.40      65.25  -7.15",
+        " Accuracy        97.1%      95.0%",
+        " Time              54s        60s",
+    ]);
+}
@@ -0,0 +1,151 @@
+use rstype::config;
+use rstype::share::ShareTarget;
+use uuid::Uuid;
+
+#[test]
+fn test_missing_config_is_default() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    assert_eq!(config::load_config_from_file(&config_path).unwrap(), config::Config::default());
+}
+
+#[test]
+fn test_save_and_load_goal() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "# my settings\nunknown = 1\n").unwrap();
+
+    config::save_config_value_to_file(&config_path, "goal_wpm", "80").unwrap();
+    assert_eq!(config::load_config_from_file(&config_path).unwrap().goal_wpm, Some(80.0));
+
+    // Saving again replaces the value and keeps the other lines
+    config::save_config_value_to_file(&config_path, "goal_wpm", "72.5").unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&config_path).unwrap(),
+        "# my settings\nunknown = 1\ngoal_wpm = 72.5\n",
+    );
+    assert_eq!(config::load_config_from_file(&config_path).unwrap().goal_wpm, Some(72.5));
+
+    config::save_config_value_to_file(&config_path, "goal_wpm", "0").unwrap();
+    assert_eq!(config::load_config_from_file(&config_path).unwrap().goal_wpm, None);
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_invalid_config_value() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "goal_wpm = fast\n").unwrap();
+
+    assert!(matches!(
+        config::load_config_from_file(&config_path),
+        Err(config::ConfigError::InvalidValue { key, value }) if key == "goal_wpm" && value == "fast"
+    ));
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_load_theme_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "theme = ocean\ntheme.error = white, 196\n").unwrap();
+
+    let config = config::load_config_from_file(&config_path).unwrap();
+    assert_eq!(config.theme.as_deref(), Some("ocean"));
+    assert_eq!(config.theme_colors, vec![("error".to_string(), "white, 196".to_string())]);
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_load_webhook_url_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "webhook_url = http://localhost:8080/results?user=me\n").unwrap();
+
+    let config = config::load_config_from_file(&config_path).unwrap();
+    assert_eq!(config.webhook_url.as_deref(), Some("http://localhost:8080/results?user=me"));
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_load_no_history_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    assert!(!config::load_config_from_file(&config_path).unwrap().no_history);
+
+    std::fs::write(&config_path, "no_history = true\n").unwrap();
+    assert!(config::load_config_from_file(&config_path).unwrap().no_history);
+
+    std::fs::write(&config_path, "no_history = sometimes\n").unwrap();
+    assert!(matches!(
+        config::load_config_from_file(&config_path),
+        Err(config::ConfigError::InvalidValue { key, .. }) if key == "no_history"
+    ));
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_load_history_file_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "history_file = /tmp/rstype history.csv\n").unwrap();
+    let config = config::load_config_from_file(&config_path).unwrap();
+    assert_eq!(config.history_file, Some(std::path::PathBuf::from("/tmp/rstype history.csv")));
+
+    // An empty value leaves the history where it would be without the key
+    std::fs::write(&config_path, "history_file =\n").unwrap();
+    assert_eq!(config::load_config_from_file(&config_path).unwrap().history_file, None);
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_profile_config_replaces_main_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    let profile_directory = std::path::PathBuf::from(format!("tests/{}", Uuid::new_v4()));
+    let profile_config_path = profile_directory.join("config");
+    std::fs::write(&config_path, "goal_wpm = 60\ntheme = dracula\nhistory_file = main.csv\n").unwrap();
+
+    // The profile is made with its first setting
+    config::save_config_value_to_file(&profile_config_path, "goal_wpm", "80").unwrap();
+    let config = config::load_profile_config_from_file(&config_path, Some(profile_config_path.clone())).unwrap();
+    assert_eq!(config.goal_wpm, Some(80.0));
+    assert_eq!(config.theme.as_deref(), Some("dracula"));
+    // The history of the main config belongs to the default profile
+    assert_eq!(config.history_file, None);
+
+    let config = config::load_profile_config_from_file(&config_path, None).unwrap();
+    assert_eq!(config.goal_wpm, Some(60.0));
+    assert_eq!(config.history_file, Some(std::path::PathBuf::from("main.csv")));
+
+    std::fs::remove_file(&config_path).unwrap();
+    std::fs::remove_dir_all(profile_directory).unwrap()
+}
+
+#[test]
+fn test_load_share_templates_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(&config_path, "share_template = {wpm} WPM = {accuracy}%\\n#rstype\ncopy_template = {result}\n").unwrap();
+
+    let config = config::load_config_from_file(&config_path).unwrap();
+    assert_eq!(config.share_template.as_deref(), Some("{wpm} WPM = {accuracy}%\n#rstype"));
+    assert_eq!(config.copy_template.as_deref(), Some("{result}"));
+
+    std::fs::remove_file(&config_path).unwrap()
+}
+
+#[test]
+fn test_load_share_targets_from_config() {
+    let config_path = format!("tests/{}.conf", Uuid::new_v4());
+    std::fs::write(
+        &config_path,
+        "share_target.Mastodon = https://mastodon.social/share?text={text}\n\
+         share_target.Bluesky = https://bsky.app/intent/compose?text={text}\n",
+    ).unwrap();
+
+    let config = config::load_config_from_file(&config_path).unwrap();
+    assert_eq!(config.share_targets, vec![
+        ShareTarget::new("Mastodon", "https://mastodon.social/share?text={text}"),
+        ShareTarget::new("Bluesky", "https://bsky.app/intent/compose?text={text}"),
+    ]);
+
+    std::fs::remove_file(&config_path).unwrap()
+}
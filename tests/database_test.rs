@@ -1,85 +1,44 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use rstype::database;
 use uuid::Uuid;
 
 #[test]
 fn test_fetching_text_from_db_with_different_difficulties() {
-    let (connection, database_path) = prepare_connection_with_table();
-
-    let value = "Hello, world!";
+    let database_path = prepare_database_with_texts();
 
-    for _ in 0..6000 {
-        connection
-            .execute(format!("INSERT INTO data (txt) VALUES ('{}');", value))
-            .unwrap();
-    }
-
-    {
-        let difficulty_level = 2;
-        let result_for_difficulty_2 =
-            database::load_text_from_database_based_on_difficulty(difficulty_level, &database_path)
-                .unwrap();
-        assert_eq!(result_for_difficulty_2.0, value);
-        let id = result_for_difficulty_2.1.parse::<u32>().unwrap();
-        let upper_limit = difficulty_level * 1200;
-        let lower_limit = upper_limit - 1200 + 1;
-        assert!((lower_limit..=upper_limit).contains(&id));
-    }
-    {
-        let difficulty_level = 3;
-        let result_for_difficulty_2 =
-            database::load_text_from_database_based_on_difficulty(difficulty_level, &database_path)
-                .unwrap();
-        assert_eq!(result_for_difficulty_2.0, value);
-        let id = result_for_difficulty_2.1.parse::<u32>().unwrap();
-        let upper_limit = difficulty_level * 1200;
-        let lower_limit = upper_limit - 1200 + 1;
-        assert!((lower_limit..=upper_limit).contains(&id));
+    // Each difficulty has its own range of 1200 texts
+    let expected_ids = [(1, 161), (2, 1361), (3, 2561), (4, 3761), (5, 4961)];
+    for (difficulty, expected_id) in expected_ids {
+        let mut rng = StdRng::seed_from_u64(42);
+        let (text, text_id) = database::load_text_from_database_based_on_difficulty_with_rng(
+            difficulty,
+            &database_path,
+            &mut rng,
+        ).unwrap();
+        assert_eq!(text_id, expected_id.to_string());
+        assert_eq!(text, format!("text {}", expected_id));
     }
-    {
-        let difficulty_level = 4;
-        let result_for_difficulty_2 =
-            database::load_text_from_database_based_on_difficulty(difficulty_level, &database_path)
-                .unwrap();
-        assert_eq!(result_for_difficulty_2.0, value);
-        let id = result_for_difficulty_2.1.parse::<u32>().unwrap();
-        let upper_limit = difficulty_level * 1200;
-        let lower_limit = upper_limit - 1200 + 1;
-        assert!((lower_limit..=upper_limit).contains(&id));
-    }
-    {
-        let difficulty_level = 5;
-        let result_for_difficulty_2 =
-            database::load_text_from_database_based_on_difficulty(difficulty_level, &database_path)
-                .unwrap();
-        assert_eq!(result_for_difficulty_2.0, value);
-        let id = result_for_difficulty_2.1.parse::<u32>().unwrap();
-        let upper_limit = difficulty_level * 1200;
-        let lower_limit = upper_limit - 1200 + 1;
-        assert!((lower_limit..=upper_limit).contains(&id));
-    }
-
-    std::fs::remove_file(&database_path).unwrap()
 }
 
 #[test]
-fn test_fetching_text_from_db_based_on_difficulty() {
-    let (connection, database_path) = prepare_connection_with_table();
-
-    let value = "Hello, world!";
+fn test_fetching_text_from_db_with_random_difficulty() {
+    let database_path = prepare_database_with_texts();
 
-    for _ in 0..6000 {
-        connection
-            .execute(format!("INSERT INTO data (txt) VALUES ('{}');", value))
-            .unwrap();
-    }
+    let mut rng = StdRng::seed_from_u64(42);
+    let (text, text_id) =
+        database::load_text_from_database_with_random_difficulty_with_rng(&database_path, &mut rng).unwrap();
+    assert_eq!(text_id, "632");
+    assert_eq!(text, "text 632");
+}
 
-    let difficulty = 1;
-    let result =
-        database::load_text_from_database_based_on_difficulty(difficulty, &database_path).unwrap();
-    assert_eq!(result.0, value);
-    let id = result.1.parse::<u32>().unwrap();
-    assert!((1..=1200).contains(&id));
-    std::fs::remove_file(&database_path).unwrap()
+#[test]
+fn test_difficulty_out_of_range() {
+    let mut rng = StdRng::seed_from_u64(42);
+    assert!(matches!(
+        database::load_text_from_database_based_on_difficulty_with_rng(6, "data.db", &mut rng),
+        Err(database::DatabaseError::DifficultyOutOfRangeError(6))
+    ));
 }
 
 #[test]
@@ -94,14 +53,93 @@ fn test_fetching_text_from_db() {
     let serial_id = 1;
     let result = database::fetch_text_with_id(serial_id, &database_path).unwrap();
     assert_eq!(result, value);
-    std::fs::remove_file(database_path).unwrap()
 }
 
-fn prepare_connection_with_table() -> (sqlite::Connection, String) {
-    let database_path = format!("tests/{}.db", Uuid::new_v4());
-    let connection = sqlite::open(&database_path).unwrap();
+/// Path of a database made by a test, the file is removed when it goes out of scope,
+/// even when an assertion failed before the end of the test
+struct TempDatabase(String);
+
+impl std::ops::Deref for TempDatabase {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn prepare_connection_with_table() -> (sqlite::Connection, TempDatabase) {
+    let database_path = TempDatabase(format!("tests/{}.db", Uuid::new_v4()));
+    let connection = sqlite::open(&*database_path).unwrap();
     connection
         .execute("CREATE TABLE data (id INTEGER PRIMARY KEY, txt TEXT);")
         .unwrap();
     (connection, database_path)
 }
+
+#[test]
+fn test_daily_text_id_is_deterministic() {
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let next_day = chrono::NaiveDate::from_ymd_opt(2024, 6, 2).unwrap();
+
+    let id = database::daily_text_id(date);
+    assert_eq!(id, database::daily_text_id(date));
+    assert!((1..=6000).contains(&id));
+    assert_ne!(id, database::daily_text_id(next_day));
+    assert_eq!(database::daily_text_key(date), "daily-2024-06-01");
+}
+
+#[test]
+fn test_difficulty_of_text_id() {
+    assert_eq!(database::difficulty_of_text_id(1), Some(1));
+    assert_eq!(database::difficulty_of_text_id(1200), Some(1));
+    assert_eq!(database::difficulty_of_text_id(1201), Some(2));
+    assert_eq!(database::difficulty_of_text_id(6000), Some(5));
+    assert_eq!(database::difficulty_of_text_id(0), None);
+    assert_eq!(database::difficulty_of_text_id(6001), None);
+}
+
+#[test]
+fn test_loading_daily_text() {
+    let database_path = prepare_database_with_texts();
+
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    let (text, text_id) = database::load_daily_text(date, &database_path).unwrap();
+    assert_eq!(text, format!("text {}", database::daily_text_id(date)));
+    assert_eq!(text_id, "daily-2024-06-01");
+}
+
+#[test]
+fn test_same_seed_selects_same_text() {
+    let database_path = prepare_database_with_texts();
+
+    let select = |seed: u64| {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (
+            database::load_text_from_database_based_on_difficulty_with_rng(3, &database_path, &mut rng)
+                .unwrap(),
+            database::load_text_from_database_with_random_difficulty_with_rng(&database_path, &mut rng)
+                .unwrap(),
+        )
+    };
+    assert_eq!(select(42), select(42));
+    assert_eq!(select(7), select(7));
+}
+
+/// Database with the 6000 texts of data.db, each text naming its id
+fn prepare_database_with_texts() -> TempDatabase {
+    let (connection, database_path) = prepare_connection_with_table();
+    let values = (1..=6000)
+        .map(|id| format!("('text {}')", id))
+        .collect::<Vec<String>>()
+        .join(", ");
+    connection
+        .execute(format!("INSERT INTO data (txt) VALUES {};", values))
+        .unwrap();
+    database_path
+}
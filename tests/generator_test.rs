@@ -0,0 +1,102 @@
+use rstype::calculations::{char_positions, word_wrap};
+use rstype::generator::{self, Drill};
+
+#[test]
+fn test_numbers_drill() {
+    let (text, text_id) = generator::generate_drill(Drill::Numbers, 40, generator::DEFAULT_MAX_WORD_LENGTH, 7);
+    assert_eq!(text_id, "drill:numbers:7");
+    assert_eq!(text.split(' ').count(), 40);
+    assert!(text.split(' ').all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_digit())));
+}
+
+#[test]
+fn test_symbols_drill() {
+    let (text, text_id) = generator::generate_drill(Drill::Symbols, 25, generator::DEFAULT_MAX_WORD_LENGTH, 7);
+    assert_eq!(text_id, "drill:symbols:7");
+    assert_eq!(text.split(' ').count(), 25);
+    assert!(text.split(' ').all(|word| !word.is_empty() && word.chars().all(|c| c.is_ascii_punctuation())));
+}
+
+#[test]
+fn test_drill_is_reproducible() {
+    let first = generator::generate_drill(Drill::Numbers, 40, 4, 99);
+    let second = generator::generate_drill(Drill::Numbers, 40, 4, 99);
+    assert_eq!(first, second);
+    assert_ne!(first.0, generator::generate_drill(Drill::Numbers, 40, 4, 100).0);
+}
+
+#[test]
+fn test_drill_words_survive_word_wrap() {
+    let max_word_length = 2;
+    let (text, _) = generator::generate_drill(Drill::Numbers, 200, max_word_length, 3);
+    assert!(text.split(' ').all(|word| word.chars().count() <= max_word_length));
+
+    // No word is split over two lines, even on a very narrow screen
+    let width = 5;
+    let wrapped = word_wrap(&text, width);
+    let positions = char_positions(&wrapped, width);
+    let mut index = 0;
+    for word in wrapped.split(' ') {
+        let length = word.chars().count();
+        if length > 0 {
+            assert_eq!(positions[index].0, positions[index + length - 1].0);
+        }
+        index += length + 1;
+    }
+}
+
+#[test]
+fn test_key_drill_uses_only_given_keys() {
+    let (text, text_id) = generator::generate_key_drill("asdfjkl;a d", 60, generator::DEFAULT_MAX_WORD_LENGTH, 5).unwrap();
+    assert_eq!(text_id, "drill:keys:asdfjkl;:5");
+    assert_eq!(text.split(' ').count(), 60);
+    for word in text.split(' ') {
+        assert!((3..=7).contains(&word.chars().count()), "{} has a wrong length", word);
+        assert!(word.chars().all(|c| "asdfjkl;".contains(c)), "{} has a key outside the set", word);
+    }
+}
+
+#[test]
+fn test_key_drill_respects_max_word_length() {
+    let (text, _) = generator::generate_key_drill("qwer", 60, 4, 5).unwrap();
+    assert!(text.split(' ').all(|word| (3..=4).contains(&word.chars().count())));
+}
+
+#[test]
+fn test_key_drill_without_keys() {
+    assert_eq!(generator::generate_key_drill("", 10, 7, 1), Err(generator::GeneratorError::EmptyKeySet));
+    assert_eq!(generator::generate_key_drill("  \t", 10, 7, 1), Err(generator::GeneratorError::EmptyKeySet));
+}
+
+#[test]
+fn test_key_drill_can_be_typed() {
+    let (text, _) = generator::generate_key_drill("fj", 3, 7, 11).unwrap();
+    let mut session = rstype::session::TypingSession::new(&text);
+    for c in text.chars() {
+        session.press(c);
+    }
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 3);
+}
+
+#[test]
+fn test_mistakes_drill_prefers_most_mistyped_words() {
+    let word_errors = vec![("rhythm".to_string(), 9), ("fox".to_string(), 1), ("the".to_string(), 0)];
+    let (text, text_id) = generator::generate_mistakes_drill(&word_errors, 200, 3).unwrap();
+    assert_eq!(text_id, "drill:mistakes:3");
+
+    let words: Vec<&str> = text.split(' ').collect();
+    assert_eq!(words.len(), 200);
+    assert!(!words.contains(&"the"));
+    let rhythm_count = words.iter().filter(|word| **word == "rhythm").count();
+    let fox_count = words.iter().filter(|word| **word == "fox").count();
+    assert_eq!(rhythm_count + fox_count, 200);
+    assert!(rhythm_count > fox_count * 3);
+}
+
+#[test]
+fn test_mistakes_drill_without_mistakes() {
+    assert_eq!(generator::generate_mistakes_drill(&[], 10, 1), Err(generator::GeneratorError::NoWords));
+    let word_errors = vec![("the".to_string(), 0)];
+    assert_eq!(generator::generate_mistakes_drill(&word_errors, 10, 1), Err(generator::GeneratorError::NoWords));
+}
@@ -0,0 +1,61 @@
+use rstype::app::Color;
+use rstype::heatmap;
+use rstype::layout;
+use std::collections::HashMap;
+
+#[test]
+fn test_key_errors_fold_shifted_characters() {
+    let text = "Hello, World!";
+    // Mistakes on 'H' and 'W' count toward the 'h' and 'w' keys
+    let mistyped_keys = HashMap::from([(0, 2), (2, 1), (3, 1), (5, 1), (7, 3), (12, 1)]);
+    let errors = heatmap::key_errors(text, &mistyped_keys);
+    assert_eq!(errors.get(&'h'), Some(&2));
+    assert_eq!(errors.get(&'l'), Some(&2));
+    assert_eq!(errors.get(&','), Some(&1));
+    assert_eq!(errors.get(&'w'), Some(&3));
+    // '!' is typed with the '1' key
+    assert_eq!(errors.get(&'1'), Some(&1));
+    assert_eq!(errors.get(&'H'), None);
+}
+
+#[test]
+fn test_key_errors_ignore_characters_off_the_keyboard() {
+    let mistyped_keys = HashMap::from([(0, 1), (1, 1), (2, 4)]);
+    let errors = heatmap::key_errors("é ü", &mistyped_keys);
+    assert!(errors.is_empty());
+    // Positions past the end of the text are ignored too
+    assert!(heatmap::key_errors("a", &HashMap::from([(5, 1)])).is_empty());
+}
+
+#[test]
+fn test_heat_color() {
+    assert_eq!(heatmap::heat_color(0, 9), None);
+    assert_eq!(heatmap::heat_color(3, 9), Some(Color::Yellow));
+    assert_eq!(heatmap::heat_color(6, 9), Some(Color::Magenta));
+    assert_eq!(heatmap::heat_color(9, 9), Some(Color::Red));
+}
+
+#[test]
+fn test_heatmap_rows() {
+    let errors = HashMap::from([('a', 4), ('j', 1)]);
+    let rows = heatmap::heatmap_rows(&errors);
+    assert_eq!(rows.len(), 4);
+    assert_eq!(rows[2][0], ('a', Some(Color::Red)));
+    assert_eq!(rows[2][6], ('j', Some(Color::Yellow)));
+    assert_eq!(rows[0][0], ('`', None));
+    let keys: String = rows.iter().flatten().map(|(key, _)| *key).collect();
+    assert_eq!(keys, heatmap::KEYBOARD_ROWS.concat());
+    assert!(rows.iter().enumerate().all(|(index, row)| {
+        index + row.len() * heatmap::HEATMAP_KEY_WIDTH <= heatmap::HEATMAP_WIDTH
+    }));
+}
+
+#[test]
+fn test_base_key() {
+    assert_eq!(layout::base_key('A'), Some('a'));
+    assert_eq!(layout::base_key('a'), Some('a'));
+    assert_eq!(layout::base_key('?'), Some('/'));
+    assert_eq!(layout::base_key('"'), Some('\''));
+    assert_eq!(layout::base_key(' '), None);
+    assert_eq!(layout::base_key('ß'), None);
+}
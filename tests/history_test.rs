@@ -0,0 +1,972 @@
+use chrono::TimeZone;
+use rstype::history;
+use rstype::session::{SessionStats, TextSource};
+use uuid::Uuid;
+use std::time::Duration;
+
+#[test]
+fn test_save_and_read_history() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+
+    history::save_history_to_file(&history_path, &entry("42", 61.5, 97.25, 320.0)).unwrap();
+    history::save_history_to_file(&history_path, &entry("43", 70.0, 100.0, 350.5)).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].id, "42");
+    assert_eq!(records[0].wpm, Some(61.5));
+    assert_eq!(records[0].accuracy, Some(97.25));
+    assert_eq!(records[0].cpm, Some(320.0));
+    assert_eq!(records[0].consistency, Some(90.0));
+    assert_eq!(records[0].time_limit, None);
+    assert_eq!(records[0].skipped, Some(0));
+    assert!(!records[0].suspect);
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::Last(1), &history::TextFilter::All).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, "43");
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_history_records_of_one_text() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    for (text_id, wpm) in [("42", 50.0), ("tests/my.txt", 55.0), ("420", 60.0), ("42", 65.0), ("tests/other.txt", 70.0)] {
+        history::save_history_to_file(&history_path, &entry(text_id, wpm, 98.0, 300.0)).unwrap();
+    }
+    let read = |number_of_records, filter: &history::TextFilter| -> Vec<(String, f64)> {
+        history::get_history_records_from_file(&history_path, number_of_records, filter).unwrap()
+            .into_iter()
+            .map(|record| (record.id, record.wpm.unwrap()))
+            .collect()
+    };
+
+    // The id matches exactly, 42 isn't 420
+    let exact = history::TextFilter::Exact("42".to_string());
+    assert_eq!(read(history::NumberOfRecords::All, &exact), vec![
+        ("42".to_string(), 50.0),
+        ("42".to_string(), 65.0),
+    ]);
+    // The last records are counted among the matching ones
+    let files = history::TextFilter::Contains("tests/".to_string());
+    assert_eq!(read(history::NumberOfRecords::Last(1), &files), vec![
+        ("tests/other.txt".to_string(), 70.0),
+    ]);
+    assert_eq!(read(history::NumberOfRecords::All, &history::TextFilter::Exact("7".to_string())), vec![]);
+    assert_eq!(read(history::NumberOfRecords::All, &history::TextFilter::All).len(), 5);
+
+    assert_eq!(exact.to_string(), "text 42");
+    assert_eq!(files.to_string(), "texts with \"tests/\" in their id");
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_history_records_of_one_source() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    // Records saved before the source column get a source guessed from their id
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\ntests/my.txt,50.00,2024-01-02,10:00:00,97.00\n",
+    ).unwrap();
+    history::save_history_to_file(&history_path, &SessionStats { source: TextSource::Drill, ..entry("drill:th", 40.0, 99.0, 200.0) }).unwrap();
+    history::save_history_to_file(&history_path, &SessionStats { source: TextSource::File, ..entry("notes.txt", 45.0, 96.0, 225.0) }).unwrap();
+    let ids = |source| -> Vec<String> {
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::Source(source))
+            .unwrap()
+            .into_iter()
+            .map(|record| record.id)
+            .collect()
+    };
+
+    assert_eq!(ids(TextSource::Database), vec!["12"]);
+    assert_eq!(ids(TextSource::File), vec!["tests/my.txt", "notes.txt"]);
+    assert_eq!(ids(TextSource::Drill), vec!["drill:th"]);
+    assert_eq!(history::TextFilter::Source(TextSource::File).to_string(), "texts from source file");
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_select_history_records_in_both_orders() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    for text_id in ["1", "2", "1", "3", "1"] {
+        history::save_history_to_file(&history_path, &entry(text_id, 50.0, 98.0, 250.0)).unwrap();
+    }
+    let numbers = |number_of_records, filter: &history::TextFilter, newest_first| -> Vec<usize> {
+        history::select_history_records_from_file(&history_path, number_of_records, filter, newest_first)
+            .unwrap()
+            .iter()
+            .map(|(number, _)| *number)
+            .collect()
+    };
+    let all = history::TextFilter::All;
+    let first_text = history::TextFilter::Exact("1".to_string());
+
+    assert_eq!(numbers(history::NumberOfRecords::All, &all, false), vec![1, 2, 3, 4, 5]);
+    assert_eq!(numbers(history::NumberOfRecords::All, &all, true), vec![5, 4, 3, 2, 1]);
+    // The last records are picked before they are ordered
+    assert_eq!(numbers(history::NumberOfRecords::Last(2), &all, false), vec![4, 5]);
+    assert_eq!(numbers(history::NumberOfRecords::Last(2), &all, true), vec![5, 4]);
+    // Records keep their number in the whole history
+    assert_eq!(numbers(history::NumberOfRecords::Last(2), &first_text, false), vec![3, 5]);
+    assert_eq!(numbers(history::NumberOfRecords::Last(2), &first_text, true), vec![5, 3]);
+    assert_eq!(numbers(history::NumberOfRecords::Last(10), &first_text, true), vec![5, 3, 1]);
+    assert_eq!(numbers(history::NumberOfRecords::Last(0), &first_text, true), Vec::<usize>::new());
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_history_without_cpm_column_is_migrated() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\n",
+    ).unwrap();
+
+    // Old records are readable before the file is migrated
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records[0].wpm, Some(55.0));
+    assert_eq!(records[0].cpm, None);
+
+    let stats = SessionStats { duration: 42.5, chars_typed: 230, errors: 3, difficulty: Some(4), ..entry("13", 60.0, 99.0, 300.0) };
+    history::save_history_to_file(&history_path, &stats).unwrap();
+
+    let content = std::fs::read_to_string(&history_path).unwrap();
+    let mut lines = content.lines();
+    assert_eq!(
+        lines.next(),
+        Some("ID,WPM,DATE,TIME,ACCURACY,CPM,CONSISTENCY,TIME_LIMIT,SKIPPED,SUSPECT,BEST,DURATION_S,CHARS,ERRORS,DIFFICULTY,SOURCE,RETRY,HASH"),
+    );
+    assert_eq!(lines.next(), Some("12,55.00,2024-01-01,10:00:00,98.00,,,,,,,,,,,,,"));
+    assert!(lines.next().unwrap().ends_with(",42.50,230,3,4,db,,"));
+
+    // Both the old and the new record parse, the old one without the new columns
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records[0].wpm, Some(55.0));
+    assert_eq!((records[0].elapsed, records[0].chars, records[0].errors, records[0].difficulty), (None, None, None, None));
+    assert_eq!(records[1].wpm, Some(60.0));
+    assert_eq!((records[1].elapsed, records[1].chars, records[1].errors, records[1].difficulty), (Some(42.5), Some(230), Some(3), Some(4)));
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_history_without_errors_column_is_migrated() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY,CPM,CONSISTENCY,DURATION,SKIPPED,SUSPECT,BEST,ELAPSED,CHARS\n\
+        12,55.00,2024-01-01,10:00:00,98.00,275.00,90.00,,0,no,yes,30.00,140\n",
+    ).unwrap();
+    history::save_history_to_file(&history_path, &SessionStats { errors: 2, ..entry("13", 60.0, 99.0, 300.0) }).unwrap();
+
+    let content = std::fs::read_to_string(&history_path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines[0], history::HISTORY_HEADER.join(","));
+    assert_eq!(lines[1], "12,55.00,2024-01-01,10:00:00,98.00,275.00,90.00,,0,no,yes,30.00,140,,,,,");
+    assert!(lines[2].ends_with(",2,,db,,"), "{}", lines[2]);
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_prune_history_keeps_the_newest_records() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n\
+        1,50.00,2024-01-01,10:00:00,98.00\n\
+        2,55.00,2024-01-02,10:00:00,97.00\n\
+        3,60.00,2024-01-03,10:00:00,99.00\n",
+    ).unwrap();
+
+    assert_eq!(history::prune_history_file(&history_path, 2).unwrap(), 1);
+    // Old files get the current header, and no temporary file is left behind
+    let header = history::HISTORY_HEADER.join(",");
+    assert_eq!(
+        std::fs::read_to_string(&history_path).unwrap(),
+        format!("{}\n2,55.00,2024-01-02,10:00:00,97.00,,,,,,,,,,,,,\n3,60.00,2024-01-03,10:00:00,99.00,,,,,,,,,,,,,\n", header),
+    );
+    assert!(!std::path::Path::new(&format!("{}.tmp", history_path)).exists());
+
+    // Keeping more records than there are removes nothing
+    assert_eq!(history::prune_history_file(&history_path, 100).unwrap(), 0);
+    assert_eq!(history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap().len(), 2);
+
+    assert_eq!(history::clear_history_file(&history_path).unwrap(), 2);
+    assert_eq!(std::fs::read_to_string(&history_path).unwrap(), format!("{}\n", header));
+    assert_eq!(history::clear_history_file(&history_path).unwrap(), 0);
+
+    // New results are saved after the header left by clearing
+    history::save_history_to_file(&history_path, &entry("4", 65.0, 98.0, 300.0)).unwrap();
+    let records = history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].id, "4");
+
+    std::fs::remove_file(&history_path).unwrap();
+    assert_eq!(history::prune_history_file(&history_path, 1).unwrap(), 0);
+}
+
+#[test]
+fn test_export_history_matches_golden_files() {
+    let records = history::select_history_records_from_file(
+        "tests/data/export_history.csv",
+        history::NumberOfRecords::All,
+        &history::TextFilter::All,
+        false,
+    ).unwrap();
+
+    for (format, golden_path) in [
+        ("json", "tests/data/export_history.json"),
+        ("markdown", "tests/data/export_history.md"),
+    ] {
+        let path = format!("tests/{}.{}", Uuid::new_v4(), format);
+        history::export_history(&records, format.parse().unwrap(), &path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), std::fs::read_to_string(golden_path).unwrap(), "{}", format);
+        assert!(!std::path::Path::new(&format!("{}.tmp", path)).exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // Filtered records keep their number in the whole history
+    let filter = history::TextFilter::Exact("12".to_string());
+    let records =
+        history::select_history_records_from_file("tests/data/export_history.csv", history::NumberOfRecords::All, &filter, true).unwrap();
+    let table = history::history_records_to_markdown(&records);
+    let rows: Vec<&str> = table.lines().skip(2).map(|row| &row[..6]).collect();
+    assert_eq!(rows, vec!["| 3 | ", "| 1 | "]);
+
+    assert!(matches!("csv".parse::<history::ExportFormat>(), Err(history::HistoryError::UnknownExportFormat(_))));
+}
+
+#[test]
+fn test_history_table_matches_golden_file() {
+    let records = history::select_history_records_from_file(
+        "tests/data/export_history.csv",
+        history::NumberOfRecords::All,
+        &history::TextFilter::All,
+        false,
+    ).unwrap();
+
+    let table = history::history_table_lines(&records).join("\n") + "\n";
+    assert_eq!(table, std::fs::read_to_string("tests/data/history_table.txt").unwrap());
+}
+
+#[test]
+fn test_history_table_cuts_long_ids() {
+    let record = |id: &str| history::HistoryRecord { id: id.to_string(), wpm: Some(50.0), ..history::HistoryRecord::default() };
+    let long_path = format!("{}/chapter 1.txt", "texts/".repeat(10));
+    let lines = history::history_table_lines(&[(1, record(&long_path)), (10, record("練習"))]);
+
+    // The end of a path names the file, the ellipsis takes the place of the start
+    let id = lines[1][4..].split("  ").next().unwrap();
+    assert_eq!(id.chars().count(), history::MAX_ID_WIDTH);
+    assert_eq!(id, format!("…{}", &long_path[long_path.len() - history::MAX_ID_WIDTH + 1..]));
+    // Numbers are aligned to the right, wide characters take two columns
+    assert!(lines[1].starts_with(" 1  …"), "{}", lines[1]);
+    assert!(lines[2].starts_with(&format!("10  練習{}  50.00", " ".repeat(history::MAX_ID_WIDTH - 4))), "{}", lines[2]);
+    assert!(lines.iter().all(|line| !line.ends_with(' ')));
+}
+
+#[test]
+fn test_history_records_as_json() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\n",
+    ).unwrap();
+    let stats = SessionStats { time_limit: Some(60), suspect: true, ..entry("13", 60.0, 99.0, 300.0) };
+    history::save_history_to_file(&history_path, &stats).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&history::history_records_to_json(&records).unwrap()).unwrap();
+    let json = json.as_array().unwrap();
+    assert_eq!(json.len(), 2);
+
+    // Columns missing from old records are null
+    assert_eq!(json[0], serde_json::json!({
+        "id": "12",
+        "wpm": 55.0,
+        "date": "2024-01-01",
+        "time": "10:00:00",
+        "accuracy": 98.0,
+        "cpm": null,
+        "consistency": null,
+        "time_limit": null,
+        "skipped": null,
+        "suspect": false,
+        "personal_best": false,
+        "elapsed": null,
+        "chars": null,
+        "errors": null,
+        "difficulty": null,
+        "source": "db",
+        "attempt": null,
+        "text_hash": null,
+    }));
+    assert_eq!(json[1]["id"], "13");
+    assert_eq!(json[1]["cpm"], 300.0);
+    assert_eq!(json[1]["time_limit"], 60);
+    assert_eq!(json[1]["skipped"], 0);
+    assert_eq!(json[1]["suspect"], true);
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_summarize_history() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\n",
+    ).unwrap();
+    let first = SessionStats { duration: 3600.5, chars_typed: 12000, ..entry("13", 60.0, 99.0, 300.0) };
+    let second = SessionStats { duration: 720.25, chars_typed: 2500, ..entry("14", 40.0, 95.0, 200.0) };
+    history::save_history_to_file(&history_path, &first).unwrap();
+    history::save_history_to_file(&history_path, &second).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    let summary = history::summarize(&records);
+    assert_eq!(summary, history::HistorySummary {
+        tests: 3,
+        practice_seconds: 4320.75,
+        chars_typed: 14500,
+        tests_without_duration: 1,
+    });
+    assert_eq!(history::summary_lines(&summary), vec![
+        "Total tests: 3",
+        "Total practice: 1h 12m",
+        "Total characters typed: 14500",
+        "1 older tests saved without a duration are left out of the time and the characters",
+    ]);
+    assert_eq!(history::summarize(&[]), history::HistorySummary::default());
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_summarize_weeks() {
+    let records =
+        history::get_history_records_from_file("tests/data/weekly_history.csv", history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    let today = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+    let weeks = history::summarize_weeks(&records, today, 4);
+
+    let week = |year: i32, week: u32, start: (i32, u32, u32), tests: usize, results: Option<(f64, f64, f64)>| {
+        history::WeekSummary {
+            year,
+            week,
+            start: chrono::NaiveDate::from_ymd_opt(start.0, start.1, start.2).unwrap(),
+            tests,
+            average_wpm: results.map(|(average, _, _)| average),
+            best_wpm: results.map(|(_, best, _)| best),
+            average_accuracy: results.map(|(_, _, accuracy)| accuracy),
+        }
+    };
+    // Records before the range, after today and flagged as suspect are left out,
+    // the week without tests stays, and the last week belongs to the ISO year 2025
+    assert_eq!(weeks, vec![
+        week(2024, 50, (2024, 12, 9), 2, Some((45.0, 50.0, 92.0))),
+        week(2024, 51, (2024, 12, 16), 0, None),
+        week(2024, 52, (2024, 12, 23), 1, Some((60.0, 60.0, 96.0))),
+        // The accuracy is averaged over the records that have one
+        week(2025, 1, (2024, 12, 30), 2, Some((60.0, 65.0, 98.0))),
+    ]);
+    assert!(history::summarize_weeks(&[], today, 2).iter().all(|week| week.tests == 0));
+}
+
+#[test]
+fn test_history_stats() {
+    let mut records: Vec<history::HistoryRecord> = (1..=20)
+        .map(|number| record(&format!("2024-01-{:02}", number), Some(number as f64 * 10.0), Some(90.0)))
+        .collect();
+    records[4].accuracy = Some(100.0);
+    // A corrupted speed and a suspect result don't count
+    records.push(history::HistoryRecord { wpm: None, ..record("2024-01-21", None, Some(10.0)) });
+    records.push(history::HistoryRecord { suspect: true, ..record("2024-01-22", Some(900.0), Some(100.0)) });
+
+    let stats = history::history_stats(&records);
+    assert_eq!(stats, history::HistoryStats {
+        tests: 22,
+        skipped: 2,
+        mean_wpm: Some(105.0),
+        median_wpm: Some(105.0),
+        best_wpm: Some((200.0, "2024-01-20".to_string())),
+        mean_accuracy: Some(90.5),
+        // 155 WPM on the last ten tests against 55 on the ten before
+        recent_trend: Some(100.0),
+    });
+    assert_eq!(history::history_stats_lines(&stats), vec![
+        "Mean WPM: 105.00",
+        "Median WPM: 105.00",
+        "Best WPM: 200.00 on 2024-01-20",
+        "Mean accuracy: 90.50%",
+        "Last 10 tests against the 10 before: +100.00 WPM",
+        "2 of 22 tests are left out, flagged as suspect or without a readable speed",
+    ]);
+
+    // The median of an odd number of tests is the middle one, ties keep the first best
+    let stats = history::history_stats(&[
+        record("2024-02-01", Some(50.0), None),
+        record("2024-02-02", Some(30.0), None),
+        record("2024-02-03", Some(50.0), None),
+    ]);
+    assert_eq!(stats.median_wpm, Some(50.0));
+    assert_eq!(stats.best_wpm, Some((50.0, "2024-02-01".to_string())));
+    assert_eq!(stats.mean_accuracy, None);
+    assert_eq!(stats.recent_trend, None);
+}
+
+#[test]
+fn test_history_totals_from_file() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    for (text_id, wpm, attempt) in [("1", 40.0, None), ("2", 80.0, None), ("1", 60.0, Some(2)), ("1", 50.0, Some(1))] {
+        history::save_history_to_file(&history_path, &SessionStats { attempt, ..entry(text_id, wpm, 98.0, 250.0) }).unwrap();
+    }
+    let first_text = history::TextFilter::Exact("1".to_string());
+    let records = history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &first_text).unwrap();
+
+    // The totals count the retry, the stats leave it out unless asked
+    let (summary, stats, retries) = history::history_totals_from_file(history_path.as_ref(), &first_text, false).unwrap();
+    assert_eq!(summary, history::summarize(&records));
+    assert_eq!(stats, history::history_stats(&[records[0].clone(), records[2].clone()]));
+    assert_eq!(retries, 1);
+    let (_, stats, retries) = history::history_totals_from_file(history_path.as_ref(), &first_text, true).unwrap();
+    assert_eq!(stats, history::history_stats(&records));
+    assert_eq!(retries, 0);
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_history_stats_without_data() {
+    let stats = history::history_stats(&[]);
+    assert_eq!(stats, history::HistoryStats::default());
+    assert_eq!(history::history_stats_lines(&stats), vec![
+        "Mean WPM: not enough data",
+        "Median WPM: not enough data",
+        "Best WPM: not enough data",
+        "Mean accuracy: not enough data",
+        "Last 10 tests against the 10 before: not enough data, it takes 20 tests",
+    ]);
+}
+
+#[test]
+fn test_retries_are_read_from_history() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(&history_path, "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\n").unwrap();
+    for attempt in [1, 2] {
+        history::save_history_to_file(&history_path, &SessionStats { attempt: Some(attempt), ..entry("13", 60.0, 99.0, 300.0) }).unwrap();
+    }
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    std::fs::remove_file(&history_path).unwrap();
+
+    // Records saved before the attempts were counted are taken as first attempts
+    assert_eq!(records.iter().map(|record| record.attempt).collect::<Vec<_>>(), vec![None, Some(1), Some(2)]);
+    assert_eq!(records.iter().map(history::HistoryRecord::is_retry).collect::<Vec<_>>(), vec![false, false, true]);
+}
+
+#[test]
+fn test_text_hash() {
+    let hash = history::text_hash("The quick brown fox");
+    assert_eq!(hash.len(), 8);
+    // Rewrapping a text keeps its hash, changing a word doesn't
+    assert_eq!(history::text_hash("The quick\r\nbrown  fox\n"), hash);
+    assert_ne!(history::text_hash("The quick brown cat"), hash);
+}
+
+#[test]
+fn test_history_record_by_number() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    let text_hash = history::text_hash("hello world");
+    history::save_history_to_file(&history_path, &entry("12", 50.0, 98.0, 250.0)).unwrap();
+    history::save_history_to_file(&history_path, &SessionStats { text_hash: Some(text_hash.clone()), ..entry("13", 60.0, 99.0, 300.0) }).unwrap();
+
+    let record = history::history_record_from_file(&history_path, 2);
+    let out_of_range = history::history_record_from_file(&history_path, 3);
+    std::fs::remove_file(&history_path).unwrap();
+
+    let record = record.unwrap();
+    assert_eq!((record.id.as_str(), record.text_hash), ("13", Some(text_hash)));
+    assert!(matches!(out_of_range, Err(history::HistoryError::RecordOutOfRange(3, 2))));
+}
+
+#[test]
+fn test_retake_of_record() {
+    let retake = |id: &str, source| {
+        let record = history::HistoryRecord { id: id.to_string(), source, ..history::HistoryRecord::default() };
+        history::Retake::of_record(17, &record)
+    };
+
+    let database = retake("42", TextSource::Database).unwrap();
+    assert_eq!(database.text, history::RetakeText::Database(42));
+    assert_eq!((database.no_punctuation, database.lowercase, database.words), (false, false, None));
+    // The options that changed the text are read back from the id
+    let options = retake("notes/my#1.txt:np:lc#25w", TextSource::File).unwrap();
+    assert_eq!(options.text, history::RetakeText::File("notes/my#1.txt".to_string()));
+    assert_eq!((options.no_punctuation, options.lowercase, options.words), (true, true, Some(25)));
+    let lowercase = retake("7:lc", TextSource::Database).unwrap();
+    assert_eq!((lowercase.text, lowercase.no_punctuation, lowercase.lowercase), (history::RetakeText::Database(7), false, true));
+    // The daily challenge is retaken on the text of its date
+    let date = chrono::NaiveDate::from_ymd_opt(2024, 6, 1).unwrap();
+    assert_eq!(
+        retake("daily-2024-06-01", TextSource::Database).unwrap().text,
+        history::RetakeText::Database(rstype::database::daily_text_id(date)),
+    );
+
+    // Generated texts and marathons have no single text to load again
+    let drill = retake("drill:numbers:42", TextSource::Drill).unwrap_err();
+    assert!(matches!(&drill, history::HistoryError::NotRetakeable(17, id) if id == "drill:numbers:42"), "{:?}", drill);
+    assert!(matches!(retake("1+2", TextSource::Database), Err(history::HistoryError::NotRetakeable(17, _))));
+}
+
+#[test]
+fn test_retake_checks_the_text_hash() {
+    let record = history::HistoryRecord {
+        id: "notes.txt".to_string(),
+        source: TextSource::File,
+        text_hash: Some(history::text_hash("hello world")),
+        ..history::HistoryRecord::default()
+    };
+    let retake = history::Retake::of_record(3, &record).unwrap();
+    assert!(retake.matches("hello\nworld"));
+    assert!(!retake.matches("hello there"));
+
+    // Records saved before the hash can't be checked
+    let retake = history::Retake::of_record(3, &history::HistoryRecord { text_hash: None, ..record }).unwrap();
+    assert!(retake.matches("hello there"));
+    assert_eq!(
+        history::HistoryError::TextChanged(3, "notes.txt".to_string()).to_string(),
+        "The text notes.txt changed since record 3 was typed, retake it anyway with --force",
+    );
+}
+
+#[test]
+fn test_wpm_trend_line() {
+    assert_eq!(history::wpm_trend_line(&[40.0, 50.0, 60.0], 80).unwrap(), "WPM trend: ▁▅█ 40-60");
+    // The sparkline is downsampled to the width left by the label and the range
+    let speeds: Vec<f64> = (0..100).map(|speed| speed as f64).collect();
+    let line = history::wpm_trend_line(&speeds, 40).unwrap();
+    assert_eq!(line.chars().count(), 40, "{}", line);
+    assert!(line.starts_with("WPM trend: ▁") && line.ends_with("█ 0-99"), "{}", line);
+    // No trend without two records
+    assert_eq!(history::wpm_trend_line(&[50.0], 80), None);
+    assert_eq!(history::wpm_trend_line(&[], 80), None);
+}
+
+#[test]
+fn test_paginate() {
+    let lines: Vec<String> = (1..=5).map(|number| format!("line {}", number)).collect();
+    let paginate = |page_size: Option<usize>, answers: &str| -> String {
+        let mut output = vec![];
+        history::paginate(&lines, page_size, &mut output, &mut std::io::Cursor::new(answers)).unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    let prompt = history::PAGE_PROMPT;
+
+    // Without a page size everything is written at once
+    assert_eq!(paginate(None, ""), "line 1\nline 2\nline 3\nline 4\nline 5\n");
+    // Enter shows the next page, there is no prompt after the last one
+    assert_eq!(
+        paginate(Some(2), "\n\n"),
+        format!("line 1\nline 2\n{}line 3\nline 4\n{}line 5\n", prompt, prompt),
+    );
+    assert_eq!(paginate(Some(5), ""), "line 1\nline 2\nline 3\nline 4\nline 5\n");
+    // q stops, the end of the input writes the rest
+    assert_eq!(paginate(Some(2), "q\n"), format!("line 1\nline 2\n{}", prompt));
+    assert_eq!(paginate(Some(2), ""), format!("line 1\nline 2\n{}line 3\nline 4\nline 5\n", prompt));
+    assert_eq!(paginate(Some(0), "Q\n"), format!("line 1\n{}", prompt));
+}
+
+fn record(date: &str, wpm: Option<f64>, accuracy: Option<f64>) -> history::HistoryRecord {
+    history::HistoryRecord { date: date.to_string(), wpm, accuracy, ..history::HistoryRecord::default() }
+}
+
+fn entry(text_id: &str, wpm: f64, accuracy: f64, cpm: f64) -> SessionStats {
+    SessionStats {
+        text_id: text_id.to_string(),
+        timestamp: chrono::Local::now(),
+        wpm,
+        accuracy,
+        cpm,
+        consistency: 90.0,
+        ..SessionStats::default()
+    }
+}
+
+#[test]
+fn test_history_row_is_dated_by_the_stats() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+
+    let timestamp = chrono::NaiveDate::from_ymd_opt(2024, 3, 9).unwrap()
+        .and_hms_opt(18, 30, 5).unwrap()
+        .and_local_timezone(chrono::Local).unwrap();
+    let stats = SessionStats { timestamp, ..entry("5", 50.0, 100.0, 250.0) };
+    history::save_history_to_file(&history_path, &stats).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records[0].date, "2024-03-09");
+    assert_eq!(records[0].time, "18:30:05");
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_timed_test_records_duration() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+
+    let timed = SessionStats { time_limit: Some(60), ..entry("7", 45.0, 95.0, 230.0) };
+    history::save_history_to_file(&history_path, &timed).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(records[0].time_limit, Some(60));
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_slowest_words_across_sessions() {
+    let word_timings_path = format!("tests/{}.csv", Uuid::new_v4());
+
+    history::save_word_timings_to_file(&word_timings_path, &[("the", 80.0), ("rhythm", 20.0)]).unwrap();
+    history::save_word_timings_to_file(&word_timings_path, &[("rhythm", 30.0), ("fox", 50.0)]).unwrap();
+
+    let slowest = history::get_slowest_words_from_file(&word_timings_path, 2).unwrap();
+    assert_eq!(slowest, vec![
+        history::WordStats { word: "rhythm".to_string(), occurrences: 2, average_wpm: 25.0 },
+        history::WordStats { word: "fox".to_string(), occurrences: 1, average_wpm: 50.0 },
+    ]);
+
+    std::fs::remove_file(&word_timings_path).unwrap()
+}
+
+#[test]
+fn test_slowest_words_without_data() {
+    let word_timings_path = format!("tests/{}.csv", Uuid::new_v4());
+    assert!(matches!(
+        history::get_slowest_words_from_file(&word_timings_path, 5),
+        Err(history::HistoryError::FileDoesNotExist)
+    ));
+}
+
+#[test]
+fn test_ghost_keeps_best_run_per_text() {
+    let ghosts_path = format!("tests/{}.csv", Uuid::new_v4());
+    let run = |wpm: f64, last_key_millis: u64| history::GhostRun {
+        wpm,
+        timeline: vec![(Duration::ZERO, 1), (Duration::from_millis(last_key_millis), 2)],
+    };
+
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "1").unwrap(), None);
+
+    assert!(history::save_ghost_to_file(&ghosts_path, "1", &run(40.0, 300)).unwrap());
+    assert!(history::save_ghost_to_file(&ghosts_path, "2", &run(30.0, 400)).unwrap());
+    // A slower run of the same text is not saved
+    assert!(!history::save_ghost_to_file(&ghosts_path, "1", &run(35.0, 350)).unwrap());
+    assert!(history::save_ghost_to_file(&ghosts_path, "1", &run(50.0, 250)).unwrap());
+
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "1").unwrap(), Some(run(50.0, 250)));
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "2").unwrap(), Some(run(30.0, 400)));
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "3").unwrap(), None);
+
+    std::fs::remove_file(&ghosts_path).unwrap()
+}
+
+#[test]
+fn test_ghost_file_with_broken_rows() {
+    let ghosts_path = format!("tests/{}.csv", Uuid::new_v4());
+    let run = history::GhostRun { wpm: 40.0, timeline: vec![(Duration::ZERO, 1), (Duration::from_millis(300), 2)] };
+    history::save_ghost_to_file(&ghosts_path, "1", &run).unwrap();
+    // A row cut off and one that isn't a run at all
+    let mut content = std::fs::read_to_string(&ghosts_path).unwrap();
+    content.push_str("2,35.00\n3,fast,slow\n");
+    std::fs::write(&ghosts_path, content).unwrap();
+
+    // The broken rows are skipped, the other runs are still raced
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "1").unwrap(), Some(run.clone()));
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "2").unwrap(), None);
+    // A new run of a broken row takes its place, the file is replaced in one go
+    assert!(history::save_ghost_to_file(&ghosts_path, "2", &run).unwrap());
+    assert_eq!(history::load_ghost_from_file(&ghosts_path, "2").unwrap(), Some(run));
+    assert_eq!(std::fs::read_to_string(&ghosts_path).unwrap().lines().count(), 3);
+    assert!(!std::path::Path::new(&format!("{}.tmp", ghosts_path)).exists());
+
+    std::fs::remove_file(&ghosts_path).unwrap()
+}
+
+#[test]
+fn test_ghost_progress_at() {
+    let ghost = history::GhostRun {
+        wpm: 60.0,
+        timeline: vec![
+            (Duration::from_millis(0), 1),
+            (Duration::from_millis(200), 2),
+            (Duration::from_millis(500), 1),
+        ],
+    };
+
+    assert_eq!(ghost.progress_at(Duration::ZERO), 1);
+    assert_eq!(ghost.progress_at(Duration::from_millis(199)), 1);
+    assert_eq!(ghost.progress_at(Duration::from_millis(200)), 2);
+    // A mistake sets the ghost back too
+    assert_eq!(ghost.progress_at(Duration::from_secs(1)), 1);
+}
+
+#[test]
+fn test_find_latest_record_of_text() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+
+    assert!(history::find_history_record_in_file(&history_path, "daily-2024-06-01").unwrap().is_none());
+
+    history::save_history_to_file(&history_path, &entry("daily-2024-06-01", 50.0, 95.0, 250.0)).unwrap();
+    history::save_history_to_file(&history_path, &entry("12", 60.0, 97.0, 300.0)).unwrap();
+    history::save_history_to_file(&history_path, &entry("daily-2024-06-01", 55.0, 96.0, 275.0)).unwrap();
+
+    let record = history::find_history_record_in_file(&history_path, "daily-2024-06-01").unwrap().unwrap();
+    assert_eq!(record.wpm, Some(55.0));
+    assert!(history::find_history_record_in_file(&history_path, "daily-2024-06-02").unwrap().is_none());
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_average_wpm_and_accuracy() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    history::save_history_to_file(&history_path, &entry("1", 40.0, 90.0, 200.0)).unwrap();
+    history::save_history_to_file(&history_path, &entry("2", 60.0, 100.0, 300.0)).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(history::average_wpm_and_accuracy(&records), Some((50.0, 95.0)));
+    assert_eq!(history::average_wpm_and_accuracy(&[]), None);
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_most_mistyped_words_across_sessions() {
+    let mistakes_path = format!("tests/{}.csv", Uuid::new_v4());
+    assert!(matches!(
+        history::get_most_mistyped_words_from_file(&mistakes_path, 5),
+        Err(history::HistoryError::FileDoesNotExist)
+    ));
+
+    let words = |word_errors: &[(&str, u32)]| -> Vec<(String, u32)> {
+        word_errors.iter().map(|(word, errors)| (word.to_string(), *errors)).collect()
+    };
+    history::save_mistyped_words_to_file(&mistakes_path, &words(&[("the", 1), ("rhythm", 3)])).unwrap();
+    history::save_mistyped_words_to_file(&mistakes_path, &words(&[("rhythm", 2), ("fox", 4)])).unwrap();
+
+    let mistyped = history::get_most_mistyped_words_from_file(&mistakes_path, 2).unwrap();
+    assert_eq!(mistyped, words(&[("rhythm", 5), ("fox", 4)]));
+
+    std::fs::remove_file(&mistakes_path).unwrap()
+}
+
+#[test]
+fn test_suspect_records_are_flagged() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    history::save_history_to_file(&history_path, &entry("1", 50.0, 100.0, 250.0)).unwrap();
+    let pasted = SessionStats { suspect: true, ..entry("1", 900.0, 100.0, 4500.0) };
+    history::save_history_to_file(&history_path, &pasted).unwrap();
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert!(records[1].suspect);
+    // Suspect runs don't count toward the averages
+    assert_eq!(history::average_wpm_and_accuracy(&records), Some((50.0, 100.0)));
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_count_runs_meeting_goal() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    for wpm in [60.0, 80.0, 79.99, 95.5] {
+        history::save_history_to_file(&history_path, &entry("1", wpm, 100.0, wpm * 5.0)).unwrap();
+    }
+
+    let records =
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap();
+    assert_eq!(history::count_runs_meeting_goal(&records, 80.0), 2);
+    assert_eq!(history::count_runs_meeting_goal(&records, 100.0), 0);
+
+    std::fs::remove_file(&history_path).unwrap()
+}
+
+#[test]
+fn test_compare_legacy_and_new_records() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY,DURATION,ELAPSED\n12,50.00,2024-01-01,10:00:00,96.00,30,48.00\n",
+    ).unwrap();
+    let stats = SessionStats {
+        timestamp: chrono::Local.with_ymd_and_hms(2024, 2, 1, 11, 30, 0).unwrap(),
+        time_limit: Some(60),
+        duration: 60.0,
+        errors: 4,
+        difficulty: Some(2),
+        attempt: Some(3),
+        ..entry("13", 60.0, 98.0, 300.0)
+    };
+    history::save_history_to_file(&history_path, &stats).unwrap();
+
+    let picked = history::pick_history_records_from_file(&history_path, 1, 2);
+    let out_of_range = history::pick_history_records_from_file(&history_path, 2, 3);
+    let zero = history::pick_history_records_from_file(&history_path, 0, 1);
+    std::fs::remove_file(&history_path).unwrap();
+
+    let (first, second) = picked.unwrap();
+    assert_eq!(history::compare_history_records(&first, &second), vec![
+        "             First                Second               Change",
+        "Text         12                   13",
+        "Date         2024-01-01 10:00:00  2024-02-01 11:30:00",
+        "WPM          50.00                60.00                +20.0%",
+        "Accuracy     96.00%               98.00%               +2.1%",
+        "CPM          n/a                  300.00",
+        "Consistency  n/a                  90.00%",
+        "Duration     48.00s               60.00s               +25.0%",
+        "Time limit   30s                  60s",
+        "Skipped      n/a                  0",
+        "Errors       n/a                  4",
+        "Source       db                   db",
+        "Difficulty   n/a                  2",
+        "Attempt      n/a                  3",
+    ]);
+    let error = out_of_range.unwrap_err();
+    assert!(matches!(error, history::HistoryError::RecordOutOfRange(3, 2)), "{:?}", error);
+    assert!(error.to_string().contains("1 to 2"), "{}", error);
+    assert!(matches!(zero, Err(history::HistoryError::RecordOutOfRange(0, 2))));
+}
+
+#[test]
+fn test_personal_best() {
+    // The first result has nothing to beat
+    assert_eq!(history::personal_best(60.0, None, None), None);
+    // A tie isn't a new best
+    assert_eq!(history::personal_best(60.0, Some(60.0), Some(60.0)), None);
+    assert_eq!(history::personal_best(62.5, Some(60.0), Some(55.0)), Some(history::PersonalBest::Overall(2.5)));
+    assert_eq!(history::personal_best(58.0, Some(60.0), Some(55.0)), Some(history::PersonalBest::Text(3.0)));
+    assert_eq!(history::personal_best(58.0, Some(60.0), None), None);
+}
+
+#[test]
+fn test_best_wpm_in_file() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    assert_eq!(history::best_wpm_in_file(&history_path, None).unwrap(), None);
+
+    std::fs::write(
+        &history_path,
+        "ID,WPM,DATE,TIME,ACCURACY\n12,55.00,2024-01-01,10:00:00,98.00\n",
+    ).unwrap();
+    history::save_history_to_file(&history_path, &entry("13", 60.0, 99.0, 300.0)).unwrap();
+    let suspect = SessionStats { suspect: true, ..entry("13", 200.0, 100.0, 1000.0) };
+    history::save_history_to_file(&history_path, &suspect).unwrap();
+
+    let overall = history::best_wpm_in_file(&history_path, None);
+    let of_text = history::best_wpm_in_file(&history_path, Some("12"));
+    let never_typed = history::best_wpm_in_file(&history_path, Some("14"));
+    std::fs::remove_file(&history_path).unwrap();
+
+    // Suspect records don't count
+    assert_eq!(overall.unwrap(), Some(60.0));
+    assert_eq!(of_text.unwrap(), Some(55.0));
+    assert_eq!(never_typed.unwrap(), None);
+}
+
+#[test]
+fn test_default_history_file_keeps_legacy_file() {
+    let home = std::path::PathBuf::from(format!("tests/{}", Uuid::new_v4()));
+    std::fs::create_dir(&home).unwrap();
+
+    // New installs keep the history in the data directory
+    assert_eq!(history::default_history_file(&home, None), home.join(".local/share/rstype/history.csv"));
+    let data_home = home.join("data");
+    assert_eq!(history::default_history_file(&home, Some(&data_home)), data_home.join("rstype/history.csv"));
+
+    // A history saved by older versions is still read from the home directory
+    std::fs::write(home.join(".rstype_history.csv"), "ID,WPM,DATE,TIME,ACCURACY\n").unwrap();
+    assert_eq!(history::default_history_file(&home, Some(&data_home)), home.join(".rstype_history.csv"));
+
+    std::fs::remove_dir_all(home).unwrap();
+}
+
+#[test]
+fn test_history_file_flag_comes_before_config() {
+    let flag = std::path::PathBuf::from("flag.csv");
+    let config = std::path::PathBuf::from("config.csv");
+    assert_eq!(history::resolve_history_file(Some(flag.clone()), Some(config), "laptop").unwrap(), flag);
+}
+
+#[test]
+fn test_profiles_keep_history_in_their_directory() {
+    let home = std::path::Path::new("/home/typist");
+    assert_eq!(
+        history::profile_history_file(home, None, "split"),
+        home.join(".local/share/rstype/split/history.csv"),
+    );
+    assert_eq!(
+        history::profile_history_file(home, Some(std::path::Path::new("/data")), "split"),
+        std::path::Path::new("/data/rstype/split/history.csv"),
+    );
+    // The default profile keeps the history it had before profiles
+    assert_eq!(
+        history::profile_history_file(home, None, "default"),
+        history::default_history_file(home, None),
+    );
+}
+
+#[test]
+fn test_save_history_creates_directory() {
+    let directory = format!("tests/{}", Uuid::new_v4());
+    let history_path = std::path::Path::new(&directory).join("rstype").join("history.csv");
+
+    history::save_history_to_file(&history_path, &entry("42", 61.5, 97.25, 320.0)).unwrap();
+    history::save_completed_lesson(&history_path, "home row").unwrap();
+    assert_eq!(
+        history::get_history_records_from_file(&history_path, history::NumberOfRecords::All, &history::TextFilter::All).unwrap().len(),
+        1,
+    );
+    assert_eq!(history::get_completed_lessons(&history_path).unwrap(), vec!["home row".to_string()]);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_last_records_of_a_long_history() {
+    let history_path = format!("tests/{}.csv", Uuid::new_v4());
+    let mut content = history::HISTORY_HEADER.join(",") + "\n";
+    for row in 1..=100_000 {
+        content.push_str(&format!("{},{}.00,2024-01-01,10:00:00,98.00,,,,,,,,,,,,,\n", row % 7, row));
+    }
+    std::fs::write(&history_path, content).unwrap();
+    let speeds = |number_of_records, filter: &history::TextFilter| -> Vec<f64> {
+        history::get_history_records_from_file(&history_path, number_of_records, filter).unwrap()
+            .into_iter()
+            .map(|record| record.wpm.unwrap())
+            .collect()
+    };
+
+    assert_eq!(speeds(history::NumberOfRecords::Last(3), &history::TextFilter::All), vec![99_998.0, 99_999.0, 100_000.0]);
+    assert_eq!(speeds(history::NumberOfRecords::Last(2), &history::TextFilter::Exact("0".to_string())), vec![99_988.0, 99_995.0]);
+    assert!(speeds(history::NumberOfRecords::Last(0), &history::TextFilter::All).is_empty());
+    assert_eq!(speeds(history::NumberOfRecords::All, &history::TextFilter::All).len(), 100_000);
+
+    // Pruning keeps the newest records without the rest of them in memory
+    assert_eq!(history::prune_history_file(&history_path, 2).unwrap(), 99_998);
+    assert_eq!(speeds(history::NumberOfRecords::All, &history::TextFilter::All), vec![99_999.0, 100_000.0]);
+
+    std::fs::remove_file(&history_path).unwrap();
+}
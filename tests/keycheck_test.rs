@@ -0,0 +1,43 @@
+use pancurses::Input;
+use rstype::keycheck;
+
+#[test]
+fn test_erase_word_keys() {
+    // Ctrl+W everywhere, and Ctrl+Backspace on Windows Terminal
+    assert!(keycheck::is_ctrl_w(&Input::Character('\x17')));
+    assert!(keycheck::is_erase_word(&Input::Character('\x17')));
+    // Ctrl+Backspace on xterm, VTE terminals, Konsole, Alacritty and kitty
+    assert!(keycheck::is_ctrl_backspace(&Input::Character('\x08')));
+    assert!(!keycheck::is_ctrl_w(&Input::Character('\x08')));
+    assert!(keycheck::is_erase_word(&Input::Character('\x08')));
+}
+
+#[test]
+fn test_control_characters() {
+    for c in (0..0x20u8).map(char::from).chain(['\x7f']) {
+        let key = Input::Character(c);
+        assert_eq!(keycheck::is_erase_word(&key), c == '\x08' || c == '\x17', "{:?}", c);
+        assert_eq!(keycheck::is_backspace(&key), c == '\x7f', "{:?}", c);
+        assert_eq!(keycheck::is_ctrl_c(&key), c == '\x03', "{:?}", c);
+        assert_eq!(keycheck::is_ctrl_n(&key), c == '\x0e', "{:?}", c);
+        assert_eq!(keycheck::is_ctrl_t(&key), c == '\x14', "{:?}", c);
+        assert_eq!(keycheck::is_ctrl_u(&key), c == '\x15', "{:?}", c);
+        assert_eq!(keycheck::is_ctrl_y(&key), c == '\x19', "{:?}", c);
+        assert_eq!(keycheck::is_tab(&key), c == '\t', "{:?}", c);
+        assert_eq!(keycheck::is_enter(&key), c == '\n', "{:?}", c);
+    }
+}
+
+#[test]
+fn test_backspace_key_is_not_erase_word() {
+    // Terminals whose Backspace sends Ctrl+H get it translated by curses
+    assert!(keycheck::is_backspace(&Input::KeyBackspace));
+    assert!(!keycheck::is_erase_word(&Input::KeyBackspace));
+}
+
+#[test]
+fn test_ctrl_u() {
+    assert!(keycheck::is_ctrl_u(&Input::Character('\x15')));
+    assert!(!keycheck::is_ctrl_u(&Input::Character('u')));
+    assert!(!keycheck::is_erase_word(&Input::Character('\x15')));
+}
@@ -0,0 +1,66 @@
+use rstype::app::{App, AppOptions};
+use rstype::layout::{self, Layout};
+use pancurses::Input;
+use std::collections::HashSet;
+
+const QWERTY_KEYS: &str = "`1234567890-=qwertyuiop[]\\asdfghjkl;'zxcvbnm,./~!@#$%^&*()_+QWERTYUIOP{}|ASDFGHJKL:\"ZXCVBNM<>?";
+
+fn type_on_qwerty(layout: Layout, keys: &str) -> String {
+    keys.chars().map(|c| layout::remap(layout, c)).collect()
+}
+
+#[test]
+fn test_remap_home_row() {
+    assert_eq!(type_on_qwerty(Layout::Colemak, "asdfjkl;"), "arstneio");
+    assert_eq!(type_on_qwerty(Layout::Dvorak, "asdfjkl;"), "aoeuhtns");
+    assert_eq!(type_on_qwerty(Layout::Workman, "asdfjkl;"), "ashtneoi");
+}
+
+#[test]
+fn test_remap_shifted_keys() {
+    assert_eq!(type_on_qwerty(Layout::Colemak, "Hello:"), "HfiiyO");
+    assert_eq!(type_on_qwerty(Layout::Dvorak, "QW_Z\""), "\"<{:_");
+    assert_eq!(type_on_qwerty(Layout::Workman, "ASDF"), "ASHT");
+}
+
+#[test]
+fn test_remap_leaves_other_keys() {
+    for layout in [Layout::Colemak, Layout::Dvorak, Layout::Workman] {
+        for c in [' ', '\x03', '\x08', '\x7f', '\n', 'é'] {
+            assert_eq!(layout::remap(layout, c), c);
+        }
+    }
+}
+
+#[test]
+fn test_remap_round_trip() {
+    for layout in [Layout::Colemak, Layout::Dvorak, Layout::Workman] {
+        // Every key produces a different character and no character is lost
+        let remapped: HashSet<char> = QWERTY_KEYS.chars().map(|c| layout::remap(layout, c)).collect();
+        assert_eq!(remapped, QWERTY_KEYS.chars().collect::<HashSet<char>>());
+
+        // So typing the remapped key again always leads back to the original one
+        for c in QWERTY_KEYS.chars() {
+            let mut key = layout::remap(layout, c);
+            let mut presses = 1;
+            while key != c {
+                key = layout::remap(layout, key);
+                presses += 1;
+                assert!(presses <= QWERTY_KEYS.len());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_app_emulates_layout() {
+    let options = AppOptions { layout: Some(Layout::Colemak), ..AppOptions::default() };
+    let mut app = App::with_options(("arst".to_string(), "test".to_string()), options);
+    for c in "asdf".chars() {
+        let key = app.emulate_layout(&Input::Character(c));
+        app.process_key(&key);
+    }
+    assert!(app.session().is_complete());
+    assert_eq!(app.emulate_layout(&Input::KeyBackspace), Input::KeyBackspace);
+    assert_eq!(app.emulate_layout(&Input::Character(' ')), Input::Character(' '));
+}
@@ -0,0 +1,57 @@
+use rstype::history;
+use rstype::lessons::{self, LESSONS};
+use uuid::Uuid;
+
+#[test]
+fn test_next_lesson_follows_progression() {
+    assert_eq!(lessons::next_lesson(&[]).unwrap().name, "home-row");
+
+    let completed = vec!["home-row".to_string(), "bottom-row".to_string()];
+    assert_eq!(lessons::next_lesson(&completed).unwrap().name, "top-row");
+
+    let all: Vec<String> = LESSONS.iter().map(|lesson| lesson.name.to_string()).collect();
+    assert!(lessons::next_lesson(&all).is_none());
+}
+
+#[test]
+fn test_lesson_pass_criteria() {
+    assert!(lessons::is_lesson_passed(95.0, 20.0, 20.0));
+    assert!(lessons::is_lesson_passed(100.0, 45.0, 20.0));
+    assert!(!lessons::is_lesson_passed(94.9, 45.0, 20.0));
+    assert!(!lessons::is_lesson_passed(100.0, 19.9, 20.0));
+}
+
+#[test]
+fn test_lesson_texts() {
+    let (text, text_id) = lessons::lesson_text(&LESSONS[0], 4);
+    assert_eq!(text_id, "lesson:home-row:4");
+    assert_eq!(text.split(' ').count(), lessons::LESSON_WORDS);
+    assert!(text.chars().all(|c| c == ' ' || "asdfghjkl;".contains(c)));
+
+    let (text, _) = lessons::lesson_text(&LESSONS[3], 4);
+    assert!(text.split(' ').all(|word| word.starts_with(|c: char| c.is_ascii_uppercase())));
+
+    let (text, _) = lessons::lesson_text(&LESSONS[4], 4);
+    assert!(text.split(' ').all(|word| word.ends_with(|c: char| ",.;:!?".contains(c))));
+
+    for lesson in &LESSONS {
+        assert_eq!(lessons::lesson_text(lesson, 9), lessons::lesson_text(lesson, 9));
+        assert!(!lessons::lesson_text(lesson, 9).0.is_empty());
+    }
+}
+
+#[test]
+fn test_completed_lessons_are_saved() {
+    let lessons_path = format!("tests/{}.csv", Uuid::new_v4());
+    assert!(history::get_completed_lessons_from_file(&lessons_path).unwrap().is_empty());
+
+    history::save_completed_lesson_to_file(&lessons_path, "home-row").unwrap();
+    history::save_completed_lesson_to_file(&lessons_path, "top-row").unwrap();
+    history::save_completed_lesson_to_file(&lessons_path, "home-row").unwrap();
+
+    let completed = history::get_completed_lessons_from_file(&lessons_path).unwrap();
+    assert_eq!(completed, vec!["home-row".to_string(), "top-row".to_string()]);
+    assert_eq!(lessons::next_lesson(&completed).unwrap().name, "bottom-row");
+
+    std::fs::remove_file(&lessons_path).unwrap()
+}
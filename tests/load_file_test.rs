@@ -8,3 +8,32 @@ fn load_text_from_file() {
     assert_eq!(result.1, file_address);
     std::fs::remove_file(file_address).unwrap()
 }
+
+#[test]
+fn prepare_word_count_text_truncates_long_text() {
+    let prepared_text = ("the quick brown fox jumps".to_string(), "42".to_string());
+    let (text, text_id) = rstype::prepare_word_count_text(prepared_text, 3);
+    assert_eq!(text, "the quick brown");
+    assert_eq!(text_id, "42#3w");
+}
+
+#[test]
+fn prepare_word_count_text_cycles_short_text() {
+    let prepared_text = ("one two\nthree".to_string(), "tests/words.txt".to_string());
+    let (text, text_id) = rstype::prepare_word_count_text(prepared_text, 7);
+    assert_eq!(text.split_whitespace().count(), 7);
+    assert_eq!(text, "one two three one two three one");
+    assert_eq!(text_id, "tests/words.txt#7w");
+}
+
+#[test]
+fn prepare_word_count_text_can_be_typed() {
+    let prepared_text = ("a bb ccc".to_string(), "1".to_string());
+    let (text, _) = rstype::prepare_word_count_text(prepared_text, 2);
+    let mut session = rstype::session::TypingSession::new(&text);
+    for c in "a bb".chars() {
+        session.press(c);
+    }
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 2);
+}
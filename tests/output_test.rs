@@ -0,0 +1,121 @@
+use pancurses::Input;
+use rstype::history::HISTORY_HEADER;
+use rstype::output::{
+    output_format, refuse_existing_file, save_keystrokes_to_file, save_results_to_file, OutputError, OutputFormat,
+};
+use rstype::session::{SessionRules, SessionStats, TypingSession};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn stats(text_id: &str, wpm: f64) -> SessionStats {
+    SessionStats {
+        text_id: text_id.to_string(),
+        timestamp: chrono::Local::now(),
+        wpm,
+        accuracy: 98.5,
+        duration: 20.0,
+        completed: true,
+        ..SessionStats::default()
+    }
+}
+
+#[test]
+fn test_output_format_by_extension() {
+    assert_eq!(output_format("results.csv").unwrap(), OutputFormat::Csv);
+    assert_eq!(output_format("logs/results.JSON").unwrap(), OutputFormat::Json);
+    assert_eq!(output_format("results.jsonl").unwrap(), OutputFormat::Json);
+    assert!(matches!(output_format("results.txt"), Err(OutputError::UnknownFormat(path)) if path == "results.txt"));
+    assert!(matches!(output_format("results"), Err(OutputError::UnknownFormat(_))));
+}
+
+#[test]
+fn test_save_results_to_csv() {
+    let dir = format!("tests/{}", Uuid::new_v4());
+    std::fs::create_dir(&dir).unwrap();
+    let path = format!("{}/results.csv", dir);
+
+    save_results_to_file(&path, &stats("1", 50.0)).unwrap();
+    save_results_to_file(&path, &stats("2", 62.5)).unwrap();
+
+    // The header is only written to the new file
+    let content = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<&str> = content.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], HISTORY_HEADER.join(","));
+    assert!(lines[1].starts_with("1,50.00,"), "{}", lines[1]);
+    assert!(lines[2].starts_with("2,62.50,"), "{}", lines[2]);
+    assert!(lines[2].ends_with(",98.50,0.00,0.00,,0,no,no,20.00,0,0,,db,,"), "{}", lines[2]);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_save_results_to_json() {
+    let dir = format!("tests/{}", Uuid::new_v4());
+    std::fs::create_dir(&dir).unwrap();
+    let path = format!("{}/results.json", dir);
+
+    save_results_to_file(&path, &stats("1", 50.0)).unwrap();
+    save_results_to_file(&path, &stats("2", 62.5)).unwrap();
+
+    let content = std::fs::read_to_string(&path).unwrap();
+    let lines: Vec<serde_json::Value> = content.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0]["text_id"], "1");
+    assert_eq!(lines[1]["text_id"], "2");
+    assert_eq!(lines[1]["wpm"], 62.5);
+    assert_eq!(lines[1]["duration"], 20.0);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_io_error_names_the_file() {
+    let path = format!("tests/{}/results.csv", Uuid::new_v4());
+
+    let error = save_results_to_file(&path, &stats("1", 50.0)).unwrap_err();
+    assert!(matches!(&error, OutputError::IoError(error_path, _) if *error_path == path));
+    assert!(rstype::AppError::from(error).to_string().contains(&path));
+}
+
+#[test]
+fn test_save_keystrokes() {
+    let dir = format!("tests/{}", Uuid::new_v4());
+    std::fs::create_dir(&dir).unwrap();
+    let path = format!("{}/keys.csv", dir);
+
+    let keys: Vec<(Duration, Input)> = [(0, 'a'), (120, 'x'), (260, '\x7f'), (300, ' '), (450, 'b')].into_iter()
+        .map(|(millis, c)| (Duration::from_millis(millis), Input::Character(c)))
+        .collect();
+    let simulated = TypingSession::simulate("a b", SessionRules::default(), &keys);
+    save_keystrokes_to_file(&path, &simulated).unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "offset_ms,key,expected_char,correct\n\
+        0,a,a,yes\n\
+        120,x,space,no\n\
+        260,backspace,,\n\
+        300,space,space,yes\n\
+        450,b,b,yes\n",
+    );
+
+    // Writing again replaces the keys
+    save_keystrokes_to_file(&path, &simulated[..1]).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 2);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_existing_file_is_refused() {
+    let path = format!("tests/{}.csv", Uuid::new_v4());
+    refuse_existing_file(&path).unwrap();
+
+    std::fs::write(&path, "keep me").unwrap();
+    assert!(matches!(refuse_existing_file(&path), Err(OutputError::FileExists(error_path)) if error_path == path));
+
+    std::fs::remove_file(&path).unwrap();
+}
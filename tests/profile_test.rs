@@ -0,0 +1,37 @@
+use rstype::profile::{self, DEFAULT_PROFILE};
+use uuid::Uuid;
+
+#[test]
+fn test_profile_names_are_directory_names() {
+    assert!(profile::check_profile_name("split-keyboard").is_ok());
+    assert!(profile::check_profile_name("Alex laptop").is_ok());
+    for name in ["", ".", "..", ".hidden", "a/b", "a\\b"] {
+        assert!(
+            matches!(profile::check_profile_name(name), Err(profile::ProfileError::InvalidName(_))),
+            "{:?}", name,
+        );
+    }
+}
+
+#[test]
+fn test_list_profiles() {
+    let data_directory = std::path::PathBuf::from(format!("tests/{}", Uuid::new_v4()));
+    // Without a data directory there is only the default profile
+    assert_eq!(profile::list_profiles(&data_directory).unwrap(), vec![DEFAULT_PROFILE]);
+
+    std::fs::create_dir_all(data_directory.join("split")).unwrap();
+    std::fs::create_dir_all(data_directory.join("laptop")).unwrap();
+    // The history of the default profile sits next to the profiles
+    std::fs::write(data_directory.join("history.csv"), "").unwrap();
+    assert_eq!(profile::list_profiles(&data_directory).unwrap(), vec![DEFAULT_PROFILE, "laptop", "split"]);
+
+    std::fs::remove_dir_all(data_directory).unwrap();
+}
+
+#[test]
+fn test_default_profile_has_no_directory() {
+    let data_directory = profile::data_directory(std::path::Path::new("/home/typist"), None);
+    assert_eq!(data_directory, std::path::Path::new("/home/typist/.local/share/rstype"));
+    assert_eq!(profile::profile_directory(&data_directory, DEFAULT_PROFILE), None);
+    assert_eq!(profile::profile_directory(&data_directory, "split"), Some(data_directory.join("split")));
+}
@@ -0,0 +1,581 @@
+use pancurses::{chtype, ColorPair, Input};
+use rstype::app::{App, AppOptions, ExitReason};
+use rstype::challenge::{load_challenge, save_challenge, Challenge};
+use rstype::clipboard::{Clipboard, ClipboardError};
+use rstype::replay::{load_replay, SavedReplay};
+use rstype::database::load_text_from_database;
+use rstype::session::{SessionStats, TextSource};
+use rstype::share::{Browser, ShareTarget};
+use rstype::terminal::{FakeTerminal, TerminalUi};
+use rstype::text_transform::TextTransforms;
+use rstype::webhook::{WebhookError, WebhookTransport};
+use rstype::AppError;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use uuid::Uuid;
+
+fn type_text(app: &mut App, term: &mut FakeTerminal, text: &str) {
+    for c in text.chars() {
+        app.handle_input(term, Some(Input::Character(c))).unwrap();
+    }
+}
+
+/// URL and body of every request sent to the webhook
+type WebhookRequests = Rc<RefCell<Vec<(String, String)>>>;
+
+/// Keeps the requests instead of sending them, or fails every one of them
+struct FakeWebhook {
+    requests: WebhookRequests,
+    fail: bool,
+}
+
+impl WebhookTransport for FakeWebhook {
+    fn post_json(&mut self, url: &str, body: &str) -> Result<(), WebhookError> {
+        if self.fail {
+            return Err(WebhookError::RequestError("connection refused".to_string()));
+        }
+        self.requests.borrow_mut().push((url.to_string(), body.to_string()));
+        Ok(())
+    }
+}
+
+/// Keeps the copied text, or fails like there is no clipboard over SSH
+struct FakeClipboard {
+    text: Rc<RefCell<Option<String>>>,
+    available: bool,
+}
+
+impl Clipboard for FakeClipboard {
+    fn set_text(&mut self, text: &str) -> Result<(), ClipboardError> {
+        if !self.available {
+            return Err(ClipboardError::Unavailable("no display".to_string()));
+        }
+        *self.text.borrow_mut() = Some(text.to_string());
+        Ok(())
+    }
+}
+
+/// Keeps the opened URLs, or fails like there is no browser over SSH
+struct FakeBrowser {
+    urls: Rc<RefCell<Vec<String>>>,
+    available: bool,
+}
+
+impl Browser for FakeBrowser {
+    fn open(&mut self, url: &str) -> std::io::Result<()> {
+        if !self.available {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "no browser"));
+        }
+        self.urls.borrow_mut().push(url.to_string());
+        Ok(())
+    }
+}
+
+fn app_with_webhook(fail: bool) -> (App, WebhookRequests) {
+    // Nothing is saved to a history
+    let options = AppOptions {
+        no_history: true,
+        webhook_url: Some("http://localhost/results".to_string()),
+        ..AppOptions::default()
+    };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let requests = Rc::new(RefCell::new(vec![]));
+    app.set_webhook_transport(Box::new(FakeWebhook { requests: requests.clone(), fail }));
+    (app, requests)
+}
+
+#[test]
+fn test_screen_after_setup() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+
+    let header = term.line(0);
+    assert!(header.starts_with(" ID:test "), "{}", header);
+    assert!(header.contains(" RSTYPE "), "{}", header);
+    assert!(header.ends_with("00:00 0.00 WPM"), "{}", header);
+    assert_eq!(term.line(2), "hello world");
+    assert_eq!(term.attributes_at(2, 0), pancurses::A_BOLD);
+    assert_eq!(term.get_cur_yx(), (2, 0));
+}
+
+#[test]
+fn test_screen_while_typing() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hellp");
+
+    // Correct characters are dimmed, the mistake is red and the rest is still bold
+    assert_eq!(term.line(2), "hello world");
+    assert_eq!(term.attributes_at(2, 3), pancurses::A_DIM);
+    assert_eq!(term.attributes_at(2, 4), chtype::from(ColorPair(2)));
+    assert_eq!(term.attributes_at(2, 6), pancurses::A_BOLD);
+    assert_eq!(term.get_cur_yx(), (2, 5));
+    // The progress bar below the text and the typed word below it
+    assert!(term.line(3).starts_with("[="), "{}", term.line(3));
+    assert!(term.line(3).ends_with(" 36%"), "{}", term.line(3));
+    assert_eq!(term.line(4), "hellp");
+
+    app.handle_input(&mut term, Some(Input::Character('\x17'))).unwrap();
+    assert_eq!(term.line(4), "");
+    assert_eq!(term.attributes_at(2, 0), pancurses::A_BOLD);
+    assert_eq!(term.get_cur_yx(), (2, 0));
+}
+
+#[test]
+fn test_arrows_switch_to_the_next_database_text_with_the_same_transforms() {
+    let transforms = TextTransforms { words: Some(5), ..TextTransforms::default() };
+    let prepared_text = transforms.apply(load_text_from_database(42, "data.db").unwrap()).unwrap();
+    let options = AppOptions { transforms, no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(prepared_text, options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    assert!(term.line(0).starts_with(" ID:42#5w "), "{}", term.line(0));
+
+    // The next text is cut to the same number of words
+    app.handle_input(&mut term, Some(Input::KeyRight)).unwrap();
+    assert!(term.line(0).starts_with(" ID:43#5w "), "{}", term.line(0));
+    assert_eq!(app.history_id(), "43#5w");
+    assert_eq!(app.session().original_text().split_whitespace().count(), 5);
+
+    // A file has no neighbours, the arrows leave it be
+    let options = AppOptions { text_source: TextSource::File, no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "notes.txt".to_string()), options);
+    app.initialize_windows(&mut term).unwrap();
+    app.handle_input(&mut term, Some(Input::KeyLeft)).unwrap();
+    assert_eq!(app.history_id(), "notes.txt");
+    assert_eq!(app.session().original_text(), "hello world");
+}
+
+#[test]
+fn test_screen_at_test_end() {
+    // Results are saved to a history of their own, the word stats go next to it
+    let directory = format!("tests/{}", Uuid::new_v4());
+    std::fs::create_dir(&directory).unwrap();
+    // A slow earlier result, for the test to beat
+    let history_path = std::path::Path::new(&directory).join("history.csv");
+    std::fs::write(&history_path, "ID,WPM,DATE,TIME,ACCURACY\ntest,1.00,2024-01-01,10:00:00,98.00\n").unwrap();
+
+    // Keys typed at once would be flagged as suspect, and suspect results beat nothing
+    let options = AppOptions { allow_fast_input: true, history_file: history_path.clone(), ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    // The best result of the text is shown before typing it
+    assert!(term.line(0).starts_with(" ID:test PB: 1.0 WPM "), "{}", term.line(0));
+    type_text(&mut app, &mut term, "hello world");
+
+    assert_eq!(term.line(2), "hello world");
+    assert!(term.line(3).ends_with(" 100%"), "{}", term.line(3));
+    assert!(term.line(4).starts_with(" Your typing speed is "), "{}", term.line(4));
+    assert_eq!(term.line(6), "  Enter  to see replay,  Tab  to retry,  C  to save a card.");
+    assert!(term.line(23).starts_with(" Words: "), "{}", term.line(23));
+    assert_eq!(term.cursor_visibility, 0);
+    assert!(term.line(5).starts_with(" New personal best! (+"), "{}", term.line(5));
+    assert_eq!(term.attributes_at(5, 1), chtype::from(ColorPair(1)));
+    let history = std::fs::read_to_string(&history_path).unwrap();
+    assert!(history.lines().last().unwrap().contains(",yes,"), "{}", history);
+    // The time of the old record isn't known, the new one took no time at all
+    assert!(term.line(5).ends_with(" Total practice: 0s"), "{}", term.line(5));
+
+    // Leaving from the end screen hands the results to the caller
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    assert!(
+        matches!(&exit, Some(ExitReason::Completed(stats)) if stats.text_id == "test" && stats.completed),
+        "{:?}", exit,
+    );
+
+    // Tab starts the test over, to beat the new best
+    let Some(ExitReason::Completed(stats)) = exit else {
+        unreachable!();
+    };
+    // The header shows the speed saved to history, of the chosen formula
+    assert!(term.line(0).ends_with(&format!("{:.2} WPM", stats.wpm)), "{}", term.line(0));
+    app.handle_input(&mut term, Some(Input::Character('\t'))).unwrap();
+    assert!(term.line(0).starts_with(&format!(" ID:test PB: {:.1} WPM ", stats.wpm)), "{}", term.line(0));
+    assert_eq!(term.line(4), "");
+    assert_eq!(term.attributes_at(2, 0), pancurses::A_BOLD);
+    assert_eq!(term.cursor_visibility, 1);
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[test]
+fn test_tab_retries_are_counted() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    let mut attempts = vec![];
+    for _ in 0..2 {
+        type_text(&mut app, &mut term, "hello world");
+        let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+        let Some(ExitReason::Completed(stats)) = exit else {
+            panic!("{:?}", exit);
+        };
+        attempts.push(stats.attempt);
+        assert_eq!(stats.text_hash, Some(rstype::history::text_hash("hello world")));
+        app.handle_input(&mut term, Some(Input::Character('\t'))).unwrap();
+    }
+    assert_eq!(attempts, vec![Some(1), Some(2)]);
+}
+
+#[test]
+fn test_too_narrow_terminal_shows_error() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    let mut term = FakeTerminal::new(24, 12);
+    // The error screen waits for a key
+    term.push_keys([Input::Character('q')]);
+
+    let result = app.run(&mut term);
+    assert!(matches!(result, Err(AppError::WindowTooNarrowError(_))));
+    assert!(term.screen().iter().any(|line| line.contains("narrow")));
+}
+
+#[test]
+fn test_leaving_mid_test_returns_stats_so_far() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hellp");
+
+    let exit = app.handle_input(&mut term, Some(Input::Character('\x03'))).unwrap();
+    let Some(ExitReason::Aborted(stats)) = exit else {
+        panic!("{:?}", exit);
+    };
+    assert_eq!(stats.text_id, "test");
+    assert_eq!(stats.chars_typed, 5);
+    assert_eq!(stats.errors, 1);
+    assert!(!stats.completed);
+}
+
+#[test]
+fn test_leaving_before_typing_returns_empty_stats() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    let Some(ExitReason::Aborted(stats)) = exit else {
+        panic!("{:?}", exit);
+    };
+    assert_eq!(stats.text_id, "test");
+    assert_eq!(stats.chars_typed, 0);
+}
+
+#[test]
+fn test_results_are_posted_to_webhook() {
+    let (mut app, requests) = app_with_webhook(false);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    let requests = requests.borrow();
+    assert_eq!(requests.len(), 1);
+    let (url, body) = &requests[0];
+    assert_eq!(url, "http://localhost/results");
+    let json: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(json["text_id"], "test");
+    assert_eq!(json["completed"], true);
+    assert_eq!(json["errors"], 0);
+    assert!(json["wpm"].is_f64());
+    assert!(json["timestamp"].is_string());
+    assert!(term.line(23).starts_with(" Words: "), "{}", term.line(23));
+    // The results are posted but left out of the history
+    assert_eq!(term.line(5), " Not recorded in history");
+    assert_eq!(term.attributes_at(5, 1), chtype::from(ColorPair(4)));
+}
+
+#[test]
+fn test_failed_webhook_only_warns() {
+    let (mut app, _) = app_with_webhook(true);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    // The end screen is shown with the warning in the status bar
+    assert!(term.line(4).starts_with(" Your typing speed is "), "{}", term.line(4));
+    assert_eq!(term.line(23), " Webhook request failed: connection refused");
+    assert_eq!(term.attributes_at(23, 1), chtype::from(ColorPair(4)));
+
+    // Retrying clears the warning
+    app.handle_input(&mut term, Some(Input::Character('\t'))).unwrap();
+    assert!(!term.line(23).contains("Webhook"), "{}", term.line(23));
+}
+
+#[test]
+fn test_saved_replay_is_played_without_the_database() {
+    let key_strokes: Vec<(Duration, Input)> = "hi yo".chars().enumerate()
+        .map(|(i, c)| (Duration::from_millis(10 * i as u64), Input::Character(c)))
+        .collect();
+    let replay = SavedReplay::new("7", "hi yo", &key_strokes);
+    let mut app = App::from_saved_replay(&replay, AppOptions::default());
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+
+    // The end screen is drawn again once the keys are played
+    app.handle_input(&mut term, Some(Input::Character('\n'))).unwrap();
+    assert_eq!(term.line(2), "hi yo");
+    assert!(term.line(4).starts_with(" Your typing speed is "), "{}", term.line(4));
+    // A replay is never saved, there is nothing to point out
+    assert_eq!(term.line(5), "");
+
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    let Some(ExitReason::Completed(stats)) = exit else {
+        panic!("{:?}", exit);
+    };
+    assert_eq!(stats.text_id, "7");
+    assert_eq!(stats.chars_typed, 5);
+    assert_eq!(stats.errors, 0);
+    assert!(stats.completed);
+}
+
+#[test]
+fn test_s_saves_the_replay() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let mut term = FakeTerminal::new(24, 80);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    app.handle_input(&mut term, Some(Input::Character('s'))).unwrap();
+    let status = term.line(23);
+    let path = status.trim()
+        .strip_prefix("Replay saved to ")
+        .and_then(|rest| rest.split(',').next())
+        .unwrap_or_else(|| panic!("{}", status))
+        .to_string();
+    let replay = load_replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let replay = replay.unwrap();
+    assert_eq!(replay.text_id, "test");
+    assert_eq!(replay.text, "hello world");
+    assert_eq!(replay.keys.iter().map(|(_, c)| c).collect::<String>(), "hello world");
+}
+
+#[test]
+fn test_replaying_twice_leaves_the_test_alone() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "helo\x7flo world");
+
+    let key_strokes = app.session().key_strokes().to_vec();
+    let mistyped_keys = app.session().mistyped_keys().clone();
+    let word_timings = app.session().word_timings().to_vec();
+    let counts = (app.session().raw_chars_typed(), app.session().error_count(), app.session().completed_words());
+    let stats = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    assert_eq!(term.attributes_at(2, 3), chtype::from(ColorPair(2)));
+
+    let mut replay_screen = None;
+    for _ in 0..2 {
+        app.handle_input(&mut term, Some(Input::Character('\n'))).unwrap();
+        assert_eq!(app.session().key_strokes(), key_strokes);
+        assert_eq!(app.session().mistyped_keys(), &mistyped_keys);
+        assert_eq!(app.session().word_timings(), word_timings);
+        assert_eq!(
+            (app.session().raw_chars_typed(), app.session().error_count(), app.session().completed_words()),
+            counts,
+        );
+        assert_eq!(app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap(), stats);
+        // Both replays end on the same screen, with the mistake marked
+        assert_eq!(term.screen(), *replay_screen.get_or_insert_with(|| term.screen()));
+        assert_eq!(term.attributes_at(2, 3), chtype::from(ColorPair(2)));
+    }
+}
+
+#[test]
+fn test_ctrl_y_copies_the_result() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let text = Rc::new(RefCell::new(None));
+    app.set_clipboard(Box::new(FakeClipboard { text: text.clone(), available: true }));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    app.handle_input(&mut term, Some(Input::Character('\x19'))).unwrap();
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    let Some(ExitReason::Completed(stats)) = exit else {
+        panic!("{:?}", exit);
+    };
+    assert_eq!(*text.borrow(), Some(format!("{} — rstype", stats.share_line())));
+    assert_eq!(term.line(23), " Copied!");
+    assert_eq!(term.attributes_at(23, 1), chtype::from(ColorPair(1)));
+}
+
+#[test]
+fn test_copy_without_clipboard_only_warns() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    app.set_clipboard(Box::new(FakeClipboard { text: Rc::new(RefCell::new(None)), available: false }));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    let exit = app.handle_input(&mut term, Some(Input::Character('\x19'))).unwrap();
+    assert!(exit.is_none());
+    assert_eq!(term.line(23), " Can't copy to the clipboard: no display");
+    assert_eq!(term.attributes_at(23, 1), chtype::from(ColorPair(4)));
+}
+
+#[test]
+fn test_ctrl_t_asks_where_to_share_with_several_targets() {
+    let options = AppOptions {
+        no_history: true,
+        share_targets: vec![
+            ShareTarget::new("Mastodon", "https://mastodon.social/share?text={text}"),
+            ShareTarget::new("Bluesky", "https://bsky.app/intent/compose?text={text}"),
+        ],
+        ..AppOptions::default()
+    };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    let stats_line = term.line(23);
+
+    app.handle_input(&mut term, Some(Input::Character('\x14'))).unwrap();
+    assert_eq!(term.line(23), " Share to: 1 Mastodon  2 Bluesky, any other key to cancel");
+
+    // Esc cancels the choice instead of leaving the end screen
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    assert!(exit.is_none());
+    assert_eq!(term.line(23), stats_line);
+    let exit = app.handle_input(&mut term, Some(Input::Character('\u{1b}'))).unwrap();
+    assert!(matches!(exit, Some(ExitReason::Completed(_))), "{:?}", exit);
+}
+
+#[test]
+fn test_ctrl_t_opens_the_share_url() {
+    let options = AppOptions { no_history: true, share_template: Some("{wpm} WPM".to_string()), ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let urls = Rc::new(RefCell::new(vec![]));
+    app.set_browser(Box::new(FakeBrowser { urls: urls.clone(), available: true }));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    let screen = term.screen();
+
+    app.handle_input(&mut term, Some(Input::Character('\x14'))).unwrap();
+    assert_eq!(urls.borrow().len(), 1);
+    assert!(urls.borrow()[0].starts_with("https://twitter.com/intent/tweet?text="), "{:?}", urls);
+    assert_eq!(term.screen(), screen);
+}
+
+#[test]
+fn test_share_url_is_shown_without_a_browser() {
+    let template = "a long share template that wraps ".repeat(3);
+    let options = AppOptions { no_history: true, share_template: Some(template.clone()), ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    app.set_browser(Box::new(FakeBrowser { urls: Rc::new(RefCell::new(vec![])), available: false }));
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    let screen = term.screen();
+
+    let exit = app.handle_input(&mut term, Some(Input::Character('\x14'))).unwrap();
+    assert!(exit.is_none());
+    assert_eq!(term.line(23), " Can't open the browser for Twitter: no browser");
+    assert_eq!(term.attributes_at(23, 1), chtype::from(ColorPair(4)));
+    assert_eq!(term.line(9), " Can't open the browser, copy the link manually:");
+    // The URL is wrapped to the window and nothing of it is cut off
+    let url: String = (10..23).map(|row| term.line(row)).collect();
+    assert!(url.starts_with("https://twitter.com/intent/tweet?text=a%20long%20share"), "{}", url);
+    assert!(url.ends_with("wraps%20"), "{}", url);
+    assert_eq!(term.line(10).chars().count(), 60);
+
+    // Any other action brings the end screen back
+    app.handle_input(&mut term, Some(Input::Character('h'))).unwrap();
+    app.handle_input(&mut term, Some(Input::Character('h'))).unwrap();
+    assert_eq!(term.screen(), screen);
+}
+
+#[test]
+fn test_c_saves_and_copies_the_result_card() {
+    let options = AppOptions { no_history: true, ..AppOptions::default() };
+    let mut app = App::with_options(("hello world".to_string(), "test".to_string()), options);
+    let text = Rc::new(RefCell::new(None));
+    app.set_clipboard(Box::new(FakeClipboard { text: text.clone(), available: true }));
+    let mut term = FakeTerminal::new(24, 80);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+
+    app.handle_input(&mut term, Some(Input::Character('c'))).unwrap();
+    let status = term.line(23);
+    let path = status.trim()
+        .strip_prefix("Card copied and saved to ")
+        .unwrap_or_else(|| panic!("{}", status))
+        .to_string();
+    let saved = std::fs::read_to_string(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let copied = text.borrow().clone().unwrap();
+    assert_eq!(saved.unwrap(), format!("{}\n", copied));
+    assert!(copied.lines().any(|line| line.starts_with("│ Text ") && line.ends_with(" test │")), "{}", copied);
+}
+
+#[test]
+fn test_challenge_is_compared_and_added_to_the_file() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let challenger = SessionStats { wpm: 1.0, accuracy: 90.0, duration: 30.0, ..SessionStats::default() };
+    save_challenge(&path, &Challenge::new("7", "hello world", Some(3), &challenger)).unwrap();
+    let options = AppOptions {
+        no_history: true,
+        challenge: Some((path.clone().into(), load_challenge(&path).unwrap())),
+        ..AppOptions::default()
+    };
+    let mut app = App::with_options(("hello world".to_string(), "7".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    // The arrows don't leave the text of the challenge
+    app.handle_input(&mut term, Some(Input::KeyRight)).unwrap();
+    assert_eq!(app.history_id(), "7");
+    type_text(&mut app, &mut term, "hello world");
+    let challenge = load_challenge(&path);
+
+    assert_eq!(term.line(9), " Challenge        Them        You");
+    assert!(term.line(10).starts_with(" WPM              1.00 "), "{}", term.line(10));
+    assert_eq!(term.line(11), " Accuracy        90.0%     100.0%");
+    let challenge = challenge.unwrap();
+    assert_eq!(challenge.results.len(), 2);
+    assert_eq!(challenge.results[1].accuracy, 100.0);
+
+    // A retry with Tab isn't added to the file again
+    app.handle_input(&mut term, Some(Input::Character('\t'))).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    let challenge = load_challenge(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(challenge.unwrap().results.len(), 2);
+}
+
+#[test]
+fn test_export_challenge_writes_the_text_and_result() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let options = AppOptions {
+        no_history: true,
+        export_challenge: Some(path.clone().into()),
+        seed: Some(3),
+        ..AppOptions::default()
+    };
+    let mut app = App::with_options(("hello world".to_string(), "7".to_string()), options);
+    let mut term = FakeTerminal::new(24, 60);
+    app.initialize_windows(&mut term).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    let exported = std::fs::read_to_string(&path);
+    // A retry with Tab doesn't replace the exported result
+    app.handle_input(&mut term, Some(Input::Character('\t'))).unwrap();
+    type_text(&mut app, &mut term, "hello world");
+    assert_eq!(std::fs::read_to_string(&path).ok(), exported.ok());
+    let challenge = load_challenge(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let challenge = challenge.unwrap();
+    assert_eq!((challenge.text_id.as_str(), challenge.text.as_str(), challenge.seed), ("7", "hello world", Some(3)));
+    assert_eq!(challenge.results.len(), 1);
+    assert!(challenge.challenger().completed);
+}
@@ -0,0 +1,96 @@
+use pancurses::Input;
+use rstype::replay::{load_replay, save_replay, ReplayError, SavedReplay, REPLAY_VERSION};
+use std::time::Duration;
+use uuid::Uuid;
+
+fn keys(keys: &[(u64, Input)]) -> Vec<(Duration, Input)> {
+    keys.iter()
+        .map(|(millis, key)| (Duration::from_millis(*millis), *key))
+        .collect()
+}
+
+#[test]
+fn test_keys_are_saved_as_delays() {
+    let key_strokes = keys(&[
+        (1500, Input::Character('h')),
+        (1620, Input::Character('x')),
+        (1800, Input::KeyBackspace),
+        (1950, Input::Character('i')),
+    ]);
+    let replay = SavedReplay::new("7", "hi", &key_strokes);
+
+    assert_eq!(replay.version, REPLAY_VERSION);
+    assert_eq!(replay.keys, vec![(1500, 'h'), (120, 'x'), (180, '\x7f'), (150, 'i')]);
+    // Backspace comes back as the character the app takes for it
+    assert_eq!(replay.key_strokes()[2], (Duration::from_millis(1800), Input::Character('\x7f')));
+    assert_eq!(replay.key_strokes()[3].0, Duration::from_millis(1950));
+}
+
+#[test]
+fn test_keys_that_type_nothing_are_left_out() {
+    let key_strokes = keys(&[
+        (100, Input::Character('a')),
+        (200, Input::KeyResize),
+        (300, Input::KeyLeft),
+        (400, Input::Character('b')),
+    ]);
+    let replay = SavedReplay::new("7", "ab", &key_strokes);
+
+    // The delay of the next key covers the time of the keys left out
+    assert_eq!(replay.keys, vec![(100, 'a'), (300, 'b')]);
+}
+
+#[test]
+fn test_save_and_load_replay() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let replay = SavedReplay::new("42", "héllo wörld", &keys(&[(250, Input::Character('h'))]));
+
+    save_replay(&path, &replay).unwrap();
+    let loaded = load_replay(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded, replay);
+}
+
+#[test]
+fn test_other_version_is_refused() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let mut replay = SavedReplay::new("42", "hello", &[]);
+    replay.version = REPLAY_VERSION + 1;
+    save_replay(&path, &replay).unwrap();
+
+    let result = load_replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        matches!(&result, Err(ReplayError::UnsupportedVersion(error_path, version)) if *error_path == path && *version == REPLAY_VERSION + 1),
+        "{:?}", result,
+    );
+}
+
+#[test]
+fn test_changed_text_is_refused() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    let mut replay = SavedReplay::new("42", "hello", &[]);
+    replay.text = "jello".to_string();
+    save_replay(&path, &replay).unwrap();
+
+    let result = load_replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(&result, Err(ReplayError::TextMismatch(error_path)) if *error_path == path), "{:?}", result);
+}
+
+#[test]
+fn test_missing_or_invalid_file_names_the_file() {
+    let path = format!("tests/{}.json", Uuid::new_v4());
+    assert!(matches!(load_replay(&path), Err(ReplayError::IoError(error_path, _)) if error_path == path));
+
+    std::fs::write(&path, "{\"version\": 1}").unwrap();
+    let result = load_replay(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    let error = result.unwrap_err();
+    assert!(matches!(&error, ReplayError::JsonError(error_path, _) if *error_path == path));
+    assert!(rstype::AppError::from(error).to_string().contains(&path));
+}
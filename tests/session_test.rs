@@ -0,0 +1,756 @@
+use pancurses::Input;
+use rstype::calculations::WpmFormula;
+use rstype::session::{SessionRules, SessionStats, SimulatedKey, TextSource, TypingSession};
+use std::time::Duration;
+
+fn type_text(session: &mut TypingSession, text: &str) {
+    for c in text.chars() {
+        session.press(c);
+    }
+}
+
+#[test]
+fn test_typing_non_ascii_text() {
+    let mut session = TypingSession::new("Ça va, café? Привіт світ");
+
+    type_text(&mut session, "Ça va, café? Привіт");
+    assert!(!session.is_complete());
+
+    type_text(&mut session, " світ");
+    assert!(session.is_complete());
+}
+
+#[test]
+fn test_correcting_non_ascii_text() {
+    let mut session = TypingSession::new("naïve résumé");
+
+    type_text(&mut session, "naïve rés");
+    // Mistype a multibyte character and correct it
+    type_text(&mut session, "ü");
+    session.backspace();
+    type_text(&mut session, "umé");
+    assert!(session.is_complete());
+}
+
+#[test]
+fn test_accuracy_with_corrections() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hx");
+    session.backspace();
+    type_text(&mut session, "ello world");
+    assert!(session.is_complete());
+
+    // 12 characters typed, one of them wrong
+    assert_eq!(session.error_count(), 1);
+    assert_eq!(session.current_accuracy(), 11.0 / 12.0 * 100.0);
+}
+
+#[test]
+fn test_accuracy_without_mistakes() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hello world");
+    assert_eq!(session.error_count(), 0);
+    assert_eq!(session.current_accuracy(), 100.0);
+}
+
+#[test]
+fn test_mistake_is_recorded_once() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hx");
+    // Keys that don't change the typed text must not record the mistake again
+    for _ in 0..5 {
+        session.process_key(&Input::KeyUp);
+    }
+    session.backspace();
+    type_text(&mut session, "ello world");
+
+    assert!(session.is_complete());
+    assert_eq!(session.error_count(), 1);
+    assert_eq!(session.mistakes_at(1), 1);
+}
+
+#[test]
+fn test_repeated_mistakes_at_same_position() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hx");
+    session.backspace();
+    type_text(&mut session, "y");
+    session.backspace();
+    type_text(&mut session, "ello world");
+
+    assert_eq!(session.error_count(), 1);
+    assert_eq!(session.mistakes_at(1), 2);
+    assert_eq!(session.mistakes_at(0), 0);
+}
+
+#[test]
+fn test_completed_words() {
+    let mut session = TypingSession::new("one two three");
+
+    type_text(&mut session, "one twx");
+    assert_eq!(session.completed_words(), 1);
+
+    // A wrong word followed by a space is not completed
+    type_text(&mut session, " ");
+    assert_eq!(session.completed_words(), 1);
+
+    for _ in 0..2 {
+        session.backspace();
+    }
+    type_text(&mut session, "o three");
+    assert_eq!(session.completed_words(), 3);
+}
+
+#[test]
+fn test_word_timings() {
+    let mut session = TypingSession::new("the quick fox");
+
+    // Type one character every 100ms, pausing for a second before "fox"
+    let mut elapsed = Duration::ZERO;
+    for c in "the quick fox".chars() {
+        if c == 'f' {
+            elapsed += Duration::from_secs(1);
+        }
+        session.record_key(&Input::Character(c), elapsed);
+        session.press(c);
+        elapsed += Duration::from_millis(100);
+    }
+
+    let timings = session.word_timings();
+    let words: Vec<&str> = timings.iter().map(|(word, _)| word.as_str()).collect();
+    assert_eq!(words, vec!["the", "quick", "fox"]);
+    assert!((timings[0].1 - 0.3).abs() < 1e-9);
+    assert!((timings[1].1 - 0.6).abs() < 1e-9);
+    assert!((timings[2].1 - 1.3).abs() < 1e-9);
+}
+
+#[test]
+fn test_raw_and_net_chars_with_backspace() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hx");
+    session.backspace();
+    type_text(&mut session, "ello world");
+
+    // The erased "x" counts toward raw but not net
+    assert_eq!(session.raw_chars_typed(), 12);
+    assert_eq!(session.net_chars_typed(), 11);
+}
+
+#[test]
+fn test_raw_and_net_chars_with_ctrl_backspace() {
+    let mut session = TypingSession::new("hello world");
+
+    // Ctrl+Backspace erases the whole mistyped word
+    type_text(&mut session, "hello wrld");
+    session.erase_word();
+    type_text(&mut session, "world");
+
+    assert!(session.is_complete());
+    // All 4 erased characters count toward raw but not net
+    assert_eq!(session.raw_chars_typed(), 15);
+    assert_eq!(session.net_chars_typed(), 11);
+}
+
+#[test]
+fn test_ctrl_h_erases_word() {
+    let mut session = TypingSession::new("hello world");
+
+    // Most terminals send Ctrl+H for Ctrl+Backspace
+    type_text(&mut session, "hello wrld");
+    session.process_key(&Input::Character('\x08'));
+    type_text(&mut session, "world");
+
+    assert!(session.is_complete());
+    assert_eq!(session.raw_chars_typed(), 15);
+}
+
+#[test]
+fn test_ctrl_u_clears_current_word() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hello wox");
+    session.clear_word();
+    assert_eq!(session.typed_text(), "hello ");
+
+    // Nothing left to clear, the accepted word stays
+    session.clear_word();
+    assert_eq!(session.typed_text(), "hello ");
+
+    type_text(&mut session, "world");
+    assert!(session.is_complete());
+    assert_eq!(session.raw_chars_typed(), 14);
+    assert_eq!(session.net_chars_typed(), 11);
+}
+
+#[test]
+fn test_ctrl_u_after_mistyped_word_clears_past_the_space() {
+    let mut session = TypingSession::new("hello world");
+
+    // The mistyped word isn't accepted, so the text after its space is part of the same word
+    type_text(&mut session, "hellp wor");
+    session.clear_word();
+    assert_eq!(session.typed_text(), "");
+
+    type_text(&mut session, "hello world");
+    assert!(session.is_complete());
+    assert_eq!(session.net_chars_typed(), 11);
+}
+
+#[test]
+fn test_ctrl_u_counts_like_backspaces() {
+    let mut cleared = TypingSession::new("hello world");
+    type_text(&mut cleared, "hellp wor");
+    cleared.clear_word();
+    type_text(&mut cleared, "hello world");
+
+    let mut erased = TypingSession::new("hello world");
+    type_text(&mut erased, "hellp wor");
+    for _ in 0.."hellp wor".len() {
+        erased.backspace();
+    }
+    type_text(&mut erased, "hello world");
+
+    assert_eq!(cleared.raw_chars_typed(), erased.raw_chars_typed());
+    assert_eq!(cleared.net_chars_typed(), erased.net_chars_typed());
+    assert_eq!(cleared.error_count(), erased.error_count());
+}
+
+#[test]
+fn test_ctrl_backspace_after_mistyped_word_erases_space() {
+    let mut session = TypingSession::new("hello world");
+
+    type_text(&mut session, "hellp ");
+    session.erase_word();
+    session.backspace();
+    type_text(&mut session, "o world");
+
+    assert!(session.is_complete());
+    assert_eq!(session.raw_chars_typed(), 13);
+    assert_eq!(session.net_chars_typed(), 11);
+}
+
+#[test]
+fn test_progress_timeline_replays_recorded_keys() {
+    let mut session = TypingSession::new("hi you");
+
+    let keys = [
+        Input::Character('h'),
+        Input::Character('o'),
+        Input::KeyBackspace,
+        Input::Character('i'),
+        Input::Character(' '),
+    ];
+    for (i, key) in keys.iter().enumerate() {
+        session.record_key(key, Duration::from_millis(100 * i as u64));
+        session.process_key(key);
+    }
+
+    assert_eq!(session.progress_timeline(), vec![
+        (Duration::from_millis(0), 1),
+        (Duration::from_millis(100), 1),
+        (Duration::from_millis(200), 1),
+        (Duration::from_millis(300), 2),
+        (Duration::from_millis(400), 3),
+    ]);
+}
+
+#[test]
+fn test_endless_pages_keep_counting() {
+    let mut session = TypingSession::new("one two");
+
+    type_text(&mut session, "onr");
+    session.backspace();
+    type_text(&mut session, "e two");
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 2);
+    assert_eq!(session.error_count(), 1);
+
+    session.start_next_page("three four five");
+    assert!(!session.is_complete());
+    assert_eq!(session.completed_words(), 2);
+    assert_eq!(session.mistakes_at(2), 0);
+
+    type_text(&mut session, "three fp");
+    assert_eq!(session.completed_words(), 3);
+    assert_eq!(session.error_count(), 2);
+
+    session.start_next_page("six");
+    assert_eq!(session.completed_words(), 3);
+    assert_eq!(session.error_count(), 2);
+}
+
+#[test]
+fn test_skipped_words_of_earlier_pages_are_taken_off_once() {
+    let rules = SessionRules { skip_wrong_words: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("aa bb", rules);
+
+    type_text(&mut session, "xx bb");
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 1);
+
+    session.start_next_page("cc dd");
+    assert_eq!(session.completed_words(), 1);
+    type_text(&mut session, "cc dd");
+    assert_eq!(session.completed_words(), 3);
+
+    session.start_next_page("ee");
+    type_text(&mut session, "ee");
+    assert_eq!(session.completed_words(), 4);
+    // The characters of the skipped word are still left out
+    assert_eq!(session.net_chars_typed(), 10);
+}
+
+#[test]
+fn test_key_strokes_are_capped() {
+    let mut session = TypingSession::new("a");
+
+    for i in 0..10_005 {
+        session.record_key(&Input::Character('a'), Duration::from_millis(i));
+    }
+
+    let timeline = session.progress_timeline();
+    assert_eq!(timeline.len(), 10_000);
+    // The oldest keys were dropped
+    assert_eq!(timeline[0].0, Duration::from_millis(5));
+}
+
+#[test]
+fn test_strict_mode_refuses_mistakes() {
+    let rules = SessionRules { strict: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hi you", rules);
+
+    type_text(&mut session, "hx");
+    assert_eq!(session.typed_text(), "h");
+    type_text(&mut session, "zi");
+    assert_eq!(session.typed_text(), "hi");
+    // A letter where the space belongs is refused too
+    type_text(&mut session, "y you");
+    assert_eq!(session.typed_text(), "hi you");
+
+    assert!(session.is_complete());
+    assert_eq!(session.error_count(), 2);
+    assert_eq!(session.mistakes_at(1), 2);
+    assert_eq!(session.mistakes_at(2), 1);
+    assert_eq!(session.raw_chars_typed(), 9);
+}
+
+#[test]
+fn test_strict_mode_backspace_still_works() {
+    let rules = SessionRules { strict: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hi", rules);
+
+    type_text(&mut session, "h");
+    session.backspace();
+    assert_eq!(session.typed_text(), "");
+    type_text(&mut session, "hi");
+    assert!(session.is_complete());
+}
+
+#[test]
+fn test_wrong_word_blocks_by_default() {
+    let mut session = TypingSession::new("one two");
+
+    type_text(&mut session, "onx two");
+    // The wrong word has to be fixed before the next one is accepted
+    assert_eq!(session.typed_text(), "onx two");
+    assert_eq!(session.completed_words(), 0);
+    assert!(!session.is_complete());
+}
+
+#[test]
+fn test_skip_wrong_word() {
+    let rules = SessionRules { skip_wrong_words: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two three", rules);
+
+    type_text(&mut session, "onx ");
+    assert_eq!(session.typed_text(), "one ");
+    type_text(&mut session, "twoo ");
+    assert_eq!(session.typed_text(), "one two ");
+    type_text(&mut session, "three");
+
+    assert!(session.is_complete());
+    // Only the correct word counts
+    assert_eq!(session.completed_words(), 1);
+    // "x" in the first word, the extra "o" of the second one isn't a position of the text
+    assert_eq!(session.error_count(), 1);
+    assert_eq!(session.mistakes_at(2), 1);
+    assert_eq!(session.mistakes_at(7), 0);
+    assert_eq!(session.raw_chars_typed(), 14);
+    assert_eq!(session.net_chars_typed(), 7);
+}
+
+#[test]
+fn test_skip_short_final_word() {
+    let rules = SessionRules { skip_wrong_words: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two", rules);
+
+    type_text(&mut session, "one tw ");
+
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 1);
+    // The missing "o" is a mistake
+    assert_eq!(session.mistakes_at(6), 1);
+    assert_eq!(session.error_count(), 1);
+}
+
+#[test]
+fn test_backtrack_fixes_skipped_word() {
+    let rules = SessionRules { skip_wrong_words: true, backtrack: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two", rules);
+
+    type_text(&mut session, "onx ");
+    assert_eq!(session.typed_text(), "one ");
+    assert_eq!(session.completed_words(), 0);
+
+    // The first backspace steps back over the space, the second one erases the "x"
+    session.backspace();
+    assert_eq!(session.typed_text(), "onx");
+    session.backspace();
+    type_text(&mut session, "e two");
+
+    assert!(session.is_complete());
+    assert_eq!(session.typed_text(), "one two");
+    assert_eq!(session.completed_words(), 2);
+    assert_eq!(session.net_chars_typed(), 7);
+}
+
+#[test]
+fn test_backtrack_into_correct_word_across_padding() {
+    let rules = SessionRules { backtrack: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two", rules);
+    let mut elapsed = Duration::ZERO;
+    let mut press = |session: &mut TypingSession, key: Input| {
+        elapsed += Duration::from_millis(200);
+        session.record_key(&key, elapsed);
+        session.process_key(&key);
+    };
+
+    "one ".chars().for_each(|c| press(&mut session, Input::Character(c)));
+    press(&mut session, Input::KeyBackspace);
+    assert_eq!(session.typed_text(), "one");
+    assert_eq!(session.completed_words(), 0);
+    " two".chars().for_each(|c| press(&mut session, Input::Character(c)));
+
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 2);
+    // The word accepted twice is only timed once
+    let words: Vec<&str> = session.word_timings().iter().map(|(word, _)| word.as_str()).collect();
+    assert_eq!(words, vec!["one", "two"]);
+}
+
+#[test]
+fn test_backspace_stops_at_word_start_by_default() {
+    let mut session = TypingSession::new("one two");
+
+    type_text(&mut session, "one ");
+    session.backspace();
+    assert_eq!(session.typed_text(), "one ");
+}
+
+#[test]
+fn test_ctrl_n_skips_current_word() {
+    let mut session = TypingSession::new("one tw© three");
+
+    type_text(&mut session, "one tw");
+    session.skip_word();
+    assert_eq!(session.typed_text(), "one tw© ");
+    type_text(&mut session, "three");
+
+    assert!(session.is_complete());
+    assert_eq!(session.words_skipped_by_key(), 1);
+    assert_eq!(session.completed_words(), 2);
+    // The untyped character is marked, but isn't a mistake by default
+    assert!(session.is_skipped_at(6));
+    assert!(!session.is_skipped_at(5));
+    assert_eq!(session.error_count(), 0);
+}
+
+#[test]
+fn test_backtrack_into_word_skipped_by_key() {
+    let rules = SessionRules { backtrack: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two three", rules);
+
+    type_text(&mut session, "one tw");
+    session.skip_word();
+    assert_eq!(session.words_skipped_by_key(), 1);
+    assert_eq!(session.completed_words(), 1);
+    // Going back into the skipped word and finishing it undoes the skip
+    session.backspace();
+    assert_eq!(session.typed_text(), "one tw");
+    type_text(&mut session, "o ");
+    assert_eq!(session.words_skipped_by_key(), 0);
+    assert!(!session.is_skipped_at(6));
+
+    // A word skipped once it was already typed right is still a skip, until it is accepted
+    type_text(&mut session, "three");
+    session.skip_word();
+    assert_eq!(session.words_skipped_by_key(), 1);
+    assert_eq!(session.completed_words(), 2);
+    session.backspace();
+    assert_eq!(session.typed_text(), "one two three");
+    assert_eq!(session.words_skipped_by_key(), 0);
+    assert_eq!(session.completed_words(), 3);
+    assert_eq!(session.net_chars_typed(), 13);
+}
+
+#[test]
+fn test_skipped_word_counted_as_errors() {
+    let rules = SessionRules { count_skipped_as_errors: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("one two", rules);
+
+    type_text(&mut session, "one tx");
+    session.skip_word();
+
+    assert!(session.is_complete());
+    assert_eq!(session.completed_words(), 1);
+    // "x" was already a mistake, the untyped "o" is one now too
+    assert_eq!(session.error_count(), 2);
+    assert!(session.is_skipped_at(5) && session.is_skipped_at(6));
+}
+
+#[test]
+fn test_mistyped_words() {
+    let mut session = TypingSession::new("one two one");
+    // A mistake in "two", one on the space after it and one in the second "one"
+    type_text(&mut session, "one twx");
+    for fixed in ["ox", " ox", "ne"] {
+        session.backspace();
+        type_text(&mut session, fixed);
+    }
+    assert!(session.is_complete());
+    assert_eq!(session.mistyped_words(), vec![("one".to_string(), 1), ("two".to_string(), 2)]);
+
+    // Mistakes of earlier pages are kept
+    session.start_next_page("two");
+    type_text(&mut session, "x");
+    session.backspace();
+    type_text(&mut session, "two");
+    assert_eq!(session.mistyped_words(), vec![("one".to_string(), 1), ("two".to_string(), 3)]);
+}
+
+#[test]
+fn test_strict_mode_press_reports_refused_keys() {
+    let rules = SessionRules { strict: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hi", rules);
+
+    assert!(session.press('h'));
+    assert!(!session.press('x'));
+    assert!(session.press('i'));
+    assert!(session.is_complete());
+}
+
+#[test]
+fn test_commit_word_types_wrapping_padding() {
+    let mut session = TypingSession::new("hello world");
+    // "world" doesn't fit after "hello" on 8 columns, it starts on the next line
+    session.wrap_text(8);
+    assert_eq!(session.text(), "hello   world");
+    assert_eq!(session.original_text(), "hello world");
+
+    type_text(&mut session, "hello");
+    session.commit_word();
+    assert_eq!(session.typed_text(), "hello   ");
+    type_text(&mut session, "world");
+    assert!(session.is_complete());
+    assert_eq!(session.raw_chars_typed(), 11);
+}
+
+#[test]
+fn test_finish() {
+    let mut session = TypingSession::new("hi you");
+    for (i, c) in "hx".chars().enumerate() {
+        session.record_key(&Input::Character(c), Duration::from_secs(i as u64 + 1));
+        session.press(c);
+    }
+    session.record_key(&Input::KeyBackspace, Duration::from_secs(3));
+    session.backspace();
+    for (i, c) in "i you".chars().enumerate() {
+        session.record_key(&Input::Character(c), Duration::from_secs(i as u64 + 4));
+        session.press(c);
+    }
+
+    let stats = session.finish("7", Duration::from_secs(12));
+    assert_eq!(stats.text_id, "7");
+    assert!(stats.completed);
+    // 2 words in a fifth of a minute, reported by default
+    assert!((stats.words_wpm - 10.0).abs() < 1e-9);
+    assert_eq!(stats.wpm, stats.words_wpm);
+    // 7 characters and 1 mistake in a fifth of a minute, 7 gross WPM less 5 mistakes per minute
+    assert!((stats.raw_wpm - 7.0).abs() < 1e-9);
+    assert!((stats.net_wpm - 2.0).abs() < 1e-9);
+    assert_eq!(stats.duration, 12.0);
+    assert_eq!(stats.chars_typed, 7);
+    assert_eq!(stats.errors, 1);
+    // 7 characters typed, one of them wrong
+    assert_eq!(stats.accuracy, 6.0 / 7.0 * 100.0);
+    assert_eq!(stats.timeline.len(), 8);
+    assert_eq!(stats.timeline.last(), Some(&(Duration::from_secs(8), 6)));
+    assert!(stats.latency.is_some());
+}
+
+#[test]
+fn test_finished_session_is_not_timed_again() {
+    let mut session = TypingSession::new("hi");
+    for (i, c) in "hi".chars().enumerate() {
+        session.record_key(&Input::Character(c), Duration::from_millis(100 * i as u64));
+        session.press(c);
+    }
+    session.finish("1", Duration::from_secs(1));
+
+    // Typing the text again for a replay keeps the stats of the session
+    session.rewind();
+    assert_eq!(session.typed_text(), "");
+    type_text(&mut session, "hi");
+    assert_eq!(session.word_timings().len(), 1);
+}
+
+#[test]
+fn test_replay_copy_starts_from_nothing_typed() {
+    let rules = SessionRules { strict: true, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hello world", rules);
+    session.wrap_text(8);
+    type_text(&mut session, "hx");
+
+    let mut copy = session.replay_copy();
+    assert_eq!(copy.typed_text(), "");
+    assert_eq!(copy.text(), session.text());
+    assert!(copy.key_strokes().is_empty());
+    assert!(copy.mistyped_keys().is_empty());
+    // The copy keeps the rules, and typing on it leaves the session alone
+    assert!(!copy.press('x'));
+    assert_eq!(copy.error_count(), 1);
+    assert_eq!(session.error_count(), 1);
+    assert_eq!(session.typed_text(), "h");
+}
+
+#[test]
+fn test_wpm_formula_picks_reported_speed() {
+    let rules = SessionRules { wpm_formula: WpmFormula::Gross, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hi", rules);
+    type_text(&mut session, "hx");
+    session.backspace();
+    type_text(&mut session, "i");
+
+    let stats = session.finish("1", Duration::from_secs(60));
+    assert_eq!(stats.wpm, stats.raw_wpm);
+    assert!((stats.raw_wpm - 0.6).abs() < 1e-9);
+
+    // Mistakes left in the text lower the net speed, down to 0
+    let rules = SessionRules { wpm_formula: WpmFormula::Net, ..SessionRules::default() };
+    let mut session = TypingSession::with_rules("hi", rules);
+    type_text(&mut session, "xy");
+    let stats = session.finish("1", Duration::from_secs(60));
+    assert!((stats.raw_wpm - 0.4).abs() < 1e-9);
+    assert_eq!(stats.wpm, 0.0);
+}
+
+#[test]
+fn test_summary_line() {
+    let mut session = TypingSession::new("hi");
+    type_text(&mut session, "hi");
+    let stats = session.finish("42", Duration::from_secs(30));
+
+    assert_eq!(
+        stats.to_string(),
+        "42: 2.00 WPM, 0.80 raw WPM, 100.00% accuracy, 0 errors, 2 characters in 30.0s",
+    );
+}
+
+#[test]
+fn test_share_line() {
+    let stats = SessionStats { wpm: 72.44, accuracy: 97.06, duration: 53.6, ..SessionStats::default() };
+    assert_eq!(stats.share_line(), "72.4 WPM, 97.1% accuracy, 54s");
+}
+
+#[test]
+fn test_stats_as_json() {
+    let mut session = TypingSession::new("hi");
+    type_text(&mut session, "hx");
+    session.backspace();
+    type_text(&mut session, "i");
+    let stats = session.finish("42", Duration::from_secs(30));
+
+    let json = serde_json::to_value(&stats).unwrap();
+    assert_eq!(json["text_id"], "42");
+    assert_eq!(json["wpm"], stats.wpm);
+    assert_eq!(json["raw_wpm"], stats.raw_wpm);
+    assert_eq!(json["accuracy"], stats.accuracy);
+    assert_eq!(json["duration"], 30.0);
+    assert_eq!(json["errors"], 1);
+    assert_eq!(json["completed"], true);
+    assert_eq!(json["time_limit"], serde_json::Value::Null);
+    // ISO-8601 with the UTC offset
+    let timestamp = json["timestamp"].as_str().unwrap();
+    assert_eq!(chrono::DateTime::parse_from_rfc3339(timestamp).unwrap(), stats.timestamp);
+    // The per-key details stay out of the summary
+    assert!(json.get("timeline").is_none());
+    assert!(json.get("latency").is_none());
+}
+
+#[test]
+fn test_simulate_checks_keys_against_text() {
+    let keys: Vec<(Duration, Input)> = [
+        (100, Input::Character('h')),
+        (200, Input::Character('x')),
+        (300, Input::Character('\x7f')),
+        (400, Input::Character('i')),
+        (500, Input::Character(' ')),
+        (600, Input::Character('y')),
+    ].into_iter()
+        .map(|(millis, key)| (Duration::from_millis(millis), key))
+        .collect();
+
+    let simulated = TypingSession::simulate("hi yo", SessionRules::default(), &keys);
+    let checked: Vec<(Option<char>, Option<bool>)> = simulated.iter()
+        .map(|key| (key.expected, key.correct))
+        .collect();
+    assert_eq!(checked, vec![
+        (Some('h'), Some(true)),
+        (Some('i'), Some(false)),
+        // Editing keys aren't checked
+        (None, None),
+        (Some('i'), Some(true)),
+        (Some(' '), Some(true)),
+        (Some('y'), Some(true)),
+    ]);
+    assert_eq!(simulated[1], SimulatedKey {
+        time: Duration::from_millis(200),
+        key: Input::Character('x'),
+        expected: Some('i'),
+        correct: Some(false),
+    });
+}
+
+#[test]
+fn test_simulated_keys_match_the_session() {
+    let mut session = TypingSession::new("hi yo");
+    for (millis, c) in [(100, 'h'), (250, 'o'), (400, '\x7f'), (500, 'i')] {
+        let key = Input::Character(c);
+        session.record_key(&key, Duration::from_millis(millis));
+        session.process_key(&key);
+    }
+
+    let simulated = session.simulated_keys();
+    assert_eq!(simulated.len(), 4);
+    assert_eq!(simulated[1].correct, Some(false));
+    assert_eq!(simulated[3].time, Duration::from_millis(500));
+    assert_eq!(simulated[3].correct, Some(true));
+}
+
+#[test]
+fn test_text_source_of_legacy_ids() {
+    assert_eq!(TextSource::guess("42"), TextSource::Database);
+    assert_eq!(TextSource::guess("daily-2024-03-01"), TextSource::Database);
+    assert_eq!(TextSource::guess("drill:th"), TextSource::Drill);
+    assert_eq!(TextSource::guess("lesson:3"), TextSource::Drill);
+    assert_eq!(TextSource::guess("tests/data/text.txt"), TextSource::File);
+
+    assert_eq!(TextSource::from_name("db"), Some(TextSource::Database));
+    assert_eq!(TextSource::from_name(TextSource::Drill.name()), Some(TextSource::Drill));
+    assert_eq!(TextSource::from_name("web"), None);
+}
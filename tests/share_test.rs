@@ -0,0 +1,78 @@
+use rstype::session::SessionStats;
+use rstype::share::{
+    check_share_target, check_template, default_share_targets, fill_template, ShareTarget, TemplateError,
+    DEFAULT_COPY_TEMPLATE, DEFAULT_SHARE_TEMPLATE, TEMPLATE_PLACEHOLDERS,
+};
+
+fn stats() -> SessionStats {
+    SessionStats {
+        text_id: "42".to_string(),
+        wpm: 72.44,
+        accuracy: 97.06,
+        duration: 53.6,
+        ..SessionStats::default()
+    }
+}
+
+#[test]
+fn test_fill_template() {
+    assert_eq!(
+        fill_template("{wpm} WPM on text {text_id}, {accuracy}% in {time}s", &stats()).unwrap(),
+        "72.4 WPM on text 42, 97.1% in 54s",
+    );
+    assert_eq!(fill_template(DEFAULT_COPY_TEMPLATE, &stats()).unwrap(), "72.4 WPM, 97.1% accuracy, 54s — rstype");
+    assert!(fill_template(DEFAULT_SHARE_TEMPLATE, &stats()).unwrap().starts_with("My typing speed is 72.4 WPM, "));
+    assert_eq!(fill_template("", &stats()).unwrap(), "");
+}
+
+#[test]
+fn test_braces_are_escaped_by_doubling() {
+    assert_eq!(fill_template("{{wpm}} = {wpm} }} {{", &stats()).unwrap(), "{wpm} = 72.4 } {");
+    // A lone closing brace has nothing to close
+    assert_eq!(fill_template(":}", &stats()).unwrap(), ":}");
+}
+
+#[test]
+fn test_unknown_placeholder_is_an_error() {
+    let error = fill_template("{wpm} WPM, {speed}", &stats()).unwrap_err();
+    assert_eq!(error, TemplateError::UnknownPlaceholder { name: "speed".to_string(), known: TEMPLATE_PLACEHOLDERS });
+    assert!(error.to_string().contains("{speed}"), "{}", error);
+    assert!(error.to_string().contains("{text_id}"), "{}", error);
+
+    assert!(matches!(check_template("{wpm"), Err(TemplateError::UnclosedPlaceholder(_))));
+    assert!(check_template("{ wpm }").is_err());
+    assert!(check_template(DEFAULT_SHARE_TEMPLATE).is_ok());
+}
+
+#[test]
+fn test_tweet_url_is_encoded() {
+    let message = fill_template(DEFAULT_SHARE_TEMPLATE, &stats()).unwrap();
+    let url = default_share_targets()[0].url(&message).unwrap();
+
+    let text = url.strip_prefix("https://twitter.com/intent/tweet?text=").unwrap();
+    for c in [' ', '\n', '#', '"', '!', '@', ','] {
+        assert!(!text.contains(c), "{:?} in {}", c, text);
+    }
+    assert!(text.starts_with("My%20typing%20speed%20is%2072.4%20WPM%2C%2097.1%25%20accuracy%2C%2054s%21%0A"), "{}", text);
+    assert!(text.ends_with("%23TypingTest%20%23Rust"), "{}", text);
+    assert!(text.contains("%22https%3A%2F%2Fgithub.com%2FCyberDogFK%2Frstype%22"), "{}", text);
+}
+
+#[test]
+fn test_share_target_urls() {
+    let mastodon = ShareTarget::new("Mastodon", "https://mastodon.social/share?text={text}");
+    assert_eq!(mastodon.url("72 WPM #Rust").unwrap(), "https://mastodon.social/share?text=72%20WPM%20%23Rust");
+
+    let bluesky = ShareTarget::new("Bluesky", "https://bsky.app/intent/compose?text={text}");
+    assert_eq!(bluesky.url("a&b").unwrap(), "https://bsky.app/intent/compose?text=a%26b");
+}
+
+#[test]
+fn test_unknown_url_placeholder_is_an_error() {
+    let target = ShareTarget::new("Mastodon", "https://mastodon.social/share?text={wpm}");
+    let error = check_share_target(&target).unwrap_err();
+
+    assert!(matches!(&error, TemplateError::UnknownPlaceholder { name, .. } if name == "wpm"), "{:?}", error);
+    assert!(error.to_string().contains("{text}"), "{}", error);
+    assert!(check_share_target(&default_share_targets()[0]).is_ok());
+}
@@ -0,0 +1,27 @@
+use pancurses::Input;
+use rstype::app::App;
+use std::time::Duration;
+
+#[test]
+#[cfg(unix)]
+fn test_resume_is_noticed_once() {
+    rstype::suspend::install_resume_handler();
+    assert!(!rstype::suspend::take_resumed());
+
+    // SIGCONT on a running process only runs the handler
+    unsafe {
+        libc::raise(libc::SIGCONT);
+    }
+    assert!(rstype::suspend::take_resumed());
+    assert!(!rstype::suspend::take_resumed());
+}
+
+#[test]
+fn test_suspended_time_is_left_out() {
+    let mut app = App::from_prepared_text(("hello world".to_string(), "test".to_string()));
+    app.process_key(&Input::Character('h'));
+
+    // The clock is moved past the suspension, so the test hasn't run yet
+    app.exclude_suspended_time(Duration::from_secs(3600));
+    assert_eq!(app.elapsed(), Duration::ZERO);
+}
@@ -0,0 +1,19 @@
+use rstype::terminal;
+
+#[test]
+fn test_panic_hook_is_installed_once() {
+    terminal::install_panic_hook();
+    terminal::install_panic_hook();
+    assert!(terminal::is_panic_hook_installed());
+
+    // The hook runs without a curses session too
+    let result = std::panic::catch_unwind(|| panic!("test panic"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_restore_terminal_is_idempotent() {
+    // Nothing to end without a curses session, however often it is called
+    terminal::restore_terminal();
+    terminal::restore_terminal();
+}
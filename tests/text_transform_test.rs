@@ -0,0 +1,75 @@
+use rstype::text_transform;
+use rstype::AppError;
+
+fn prepared(text: &str) -> rstype::PreparedText {
+    (text.to_string(), "42".to_string())
+}
+
+#[test]
+fn test_strip_punctuation() {
+    let (text, text_id) = text_transform::strip_punctuation(prepared("Hello, world! How are you?")).unwrap();
+    assert_eq!(text, "Hello world How are you");
+    assert_eq!(text_id, "42:np");
+}
+
+#[test]
+fn test_strip_punctuation_apostrophes_and_hyphens() {
+    let (text, _) = text_transform::strip_punctuation(prepared("Don't stop a well-known - “quoted” song…")).unwrap();
+    assert_eq!(text, "Dont stop a well known quoted song");
+}
+
+#[test]
+fn test_strip_punctuation_keeps_other_letters() {
+    let (text, _) = text_transform::strip_punctuation(prepared("¿Qué tal? Über-schön!")).unwrap();
+    assert_eq!(text, "Qué tal Über schön");
+}
+
+#[test]
+fn test_strip_punctuation_of_punctuation_only_text() {
+    assert!(matches!(
+        text_transform::strip_punctuation(prepared("... !? --")),
+        Err(AppError::EmptyTextError(text_id)) if text_id == "42"
+    ));
+}
+
+#[test]
+fn test_lowercase() {
+    let (text, text_id) = text_transform::lowercase(prepared("Hello World, ÄÖÜ"));
+    assert_eq!(text, "hello world, äöü");
+    assert_eq!(text_id, "42:lc");
+}
+
+#[test]
+fn test_lowercase_changing_char_count() {
+    let (text, _) = text_transform::lowercase(prepared("İstanbul"));
+    assert_eq!(text, "i\u{307}stanbul");
+    assert_eq!(text.chars().count(), "İstanbul".chars().count() + 1);
+
+    // The longer text can still be typed through
+    let mut session = rstype::session::TypingSession::new(&text);
+    for c in text.chars() {
+        session.press(c);
+    }
+    assert!(session.is_complete());
+}
+
+#[test]
+fn test_transforms_compose() {
+    let prepared_text = text_transform::strip_punctuation(prepared("Don't PANIC!")).unwrap();
+    let (text, text_id) = text_transform::lowercase(prepared_text);
+    assert_eq!(text, "dont panic");
+    assert_eq!(text_id, "42:np:lc");
+}
+
+#[test]
+fn test_text_transforms() {
+    let transforms = text_transform::TextTransforms { no_punctuation: true, ..Default::default() };
+    assert_eq!(transforms.apply(prepared("Hello, world!")).unwrap(), ("Hello world".to_string(), "42:np".to_string()));
+    // The suffixes are added in the order --retake takes them off
+    let transforms = text_transform::TextTransforms { no_punctuation: true, lowercase: true, words: Some(3) };
+    assert_eq!(transforms.apply(prepared("Hello, world!")).unwrap(), ("hello world hello".to_string(), "42:np:lc#3w".to_string()));
+    assert_eq!(transforms.original_id("42:np:lc#3w"), Some("42"));
+    assert_eq!(transforms.original_id("42:np#3w"), None);
+    // Without a transform the text and its id are kept
+    assert_eq!(text_transform::TextTransforms::default().apply(prepared("Hello, world!")).unwrap(), prepared("Hello, world!"));
+}
@@ -0,0 +1,110 @@
+use rstype::app::Color;
+use rstype::theme::{self, ColorMode, Theme, ThemeError};
+
+#[test]
+fn test_default_theme_pairs() {
+    let pairs = Theme::default().pairs();
+    assert_eq!(pairs.len(), 7);
+    assert_eq!(pairs[0], (1, Color::Green, pancurses::COLOR_WHITE, pancurses::COLOR_GREEN));
+    assert_eq!(pairs[1], (2, Color::Red, pancurses::COLOR_WHITE, pancurses::COLOR_RED));
+    assert_eq!(pairs[6], (7, Color::Black, pancurses::COLOR_BLACK, pancurses::COLOR_WHITE));
+}
+
+#[test]
+fn test_builtin_themes() {
+    for name in theme::THEMES {
+        let pairs = Theme::builtin(name).unwrap().pairs();
+        // Every role gets its own pair
+        let numbers: Vec<i16> = pairs.iter().map(|(pair, ..)| *pair).collect();
+        assert_eq!(numbers, (1..=7).collect::<Vec<i16>>());
+    }
+    assert_eq!(Theme::builtin("default").unwrap(), Theme::default());
+    assert_eq!(Theme::builtin("neon"), Err(ThemeError::UnknownTheme("neon".to_string())));
+}
+
+#[test]
+fn test_override_theme_role() {
+    let mut theme = Theme::default();
+    theme.set_role("error", "yellow, 196").unwrap();
+    assert_eq!(theme.pairs()[1], (2, Color::Red, pancurses::COLOR_YELLOW, 196));
+
+    assert_eq!(theme.set_role("errors", "white,red"), Err(ThemeError::UnknownRole("errors".to_string())));
+    assert_eq!(theme.set_role("error", "white"), Err(ThemeError::UnknownColor("white".to_string())));
+    assert_eq!(theme.set_role("error", "white,pink"), Err(ThemeError::UnknownColor("pink".to_string())));
+}
+
+#[test]
+fn test_parse_color() {
+    assert_eq!(theme::parse_color("Red"), Ok(pancurses::COLOR_RED));
+    assert_eq!(theme::parse_color(" black "), Ok(pancurses::COLOR_BLACK));
+    assert_eq!(theme::parse_color("0"), Ok(0));
+    assert_eq!(theme::parse_color("255"), Ok(255));
+    assert_eq!(theme::parse_color("256"), Err(ThemeError::UnknownColor("256".to_string())));
+    assert_eq!(theme::parse_color("-1"), Ok(theme::DEFAULT_COLOR));
+    assert_eq!(theme::parse_color("Default"), Ok(theme::DEFAULT_COLOR));
+    assert_eq!(theme::parse_color("-2"), Err(ThemeError::UnknownColor("-2".to_string())));
+}
+
+#[test]
+fn test_colorblind_theme_avoids_red_and_green() {
+    let pairs = Theme::builtin("default").unwrap().colorblind().pairs();
+    for (_, _, foreground, background) in pairs {
+        assert!(![pancurses::COLOR_RED, pancurses::COLOR_GREEN].contains(&foreground));
+        assert!(![pancurses::COLOR_RED, pancurses::COLOR_GREEN].contains(&background));
+    }
+}
+
+#[test]
+fn test_color_attributes() {
+    let theme = Theme::default();
+    let full = theme::color_attributes(&theme, ColorMode::Full);
+    assert_eq!(full.len(), 7);
+    assert_eq!(full[&Color::Red], pancurses::chtype::from(pancurses::ColorPair(2)));
+
+    // Mistakes are underlined so they don't depend on the color alone
+    let colorblind = theme::color_attributes(&theme.colorblind(), ColorMode::Colorblind);
+    assert_eq!(colorblind[&Color::Red] & pancurses::A_COLOR, pancurses::chtype::from(pancurses::ColorPair(2)));
+    assert_ne!(colorblind[&Color::Red] & pancurses::A_UNDERLINE, 0);
+
+    // Without colors, every role is drawn with attributes only
+    let monochrome = theme::color_attributes(&theme, ColorMode::Monochrome);
+    assert_eq!(monochrome.len(), 7);
+    assert!(monochrome.values().all(|attribute| attribute & pancurses::A_COLOR == 0));
+    assert_ne!(monochrome[&Color::Red], monochrome[&Color::Green]);
+}
+
+#[test]
+fn test_basic_color() {
+    assert_eq!(theme::basic_color(pancurses::COLOR_CYAN), pancurses::COLOR_CYAN);
+    assert_eq!(theme::basic_color(9), pancurses::COLOR_RED);
+    assert_eq!(theme::basic_color(16), pancurses::COLOR_BLACK);
+    assert_eq!(theme::basic_color(160), pancurses::COLOR_RED);
+    assert_eq!(theme::basic_color(33), pancurses::COLOR_BLUE);
+    assert_eq!(theme::basic_color(37), pancurses::COLOR_CYAN);
+    assert_eq!(theme::basic_color(231), pancurses::COLOR_WHITE);
+    assert_eq!(theme::basic_color(235), pancurses::COLOR_BLACK);
+    assert_eq!(theme::basic_color(250), pancurses::COLOR_WHITE);
+}
+
+#[test]
+fn test_theme_for_terminal() {
+    let solarized = Theme::builtin("solarized").unwrap();
+    // Terminals with 256 colors and default colors show the theme as it is
+    assert_eq!(solarized.for_terminal(256, true), solarized);
+
+    let pairs = solarized.for_terminal(8, false).pairs();
+    assert_eq!(pairs[1], (2, Color::Red, pancurses::COLOR_RED, pancurses::COLOR_BLACK));
+    assert_eq!(pairs[6], (7, Color::Black, pancurses::COLOR_BLACK, pancurses::COLOR_WHITE));
+    assert!(pairs.iter().all(|(_, _, foreground, background)| (0..8).contains(foreground) && (0..8).contains(background)));
+
+    // Default colors are kept on 8 color terminals that support them
+    let pairs = Theme::builtin("transparent").unwrap().for_terminal(8, true).pairs();
+    assert_eq!(pairs[0], (1, Color::Green, pancurses::COLOR_GREEN, theme::DEFAULT_COLOR));
+}
+
+#[test]
+fn test_override_theme_role_with_default_color() {
+    let mut theme = Theme::default();
+    theme.set_role("error", "196,default").unwrap();
+    assert_eq!(theme.pairs()[1], (2, Color::Red, 196, theme::DEFAULT_COLOR));
+}
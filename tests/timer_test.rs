@@ -0,0 +1,18 @@
+use rstype::timer;
+use std::time::Duration;
+
+#[test]
+fn test_format_elapsed() {
+    assert_eq!(timer::format_elapsed(Duration::ZERO), "00:00");
+    assert_eq!(timer::format_elapsed(Duration::from_millis(59_999)), "00:59");
+    assert_eq!(timer::format_elapsed(Duration::from_secs(61)), "01:01");
+    assert_eq!(timer::format_elapsed(Duration::from_secs(100 * 60 + 5)), "100:05");
+}
+
+#[test]
+fn test_format_total() {
+    assert_eq!(timer::format_total(0.0), "0s");
+    assert_eq!(timer::format_total(59.9), "59s");
+    assert_eq!(timer::format_total(12.0 * 60.0 + 30.0), "12m");
+    assert_eq!(timer::format_total(6.0 * 3600.0 + 12.0 * 60.0), "6h 12m");
+}
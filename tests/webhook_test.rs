@@ -0,0 +1,63 @@
+use rstype::session::SessionStats;
+use rstype::webhook::{post_stats, HttpTransport, WebhookError};
+
+fn stats() -> SessionStats {
+    SessionStats { text_id: "42".to_string(), wpm: 55.5, completed: true, ..SessionStats::default() }
+}
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_stats_are_posted_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/results", listener.local_addr().unwrap());
+    let server = std::thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = vec![];
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push(line.trim_end().to_lowercase());
+        }
+        let length: usize = head.iter()
+            .find_map(|line| line.strip_prefix("content-length: "))
+            .unwrap()
+            .parse()
+            .unwrap();
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        reader.get_mut().write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n").unwrap();
+        (head, String::from_utf8(body).unwrap())
+    });
+
+    post_stats(&mut HttpTransport, &url, &stats()).unwrap();
+
+    let (head, body) = server.join().unwrap();
+    assert_eq!(head[0], "post /results http/1.1");
+    assert!(head.contains(&"content-type: application/json".to_string()), "{:?}", head);
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["text_id"], "42");
+    assert_eq!(json["wpm"], 55.5);
+}
+
+#[cfg(feature = "webhook")]
+#[test]
+fn test_unreachable_webhook_is_an_error() {
+    // Nothing listens on the port once the listener is dropped
+    let address = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    let result = post_stats(&mut HttpTransport, &format!("http://{}/", address), &stats());
+    assert!(matches!(result, Err(WebhookError::RequestError(_))), "{:?}", result);
+}
+
+#[cfg(not(feature = "webhook"))]
+#[test]
+fn test_webhook_needs_the_feature() {
+    let result = post_stats(&mut HttpTransport, "http://localhost/", &stats());
+    assert!(matches!(result, Err(WebhookError::Unsupported)), "{:?}", result);
+}